@@ -1,5 +1,8 @@
 use crate::core::app_controller::AppController;
 use crate::core::app_controller::Theme;
+use crate::core::bookmarks::Bookmarks;
+use crate::core::cell_display::HierarchyLevels;
+use crate::core::instancer::InstanceLimits;
 use crate::graphics::renderer::Renderer;
 
 use bevy_ecs::world::World;
@@ -13,7 +16,11 @@ use glutin::surface::SurfaceAttributesBuilder;
 use glutin::surface::WindowSurface;
 use glutin_winit::DisplayBuilder;
 use raw_window_handle::HasRawWindowHandle;
+use std::fs;
+use std::io;
 use std::num::NonZeroU32;
+use std::path::Path;
+use std::path::PathBuf;
 use std::time::Duration;
 use std::time::Instant;
 use winit::dpi::PhysicalPosition;
@@ -25,11 +32,18 @@ use winit::window::WindowBuilder;
 
 const INITIAL_WINDOW_WIDTH: u32 = 800;
 const INITIAL_WINDOW_HEIGHT: u32 = 600;
+const WINDOW_TITLE: &str = "Layout Viewer";
 
-pub fn spawn_window(world: World, theme: Theme) -> anyhow::Result<()> {
+pub fn spawn_window(
+    world: World,
+    theme: Theme,
+    instance_limits: InstanceLimits,
+    bookmarks_path: PathBuf,
+    selection_path: PathBuf,
+) -> anyhow::Result<()> {
     let event_loop = EventLoop::new()?;
     let window_builder = WindowBuilder::new()
-        .with_title("Layout Viewer")
+        .with_title(WINDOW_TITLE)
         .with_inner_size(winit::dpi::LogicalSize::new(
             INITIAL_WINDOW_WIDTH,
             INITIAL_WINDOW_HEIGHT,
@@ -101,10 +115,18 @@ pub fn spawn_window(world: World, theme: Theme) -> anyhow::Result<()> {
     let mut controller = AppController::new(renderer, window_size.width, window_size.height);
 
     controller.set_world(world);
+    controller.set_instance_limits(instance_limits);
     controller.apply_theme(&theme);
     controller.resize(window_size.width, window_size.height);
+    controller.set_bookmarks(load_bookmarks(&bookmarks_path));
+    for (index, name) in controller.bookmarks().names().iter().enumerate().take(9) {
+        println!("Bookmark {}: {}", index + 1, name);
+    }
 
     let mut current_cursor_pos: Option<PhysicalPosition<f64>> = None;
+    let mut shift_pressed = false;
+    let mut alt_pressed = false;
+    let mut window_title = WINDOW_TITLE.to_string();
     let mut next_tick = Instant::now();
     let tick_interval = Duration::from_millis(16);
 
@@ -120,6 +142,16 @@ pub fn spawn_window(world: World, theme: Theme) -> anyhow::Result<()> {
                     if controller.tick() {
                         surface.swap_buffers(&context).unwrap();
                     }
+
+                    // The title doubles as the coordinate readout.
+                    let title = match controller.cursor_readout() {
+                        Some(readout) => format!("{} - {}", WINDOW_TITLE, readout),
+                        None => WINDOW_TITLE.to_string(),
+                    };
+                    if title != window_title {
+                        window.set_title(&title);
+                        window_title = title;
+                    }
                     next_tick = now + tick_interval;
                 }
             }
@@ -136,19 +168,98 @@ pub fn spawn_window(world: World, theme: Theme) -> anyhow::Result<()> {
                             controller.destroy();
                             window_target.exit();
                         }
+                        if code == KeyCode::KeyE && event.state.is_pressed() {
+                            export_selection(&mut controller, &selection_path);
+                        }
+                        if code == KeyCode::KeyI && event.state.is_pressed() {
+                            print_inspector(&mut controller);
+                        }
+                        if code == KeyCode::BracketLeft && event.state.is_pressed() {
+                            step_hierarchy_levels(&mut controller, shift_pressed, false);
+                        }
+                        if code == KeyCode::BracketRight && event.state.is_pressed() {
+                            step_hierarchy_levels(&mut controller, shift_pressed, true);
+                        }
+                        if code == KeyCode::KeyR && event.state.is_pressed() {
+                            let enabled = !controller.ruler_mode();
+                            controller.set_ruler_mode(enabled);
+                            println!("Ruler mode {}", if enabled { "on" } else { "off" });
+                        }
+                        if code == KeyCode::KeyS && event.state.is_pressed() {
+                            let enabled = !controller.ruler_snapping();
+                            controller.set_ruler_snapping(enabled);
+                            println!("Ruler snapping {}", if enabled { "on" } else { "off" });
+                        }
+                        if code == KeyCode::KeyG && event.state.is_pressed() {
+                            let visible = !controller.grid_visible();
+                            controller.set_grid_visible(visible);
+                        }
+                        if code == KeyCode::KeyM && event.state.is_pressed() {
+                            let visible = !controller.minimap_visible();
+                            controller.set_minimap_visible(visible);
+                        }
+                        if code == KeyCode::KeyV && event.state.is_pressed() {
+                            let count = if controller.pane_count() > 1 { 1 } else { 2 };
+                            controller.set_pane_count(count);
+                            println!("Split view {}", if count > 1 { "on" } else { "off" });
+                        }
+                        if code == KeyCode::KeyL && event.state.is_pressed() {
+                            let linked = !controller.cameras_linked();
+                            controller.set_cameras_linked(linked);
+                            println!("Linked cameras {}", if linked { "on" } else { "off" });
+                        }
+                        if code == KeyCode::KeyC && event.state.is_pressed() {
+                            controller.clear_rulers();
+                        }
+                        if code == KeyCode::ArrowLeft && alt_pressed && event.state.is_pressed() {
+                            controller.go_back();
+                        }
+                        if code == KeyCode::ArrowRight && alt_pressed && event.state.is_pressed() {
+                            controller.go_forward();
+                        }
+                        if code == KeyCode::KeyB && event.state.is_pressed() {
+                            add_bookmark(&mut controller, &bookmarks_path);
+                        }
+                        if let Some(index) = digit(code).filter(|_| event.state.is_pressed()) {
+                            let name = controller.bookmarks().names().get(index).cloned();
+                            if let Some(name) = name {
+                                controller.go_to_bookmark(&name);
+                                println!("Bookmark {}: {}", index + 1, name);
+                            }
+                        }
                     }
                 }
+                WindowEvent::ModifiersChanged(modifiers) => {
+                    shift_pressed = modifiers.state().shift_key();
+                    alt_pressed = modifiers.state().alt_key();
+                }
                 WindowEvent::MouseInput { state, button, .. } => {
                     use winit::event::MouseButton;
+                    if button == MouseButton::Back && state.is_pressed() {
+                        controller.go_back();
+                    }
+                    if button == MouseButton::Forward && state.is_pressed() {
+                        controller.go_forward();
+                    }
                     if button == MouseButton::Left {
                         match state {
                             winit::event::ElementState::Pressed => {
                                 if let Some(pos) = current_cursor_pos {
-                                    controller.handle_mouse_press(pos.x as u32, pos.y as u32);
+                                    controller.handle_mouse_press(
+                                        pos.x as u32,
+                                        pos.y as u32,
+                                        shift_pressed,
+                                    );
                                 }
                             }
                             winit::event::ElementState::Released => {
+                                let ruler_count = controller.rulers().len();
                                 controller.handle_mouse_release();
+                                let rulers = controller.rulers();
+                                if rulers.len() > ruler_count {
+                                    let units = controller.units();
+                                    println!("{}", rulers.last().unwrap().label(&units));
+                                }
                             }
                         }
                     }
@@ -189,3 +300,109 @@ pub fn spawn_window(world: World, theme: Theme) -> anyhow::Result<()> {
 
     Ok(())
 }
+
+/// Prints the inspector for the hovered shape, or for every selected shape if
+/// nothing is hovered.
+fn print_inspector(controller: &mut AppController) {
+    let shapes = match controller.hovered_shape() {
+        Some(shape) => vec![shape],
+        None => controller.selected_shapes(),
+    };
+    let units = controller.units();
+    for shape in shapes {
+        let Some(report) = controller.inspect(shape) else {
+            continue;
+        };
+        for (label, value) in report.rows(&units) {
+            println!("{:>10}: {}", label, value);
+        }
+        println!();
+    }
+}
+
+/// Moves the deepest drawn hierarchy level up or down by one, or the
+/// shallowest level if `min` is true, then prints the new range.
+fn step_hierarchy_levels(controller: &mut AppController, min: bool, deeper: bool) {
+    let deepest = controller.deepest_level();
+    let levels = controller.hierarchy_levels();
+    let (mut lo, mut hi) = (levels.min.min(deepest), levels.max.min(deepest));
+    match (min, deeper) {
+        (true, true) => lo = (lo + 1).min(deepest),
+        (true, false) => lo = lo.saturating_sub(1),
+        (false, true) => hi = (hi + 1).min(deepest),
+        (false, false) => hi = hi.saturating_sub(1),
+    }
+    if min {
+        hi = hi.max(lo);
+    } else {
+        lo = lo.min(hi);
+    }
+
+    // Reaching the deepest level draws everything, including instances added
+    // by a later change of root.
+    if hi == deepest {
+        hi = usize::MAX;
+    }
+    controller.set_hierarchy_levels(HierarchyLevels::new(lo, hi));
+    println!(
+        "Drawing levels {} to {} of {}",
+        lo,
+        hi.min(deepest),
+        deepest
+    );
+}
+
+/// Reads the bookmarks sidecar file. A missing file means no bookmarks.
+fn load_bookmarks(path: &Path) -> Bookmarks {
+    match fs::read_to_string(path) {
+        Ok(text) => Bookmarks::from_text(&text),
+        Err(error) => {
+            if error.kind() != io::ErrorKind::NotFound {
+                log::warn!("Unable to read {}: {}", path.display(), error);
+            }
+            Bookmarks::default()
+        }
+    }
+}
+
+/// Bookmarks the current view under the next free "View N" name, then
+/// saves every bookmark to the sidecar file. Bookmarks can be renamed by
+/// editing that file.
+fn add_bookmark(controller: &mut AppController, path: &Path) {
+    let names = controller.bookmarks().names();
+    let name = (names.len() + 1..)
+        .map(|number| format!("View {}", number))
+        .find(|name| !names.contains(name))
+        .unwrap();
+    controller.add_bookmark(&name);
+    let number = controller.bookmarks().names().len();
+    match fs::write(path, controller.bookmarks().to_text()) {
+        Ok(()) => println!("Bookmark {}: {} saved to {}", number, name, path.display()),
+        Err(error) => log::warn!("Unable to write {}: {}", path.display(), error),
+    }
+}
+
+/// Writes the selected shapes to a CSV file, replacing any earlier export.
+fn export_selection(controller: &mut AppController, path: &Path) {
+    match fs::write(path, controller.export_selection()) {
+        Ok(()) => println!("Selection saved to {}", path.display()),
+        Err(error) => log::warn!("Unable to write {}: {}", path.display(), error),
+    }
+}
+
+/// Maps the keys 1 to 9 to bookmark indices.
+fn digit(code: winit::keyboard::KeyCode) -> Option<usize> {
+    use winit::keyboard::KeyCode;
+    let digits = [
+        KeyCode::Digit1,
+        KeyCode::Digit2,
+        KeyCode::Digit3,
+        KeyCode::Digit4,
+        KeyCode::Digit5,
+        KeyCode::Digit6,
+        KeyCode::Digit7,
+        KeyCode::Digit8,
+        KeyCode::Digit9,
+    ];
+    digits.iter().position(|digit| *digit == code)
+}
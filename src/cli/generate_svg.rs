@@ -1,16 +1,39 @@
 use bevy_ecs::query::QueryState;
 use bevy_ecs::world::World;
+use geo::AffineOps;
 use svg::node::element::Group;
+use svg::node::element::Line;
 use svg::node::element::Path;
+use svg::node::element::Rectangle;
+use svg::node::element::Text;
+use svg::node::Text as TextNode;
 use svg::Document;
 
+use crate::core::cell_display::abstract_outlines;
+use crate::core::cell_display::is_concealed;
 use crate::core::components::Layer;
+use crate::core::components::ShapeDefinition;
 use crate::core::components::ShapeInstance;
+use crate::core::components::ShapeType;
+use crate::core::components::TextDefinition;
+use crate::core::components::TextInstance;
+use crate::core::ruler::Rulers;
+use crate::core::units::LibraryUnits;
 use crate::graphics::bounds::BoundingBox;
+use crate::graphics::text::HorizontalAlign;
+use crate::graphics::text::VerticalAlign;
 
 const PRECISION: f64 = 0.0001;
 
-pub fn generate_svg(world: &mut World) -> String {
+const RULER_COLOR: &str = "#00ccff";
+
+/// Writes every visible layer and label as SVG. Coordinates are in database
+/// units unless `in_microns` is set.
+///
+/// Shapes and labels in hidden or abstract cells are left out, and abstract
+/// cells are drawn as outlined boxes with their names. Rulers are drawn on
+/// top, labelled with their measurements.
+pub fn generate_svg(world: &mut World, in_microns: bool) -> String {
     // Get the overall bounding box
     let mut layer_query: QueryState<&Layer> = QueryState::new(world);
     let mut bounds = BoundingBox::new();
@@ -21,20 +44,40 @@ pub fn generate_svg(world: &mut World) -> String {
     // Add padding
     let padding = (bounds.width() + bounds.height()) * 0.05;
     let padded_bounds = bounds.inflate(padding);
+    let units = world
+        .get_resource::<LibraryUnits>()
+        .copied()
+        .unwrap_or_default();
+    let scale = if in_microns {
+        units.microns_per_db_unit()
+    } else {
+        1.0
+    };
     let view_box = (
-        padded_bounds.min_y,
-        padded_bounds.min_x,
-        padded_bounds.height(),
-        padded_bounds.width(),
+        round_to_precision(padded_bounds.min_y * scale),
+        round_to_precision(padded_bounds.min_x * scale),
+        round_to_precision(padded_bounds.height() * scale),
+        round_to_precision(padded_bounds.width() * scale),
     );
 
+    // Nodes are drawn as outlines rather than filled
+    let stroke_width = round_to_precision((bounds.width() + bounds.height()) * 0.001);
+
     // Create SVG document
-    let mut doc = Document::new()
+    let doc = Document::new()
         .set("viewBox", view_box)
         .set("style", "background-color: #2D2D2D");
 
-    // Add each layer as a group
-    for layer in layer_query.iter(world) {
+    // Shapes and labels are written in database units and scaled as a whole
+    let mut content = Group::new();
+    if scale != 1.0 {
+        content = content.set("transform", format!("scale({})", scale));
+    }
+
+    // Add each layer as a group, in drawing order
+    let mut layers: Vec<&Layer> = layer_query.iter(world).collect();
+    layers.sort_by_key(|layer| layer.key);
+    for layer in layers {
         // Convert the layer's color from [0,1] to hex string
         let color = format!(
             "#{:02x}{:02x}{:02x}",
@@ -43,21 +86,149 @@ pub fn generate_svg(world: &mut World) -> String {
             (layer.color.z * 255.0) as u8
         );
 
-        let mut group = Group::new().set("fill", color).set("opacity", 0.5);
+        // XML ids cannot contain slashes, so "L/D" becomes "layer_L_D".
+        let id = format!("layer_{}_{}", layer.key.layer, layer.key.datatype);
+        let mut group = Group::new()
+            .set("id", id)
+            .set("fill", color.clone())
+            .set("opacity", 0.5);
 
         for shape_instance in &layer.shape_instances {
             let shape_instance = world
                 .get::<ShapeInstance>(*shape_instance)
                 .expect("ShapeInstance not found");
-            let path_data = polygon_to_path_data(&shape_instance.world_polygon);
-            let path = Path::new().set("d", path_data).set("stroke", "none");
+            if is_concealed(world, shape_instance.cell_instance) {
+                continue;
+            }
+            let shape_def = world
+                .get::<ShapeDefinition>(shape_instance.shape_definition)
+                .expect("ShapeDefinition not found");
+            let path_data = polygon_to_path_data(&shape_instance.world_polygon(world));
+            let path = match shape_def.shape_type {
+                ShapeType::Node(_) => Path::new()
+                    .set("d", path_data)
+                    .set("fill", "none")
+                    .set("stroke", color.clone())
+                    .set("stroke-width", stroke_width),
+                _ => Path::new().set("d", path_data).set("stroke", "none"),
+            };
             group = group.add(path);
         }
 
-        doc = doc.add(group);
+        content = content.add(group);
     }
 
-    doc.to_string()
+    // Add text elements as labels on top of all layers
+    let font_size = round_to_precision((bounds.width() + bounds.height()) * 0.005);
+    let mut labels = Group::new()
+        .set("fill", "#ffffff")
+        .set("font-family", "monospace")
+        .set("font-size", font_size);
+
+    let mut text_query: QueryState<&TextInstance> = QueryState::new(world);
+    for text_instance in text_query.iter(world) {
+        let layer = world
+            .get::<Layer>(text_instance.layer)
+            .expect("Layer not found");
+        if !layer.visible || is_concealed(world, text_instance.cell_instance) {
+            continue;
+        }
+        let text_def = world
+            .get::<TextDefinition>(text_instance.text_definition)
+            .expect("TextDefinition not found");
+        let anchor = geo::Point::new(0.0, 0.0).affine_transform(&text_instance.world_transform);
+        let text_anchor = match text_def.presentation.horizontal {
+            HorizontalAlign::Left => "start",
+            HorizontalAlign::Center => "middle",
+            HorizontalAlign::Right => "end",
+        };
+        let baseline = match text_def.presentation.vertical {
+            VerticalAlign::Top => "hanging",
+            VerticalAlign::Middle => "middle",
+            VerticalAlign::Bottom => "auto",
+        };
+        let text = Text::new()
+            .set("x", round_to_precision(anchor.y()))
+            .set("y", round_to_precision(anchor.x()))
+            .set("text-anchor", text_anchor)
+            .set("dominant-baseline", baseline)
+            .add(TextNode::new(escape_xml(&text_def.string)));
+        labels = labels.add(text);
+    }
+
+    content = content.add(labels);
+
+    let mut abstracts = Group::new()
+        .set("fill", "none")
+        .set("stroke", "#ffffff")
+        .set("stroke-width", stroke_width);
+    let mut names = Group::new()
+        .set("fill", "#ffffff")
+        .set("font-family", "monospace")
+        .set("font-size", font_size)
+        .set("text-anchor", "middle")
+        .set("dominant-baseline", "middle");
+    let outlines = abstract_outlines(world);
+    for outline in &outlines {
+        let bounds = outline.world_bounds;
+        if bounds.is_empty() {
+            continue;
+        }
+        let rect = Rectangle::new()
+            .set("x", round_to_precision(bounds.min_y))
+            .set("y", round_to_precision(bounds.min_x))
+            .set("width", round_to_precision(bounds.height()))
+            .set("height", round_to_precision(bounds.width()));
+        abstracts = abstracts.add(rect);
+        let name = Text::new()
+            .set("x", round_to_precision((bounds.min_y + bounds.max_y) / 2.0))
+            .set("y", round_to_precision((bounds.min_x + bounds.max_x) / 2.0))
+            .add(TextNode::new(escape_xml(&outline.name)));
+        names = names.add(name);
+    }
+
+    if !outlines.is_empty() {
+        content = content.add(abstracts).add(names);
+    }
+
+    let rulers = world
+        .get_resource::<Rulers>()
+        .map_or_else(Vec::new, |rulers| rulers.0.clone());
+    if !rulers.is_empty() {
+        let mut lines = Group::new()
+            .set("id", "rulers")
+            .set("stroke", RULER_COLOR)
+            .set("stroke-width", stroke_width);
+        let mut measurements = Group::new()
+            .set("fill", RULER_COLOR)
+            .set("font-family", "monospace")
+            .set("font-size", font_size)
+            .set("text-anchor", "middle")
+            .set("dominant-baseline", "auto");
+        for ruler in &rulers {
+            let line = Line::new()
+                .set("x1", round_to_precision(ruler.start.y))
+                .set("y1", round_to_precision(ruler.start.x))
+                .set("x2", round_to_precision(ruler.end.y))
+                .set("y2", round_to_precision(ruler.end.x));
+            lines = lines.add(line);
+            let midpoint = ruler.midpoint();
+            let label = Text::new()
+                .set("x", round_to_precision(midpoint.y))
+                .set("y", round_to_precision(midpoint.x))
+                .add(TextNode::new(escape_xml(&ruler.label(&units))));
+            measurements = measurements.add(label);
+        }
+        content = content.add(lines).add(measurements);
+    }
+
+    doc.add(content).to_string()
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
 }
 
 fn polygon_to_path_data(polygon: &geo::Polygon<f64>) -> String {
@@ -6,9 +6,19 @@ use crate::cli::app_window::spawn_window;
 use crate::cli::generate_svg::generate_svg;
 use crate::cli::wgpu_window::spawn_wgpu_window;
 use crate::core::app_controller::Theme;
+use crate::core::cell_display::set_cell_display;
+use crate::core::components::CellDefinition;
+use crate::core::components::CellDisplay;
+use crate::core::instancer::InstanceLimits;
 use crate::core::instancer::Instancer;
+use crate::core::instancer::DEFAULT_MAX_DEPTH;
+use crate::core::instancer::DEFAULT_MAX_INSTANCES;
 use crate::core::loader::Loader;
 use crate::core::root_finder::RootFinder;
+use crate::core::ruler::Ruler;
+use crate::core::ruler::Rulers;
+use crate::core::units::LibraryUnits;
+use crate::graphics::vectors::Point2d;
 
 use anyhow::anyhow;
 use anyhow::Result;
@@ -39,6 +49,70 @@ pub struct Args {
     /// Use light theme instead of dark theme
     #[arg(long)]
     pub light: bool,
+
+    /// Write SVG coordinates in microns instead of database units
+    #[arg(long)]
+    pub microns: bool,
+
+    /// Name of the cell to display; defaults to the top-level cell with the
+    /// most descendants
+    #[arg(long, value_name = "NAME")]
+    pub cell: Option<String>,
+
+    /// Name of a cell to leave out, along with everything inside it; may be
+    /// repeated
+    #[arg(long, value_name = "NAME")]
+    pub hide_cell: Vec<String>,
+
+    /// Name of a cell to draw as an outlined box with its name instead of its
+    /// contents; may be repeated
+    #[arg(long, value_name = "NAME")]
+    pub abstract_cell: Vec<String>,
+
+    /// Ruler to draw in the SVG and the window, given as X0,Y0,X1,Y1 in
+    /// microns; may be repeated
+    #[arg(
+        long,
+        value_name = "X0,Y0,X1,Y1",
+        value_parser = parse_ruler,
+        allow_hyphen_values = true
+    )]
+    pub ruler: Vec<[f64; 4]>,
+
+    /// Number of nested cell references to follow below the displayed cell
+    #[arg(long, value_name = "N", default_value_t = DEFAULT_MAX_DEPTH)]
+    pub max_depth: usize,
+
+    /// Number of cell instances to create before skipping the remaining
+    /// cell references
+    #[arg(long, value_name = "N", default_value_t = DEFAULT_MAX_INSTANCES)]
+    pub max_instances: usize,
+}
+
+fn parse_ruler(value: &str) -> Result<[f64; 4], String> {
+    let coords: Vec<f64> = value
+        .split(',')
+        .map(|coord| coord.trim().parse::<f64>())
+        .collect::<Result<_, _>>()
+        .map_err(|error| error.to_string())?;
+    coords
+        .try_into()
+        .map_err(|_| "expected four comma-separated numbers".to_string())
+}
+
+/// Bookmarks are kept next to the GDS file, as in `chip.gds.bookmarks`.
+fn bookmarks_path(input: &Path) -> PathBuf {
+    let mut path = input.as_os_str().to_owned();
+    path.push(".bookmarks");
+    PathBuf::from(path)
+}
+
+/// Selection exports are kept next to the GDS file, as in
+/// `chip.gds.selection.csv`.
+fn selection_path(input: &Path) -> PathBuf {
+    let mut path = input.as_os_str().to_owned();
+    path.push(".selection.csv");
+    PathBuf::from(path)
 }
 
 fn verify_file_extension(path: &Path, expected: &str) -> Result<()> {
@@ -72,32 +146,95 @@ pub fn run_cli() -> Result<()> {
     // Read and process the GDSII file
     let file_content = fs::read(&args.input)?;
 
+    let instance_limits = InstanceLimits {
+        max_depth: args.max_depth,
+        max_instances: args.max_instances,
+    };
+
     let mut world = pollster::block_on(async {
         let loader = Loader::new(&file_content);
         let mut world = None;
-        for mut progress in loader {
+        for progress in loader {
+            let mut progress = progress?;
             print!(".");
             world = progress.take_world();
         }
         let mut world = world.expect("World was not yielded");
         log::info!("Done with loading.");
 
+        if let Some(units) = world.get_resource::<LibraryUnits>() {
+            log::info!("Database unit is {} µm.", units.microns_per_db_unit());
+        }
+
         let mut root_finder = RootFinder::new(&mut world);
         let roots = root_finder.find_roots(&world);
 
         log::info!("Found {} roots.", roots.len());
 
+        let root = match args.cell {
+            Some(ref name) => root_finder.find_cell(&world, name).ok_or_else(|| {
+                let names: Vec<_> = roots
+                    .iter()
+                    .map(|root| CellDefinition::name_of(&world, *root))
+                    .collect();
+                anyhow!(
+                    "Cell '{}' not found; top-level cells are: {}",
+                    name,
+                    names.join(", ")
+                )
+            })?,
+            None => root_finder
+                .find_default_root(&world)
+                .ok_or_else(|| anyhow!("No top-level cell found"))?,
+        };
+
         let mut instancer = Instancer::new(&mut world);
-        instancer.select_root(&mut world, roots[0]);
+        instancer.set_limits(instance_limits);
+        instancer.select_root(&mut world, root);
+
+        let skipped = instancer.skipped_references().len();
+        if skipped > 0 {
+            log::warn!("Skipped {} cell references.", skipped);
+        }
 
         log::info!("Done with instantiation.");
 
-        world
-    });
+        let displays = args
+            .hide_cell
+            .iter()
+            .map(|name| (name, CellDisplay::Hidden))
+            .chain(
+                args.abstract_cell
+                    .iter()
+                    .map(|name| (name, CellDisplay::Abstract)),
+            );
+        for (name, display) in displays {
+            let cell = root_finder
+                .find_cell(&world, name)
+                .ok_or_else(|| anyhow!("Cell '{}' not found", name))?;
+            set_cell_display(&mut world, cell, display);
+        }
+
+        let units = world
+            .get_resource::<LibraryUnits>()
+            .copied()
+            .unwrap_or_default();
+        let rulers = args
+            .ruler
+            .iter()
+            .map(|coords| {
+                let [x0, y0, x1, y1] = coords.map(|coord| units.from_microns(coord));
+                Ruler::new(Point2d::new(x0, y0), Point2d::new(x1, y1))
+            })
+            .collect();
+        world.insert_resource(Rulers(rulers));
+
+        Ok::<_, anyhow::Error>(world)
+    })?;
 
     // Generate and save SVG if output path is provided
     if let Some(ref output_path) = args.output {
-        let svg_content = generate_svg(&mut world);
+        let svg_content = generate_svg(&mut world, args.microns);
 
         fs::write(output_path, svg_content)?;
         println!("SVG file written to: {}", output_path.display());
@@ -110,7 +247,13 @@ pub fn run_cli() -> Result<()> {
     if args.wgpu {
         spawn_wgpu_window(world, theme)?;
     } else if args.gl {
-        spawn_window(world, theme)?;
+        spawn_window(
+            world,
+            theme,
+            instance_limits,
+            bookmarks_path(&args.input),
+            selection_path(&args.input),
+        )?;
     }
 
     Ok(())
@@ -1,14 +1,17 @@
 use anyhow::anyhow;
 use anyhow::Result;
+use bevy_ecs::change_detection::DetectChanges;
+use bevy_ecs::change_detection::Tick;
+use bevy_ecs::entity::Entity;
 use bevy_ecs::world::World;
 use bytemuck::Pod;
 use bytemuck::Zeroable;
-use geo::Contains;
 use geo::TriangulateEarcut;
 use rstar::RTree;
-use rstar::RTreeObject;
+use std::collections::HashMap;
 use std::num::NonZeroU32;
 use std::num::NonZeroU64;
+use std::ops::Range;
 use std::time::Duration;
 use std::time::Instant;
 use winit::dpi::PhysicalPosition;
@@ -21,7 +24,11 @@ use winit::window::WindowBuilder;
 
 use crate::core::app_controller::Theme;
 use crate::core::components::Layer;
+use crate::core::components::LayerKey;
+use crate::core::components::LayerMesh;
 use crate::core::components::ShapeInstance;
+use crate::core::components::OVERLAY_RENDER_ORDER;
+use crate::core::rtree::build_rtree;
 use crate::core::rtree::RTreeItem;
 use crate::graphics::bounds::BoundingBox;
 use crate::graphics::camera::Camera;
@@ -32,7 +39,23 @@ use crate::graphics::mesh::Mesh;
 const INITIAL_WINDOW_WIDTH: u32 = 800;
 const INITIAL_WINDOW_HEIGHT: u32 = 600;
 
-const MAX_DRAWS_PER_FRAME: usize = 4096;
+// uniform buffer 初始可容纳的绘制次数，不够时按需扩容。
+const INITIAL_DRAW_CAPACITY: usize = 4096;
+
+// 非实例化的几何体按单位变换绘制一次。
+const IDENTITY_INSTANCE: [f32; 6] = [1.0, 0.0, 0.0, 0.0, 1.0, 0.0];
+
+/// GPU buffers for one Geometry, kept until the Geometry changes.
+struct GeometryBuffers {
+    changed: Tick,
+    vertex_buffer: wgpu::Buffer,
+    index_buffer: wgpu::Buffer,
+    instance_buffer: wgpu::Buffer,
+    index_count: u32,
+
+    /// Range of the instance buffer that each instance group occupies.
+    groups: Vec<Range<u32>>,
+}
 
 fn apply_theme_to_world(world: &mut World, theme: Theme) {
     let mut non_empty_layers = 0usize;
@@ -56,21 +79,30 @@ fn apply_theme_to_world(world: &mut World, theme: Theme) {
     // 先收集要改的 mesh，避免同时可变/不可变借用 world。
     let mut layer_meshes: Vec<(bevy_ecs::entity::Entity, bool)> = Vec::new();
     for layer in world.query::<&Layer>().iter(world) {
-        layer_meshes.push((layer.mesh, layer.visible));
+        for mesh in &layer.meshes {
+            layer_meshes.push((*mesh, layer.visible));
+        }
     }
 
     for (mesh_entity, visible) in layer_meshes {
+        // 密度 quad 按覆盖率降低不透明度。
+        let opacity = world
+            .get::<LayerMesh>(mesh_entity)
+            .map_or(1.0, |layer_mesh| layer_mesh.opacity);
         if let Some(mut mesh) = world.get_mut::<Mesh>(mesh_entity) {
             mesh.visible = visible;
             mesh.set_vec4(
                 "color",
-                nalgebra::Vector4::new(base_rgb[0], base_rgb[1], base_rgb[2], alpha),
+                nalgebra::Vector4::new(base_rgb[0], base_rgb[1], base_rgb[2], alpha * opacity),
             );
         }
     }
 }
 
-fn build_fill_geometry(polygon: &crate::graphics::vectors::Polygon) -> (Vec<f32>, Vec<u32>) {
+fn build_fill_geometry(
+    polygon: &crate::graphics::vectors::Polygon,
+    origin: crate::graphics::vectors::Point2d,
+) -> (Vec<f32>, Vec<u32>) {
     let triangles = polygon.earcut_triangles_raw();
 
     let mut positions: Vec<f32> = Vec::new();
@@ -78,9 +110,10 @@ fn build_fill_geometry(polygon: &crate::graphics::vectors::Polygon) -> (Vec<f32>
     positions.reserve(3 * triangles.vertices.len().saturating_div(2));
     indices.reserve(triangles.triangle_indices.len());
 
+    // 顶点相对于 origin 存储。
     for coord in triangles.vertices.chunks(2) {
-        positions.push(coord[0] as f32);
-        positions.push(coord[1] as f32);
+        positions.push((coord[0] - origin.x) as f32);
+        positions.push((coord[1] - origin.y) as f32);
         positions.push(0.0);
     }
     for index in triangles.triangle_indices {
@@ -93,6 +126,7 @@ fn build_ribbon_geometry(
     spine: &[crate::graphics::vectors::Point2d],
     width: f64,
     closed: bool,
+    origin: crate::graphics::vectors::Point2d,
 ) -> (Vec<f32>, Vec<u32>) {
     use crate::graphics::vectors::Point2d;
     use crate::graphics::vectors::Vector2d;
@@ -106,6 +140,7 @@ fn build_ribbon_geometry(
     let mut indices: Vec<u32> = Vec::new();
 
     let add_point = |positions: &mut Vec<f32>, p: Point2d| {
+        let p = p - origin;
         positions.extend_from_slice(&[p.x as f32, p.y as f32, 0.0]);
     };
 
@@ -188,9 +223,12 @@ struct WgpuState {
     clear_color: wgpu::Color,
 
     pipeline: wgpu::RenderPipeline,
+    bind_group_layout: wgpu::BindGroupLayout,
     bind_group: wgpu::BindGroup,
     uniform_buffer: wgpu::Buffer,
     uniform_stride: u64,
+    draw_capacity: usize,
+    geometry_buffers: HashMap<Entity, GeometryBuffers>,
 
     camera: Camera,
 
@@ -313,17 +351,7 @@ impl WgpuState {
         }
 
         // Build R-tree once (for hover picking)
-        let mut rtree_items: Vec<RTreeItem> = Vec::new();
-        for (entity, shape_instance) in world
-            .query::<(bevy_ecs::entity::Entity, &ShapeInstance)>()
-            .iter(world)
-        {
-            rtree_items.push(RTreeItem {
-                shape_instance: entity,
-                aabb: shape_instance.world_polygon.envelope(),
-            });
-        }
-        let rtree = RTree::bulk_load(rtree_items);
+        let rtree = build_rtree(world);
 
         // Create hover overlay meshes (rendered on top)
         let hover_fill_geometry = world.spawn(Geometry::new()).id();
@@ -332,12 +360,12 @@ impl WgpuState {
 
         let mut fill_mesh = Mesh::new(hover_fill_geometry, hover_material);
         fill_mesh.visible = false;
-        fill_mesh.render_order = 9_999;
+        fill_mesh.render_order = OVERLAY_RENDER_ORDER;
         let hover_fill_mesh = world.spawn(fill_mesh).id();
 
         let mut stroke_mesh = Mesh::new(hover_stroke_geometry, hover_material);
         stroke_mesh.visible = false;
-        stroke_mesh.render_order = 10_000;
+        stroke_mesh.render_order = OVERLAY_RENDER_ORDER + 1;
         let hover_stroke_mesh = world.spawn(stroke_mesh).id();
 
         let hover_stroke_width = 5.0 * camera.width / (size.width.max(1) as f64);
@@ -358,9 +386,15 @@ struct VSOut {
 };
 
 @vertex
-fn vs_main(@location(0) position: vec3<f32>) -> VSOut {
+fn vs_main(
+    @location(0) position: vec3<f32>,
+    @location(1) row0: vec3<f32>,
+    @location(2) row1: vec3<f32>,
+) -> VSOut {
     var out: VSOut;
-    out.pos = u.projection * u.view * u.model * vec4<f32>(position, 1.0);
+    let local = vec3<f32>(position.xy, 1.0);
+    let world = vec2<f32>(dot(row0, local), dot(row1, local));
+    out.pos = u.projection * u.view * u.model * vec4<f32>(world, position.z, 1.0);
     return out;
 }
 
@@ -377,14 +411,9 @@ fn fs_main() -> @location(0) vec4<f32> {
 
         let uniform_size = std::mem::size_of::<DrawUniform>() as u64;
         let align = device.limits().min_uniform_buffer_offset_alignment as u64;
-        let uniform_stride = ((uniform_size + align - 1) / align) * align;
+        let uniform_stride = uniform_size.div_ceil(align) * align;
 
-        let uniform_buffer = device.create_buffer(&wgpu::BufferDescriptor {
-            label: Some("layout-viewer uniform buffer"),
-            size: uniform_stride * (MAX_DRAWS_PER_FRAME as u64),
-            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
-            mapped_at_creation: false,
-        });
+        let uniform_buffer = create_uniform_buffer(&device, uniform_stride, INITIAL_DRAW_CAPACITY);
 
         // 不设置 min_binding_size：避免不同后端/平台对该字段的严格程度差异。
         let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
@@ -401,18 +430,7 @@ fn fs_main() -> @location(0) vec4<f32> {
             }],
         });
 
-        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: Some("layout-viewer bind group"),
-            layout: &bind_group_layout,
-            entries: &[wgpu::BindGroupEntry {
-                binding: 0,
-                resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
-                    buffer: &uniform_buffer,
-                    offset: 0,
-                    size: NonZeroU64::new(uniform_size),
-                }),
-            }],
-        });
+        let bind_group = create_bind_group(&device, &bind_group_layout, &uniform_buffer);
 
         let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
             label: Some("layout-viewer pipeline layout"),
@@ -426,15 +444,34 @@ fn fs_main() -> @location(0) vec4<f32> {
             vertex: wgpu::VertexState {
                 module: &shader,
                 entry_point: "vs_main",
-                buffers: &[wgpu::VertexBufferLayout {
-                    array_stride: 12,
-                    step_mode: wgpu::VertexStepMode::Vertex,
-                    attributes: &[wgpu::VertexAttribute {
-                        format: wgpu::VertexFormat::Float32x3,
-                        offset: 0,
-                        shader_location: 0,
-                    }],
-                }],
+                buffers: &[
+                    wgpu::VertexBufferLayout {
+                        array_stride: 12,
+                        step_mode: wgpu::VertexStepMode::Vertex,
+                        attributes: &[wgpu::VertexAttribute {
+                            format: wgpu::VertexFormat::Float32x3,
+                            offset: 0,
+                            shader_location: 0,
+                        }],
+                    },
+                    // 每个实例一个仿射变换的两行：(a, b, xoff) 和 (d, e, yoff)。
+                    wgpu::VertexBufferLayout {
+                        array_stride: 24,
+                        step_mode: wgpu::VertexStepMode::Instance,
+                        attributes: &[
+                            wgpu::VertexAttribute {
+                                format: wgpu::VertexFormat::Float32x3,
+                                offset: 0,
+                                shader_location: 1,
+                            },
+                            wgpu::VertexAttribute {
+                                format: wgpu::VertexFormat::Float32x3,
+                                offset: 12,
+                                shader_location: 2,
+                            },
+                        ],
+                    },
+                ],
             },
             fragment: Some(wgpu::FragmentState {
                 module: &shader,
@@ -468,9 +505,12 @@ fn fs_main() -> @location(0) vec4<f32> {
             clear_color,
 
             pipeline,
+            bind_group_layout,
             bind_group,
             uniform_buffer,
             uniform_stride,
+            draw_capacity: INITIAL_DRAW_CAPACITY,
+            geometry_buffers: HashMap::new(),
 
             camera,
 
@@ -511,8 +551,9 @@ fn fs_main() -> @location(0) vec4<f32> {
         }
         self.hover_stroke_width = desired;
 
+        let origin = self.hover_spine[0];
         let (positions, indices) =
-            build_ribbon_geometry(&self.hover_spine, self.hover_stroke_width, true);
+            build_ribbon_geometry(&self.hover_spine, self.hover_stroke_width, true, origin);
         if let Some(mut geo) = world.get_mut::<Geometry>(self.hover_stroke_geometry) {
             geo.positions = positions;
             geo.indices = indices;
@@ -524,14 +565,14 @@ fn fs_main() -> @location(0) vec4<f32> {
         let items = self.rtree.locate_all_at_point(&point);
 
         let mut result: Option<bevy_ecs::entity::Entity> = None;
-        let mut result_layer_index = -i16::MAX;
+        let mut result_layer_key: Option<LayerKey> = None;
 
         for item in items {
             let Some(shape_instance) = world.get::<ShapeInstance>(item.shape_instance) else {
                 continue;
             };
 
-            if shape_instance.layer_index < result_layer_index {
+            if result_layer_key.is_some_and(|key| shape_instance.layer_key < key) {
                 continue;
             }
 
@@ -542,12 +583,12 @@ fn fs_main() -> @location(0) vec4<f32> {
                 continue;
             }
 
-            if !shape_instance.world_polygon.contains(&point) {
+            if !shape_instance.contains(world, &point) {
                 continue;
             }
 
             result = Some(item.shape_instance);
-            result_layer_index = shape_instance.layer_index;
+            result_layer_key = Some(shape_instance.layer_key);
         }
 
         result
@@ -576,15 +617,26 @@ fn fs_main() -> @location(0) vec4<f32> {
         };
 
         // 先在不可变借用阶段把数据算出来，避免后续 get_mut 的借用冲突。
-        let (fill_positions, fill_indices, spine) = {
-            let (fill_positions, fill_indices) = build_fill_geometry(&shape_instance.world_polygon);
+        let (fill_positions, fill_indices, spine, origin) = {
+            let world_polygon = shape_instance.world_polygon(world);
             let mut spine: Vec<crate::graphics::vectors::Point2d> = Vec::new();
-            for coord in shape_instance.world_polygon.exterior().points() {
+            for coord in world_polygon.exterior().points() {
                 spine.push(crate::graphics::vectors::Point2d::new(coord.x(), coord.y()));
             }
-            (fill_positions, fill_indices, spine)
+            let origin = spine
+                .first()
+                .copied()
+                .unwrap_or_else(crate::graphics::vectors::Point2d::origin);
+            let (fill_positions, fill_indices) = build_fill_geometry(&world_polygon, origin);
+            (fill_positions, fill_indices, spine, origin)
         };
 
+        for mesh in [self.hover_fill_mesh, self.hover_stroke_mesh] {
+            if let Some(mut mesh) = world.get_mut::<Mesh>(mesh) {
+                mesh.origin = origin;
+            }
+        }
+
         // Fill geometry
         if let Some(mut geo) = world.get_mut::<Geometry>(self.hover_fill_geometry) {
             geo.positions = fill_positions;
@@ -594,7 +646,7 @@ fn fs_main() -> @location(0) vec4<f32> {
         // Stroke geometry
         self.hover_spine = spine;
         let (stroke_positions, stroke_indices) =
-            build_ribbon_geometry(&self.hover_spine, self.hover_stroke_width, true);
+            build_ribbon_geometry(&self.hover_spine, self.hover_stroke_width, true, origin);
         if let Some(mut geo) = world.get_mut::<Geometry>(self.hover_stroke_geometry) {
             geo.positions = stroke_positions;
             geo.indices = stroke_indices;
@@ -714,6 +766,93 @@ fn fs_main() -> @location(0) vec4<f32> {
         self.camera.height = self.camera.width / aspect;
     }
 
+    /// 几何体变化后重新创建它的缓冲区，否则沿用上一帧的。
+    fn upload_geometry(&mut self, world: &World, entity: Entity) {
+        let Some(geometry) = world
+            .get_entity(entity)
+            .ok()
+            .and_then(|entity| entity.get_ref::<Geometry>())
+        else {
+            self.geometry_buffers.remove(&entity);
+            return;
+        };
+        let changed = geometry.last_changed();
+        if self
+            .geometry_buffers
+            .get(&entity)
+            .is_some_and(|buffers| buffers.changed == changed)
+        {
+            return;
+        }
+
+        // 各实例组首尾相接放入同一个 instance buffer。
+        let mut instances: Vec<f32> = Vec::new();
+        let mut groups = Vec::new();
+        if geometry.is_instanced() {
+            for group in geometry.instance_groups() {
+                let first = (instances.len() / 6) as u32;
+                instances.extend_from_slice(group.instance_data());
+                groups.push(first..(instances.len() / 6) as u32);
+            }
+        } else {
+            instances.extend_from_slice(&IDENTITY_INSTANCE);
+        }
+        if geometry.positions.is_empty() || geometry.indices.is_empty() || instances.is_empty() {
+            self.geometry_buffers.remove(&entity);
+            return;
+        }
+
+        let buffers = GeometryBuffers {
+            changed,
+            vertex_buffer: self.create_buffer(
+                "layout-viewer vertex buffer",
+                wgpu::BufferUsages::VERTEX,
+                bytemuck::cast_slice(geometry.positions.as_slice()),
+            ),
+            index_buffer: self.create_buffer(
+                "layout-viewer index buffer",
+                wgpu::BufferUsages::INDEX,
+                bytemuck::cast_slice(geometry.indices.as_slice()),
+            ),
+            instance_buffer: self.create_buffer(
+                "layout-viewer instance buffer",
+                wgpu::BufferUsages::VERTEX,
+                bytemuck::cast_slice(instances.as_slice()),
+            ),
+            index_count: geometry.indices.len() as u32,
+            groups,
+        };
+        self.geometry_buffers.insert(entity, buffers);
+    }
+
+    fn create_buffer(
+        &self,
+        label: &str,
+        usage: wgpu::BufferUsages,
+        contents: &[u8],
+    ) -> wgpu::Buffer {
+        let buffer = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some(label),
+            size: contents.len() as u64,
+            usage: usage | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        self.queue.write_buffer(&buffer, 0, contents);
+        buffer
+    }
+
+    /// uniform buffer 容量不足时按 2 的幂扩容，并重建 bind group。
+    fn reserve_draws(&mut self, count: usize) {
+        if count <= self.draw_capacity {
+            return;
+        }
+        self.draw_capacity = count.next_power_of_two();
+        self.uniform_buffer =
+            create_uniform_buffer(&self.device, self.uniform_stride, self.draw_capacity);
+        self.bind_group =
+            create_bind_group(&self.device, &self.bind_group_layout, &self.uniform_buffer);
+    }
+
     fn render(&mut self, world: &mut World) -> Result<()> {
         // 缩放/resize 后，hover 线宽需要跟随更新
         self.refresh_hover_stroke_width_if_needed(world);
@@ -744,13 +883,26 @@ fn fs_main() -> @location(0) vec4<f32> {
             });
 
         let projection = self.camera.get_projection_matrix().cast::<f32>();
-        let view_matrix = self.camera.get_view_matrix().cast::<f32>();
+        // 顶点相对于 mesh 原点，相机平移在 f64 中计算（见 Mesh::model_matrix）。
+        let view_matrix = self.camera.get_relative_view_matrix().cast::<f32>();
+        let eye = self.camera.eye();
+
+        // 收集所有可见 mesh，并按 render_order 排序。实例化的 mesh 按实例组拆分，
+        // 每个组使用自己的原点。
+        struct MeshDraw {
+            render_order: i32,
+            model_matrix: nalgebra::Matrix4<f32>,
+            color: [f32; 4],
+            geometry: Entity,
+            group: Option<usize>,
+        }
 
-        // 收集所有可见 mesh，并按 render_order 排序。
-        let mut meshes: Vec<(i32, nalgebra::Matrix4<f32>, [f32; 4], bevy_ecs::entity::Entity)> =
-            Vec::new();
+        let mut meshes: Vec<MeshDraw> = Vec::new();
+        let view_bounds = self.camera.world_bounds();
+        let pixel_size = self.camera.width / self.config.width.max(1) as f64;
         for (_entity, mesh) in world.query::<(bevy_ecs::entity::Entity, &Mesh)>().iter(world) {
-            if !mesh.visible {
+            // 跳过隐藏的、视野之外的，以及不适合当前缩放级别的 mesh。
+            if !mesh.is_drawn(&view_bounds, pixel_size) {
                 continue;
             }
             let color = mesh
@@ -758,76 +910,77 @@ fn fs_main() -> @location(0) vec4<f32> {
                 .map(|c| [c.x, c.y, c.z, c.w])
                 .unwrap_or([1.0, 1.0, 1.0, 1.0]);
 
-            meshes.push((mesh.render_order, mesh.matrix, color, mesh.geometry));
+            let Some(geometry) = world.get::<Geometry>(mesh.geometry) else {
+                continue;
+            };
+            if !geometry.is_instanced() {
+                meshes.push(MeshDraw {
+                    render_order: mesh.render_order,
+                    model_matrix: mesh.model_matrix(&eye),
+                    color,
+                    geometry: mesh.geometry,
+                    group: None,
+                });
+                continue;
+            }
+
+            // 跳过视野之外的实例组。
+            for (index, group) in geometry.instance_groups().iter().enumerate() {
+                if group.bounds.intersects(&view_bounds) {
+                    meshes.push(MeshDraw {
+                        render_order: mesh.render_order,
+                        model_matrix: mesh.model_matrix_at(&group.origin, &eye),
+                        color,
+                        geometry: mesh.geometry,
+                        group: Some(index),
+                    });
+                }
+            }
         }
-        meshes.sort_by_key(|(order, _, _, _)| *order);
+        meshes.sort_by_key(|draw| draw.render_order);
+
+        // 复用未变化的几何体缓冲区；之后的修改会带上更新的 tick。
+        for draw in &meshes {
+            self.upload_geometry(world, draw.geometry);
+        }
+        self.geometry_buffers
+            .retain(|entity, _| world.get::<Geometry>(*entity).is_some());
+        world.increment_change_tick();
+        self.reserve_draws(meshes.len());
 
         struct DrawGpu {
-            vertex_buffer: wgpu::Buffer,
-            index_buffer: wgpu::Buffer,
-            index_count: u32,
+            geometry: Entity,
+            instances: Range<u32>,
             uniform_offset: u32,
         }
 
         let mut draws: Vec<DrawGpu> = Vec::new();
-        for (i, (_order, model_matrix, color, geometry_entity)) in meshes.iter().enumerate() {
-            if i >= MAX_DRAWS_PER_FRAME {
-                log::warn!(
-                    "wgpu: exceeded MAX_DRAWS_PER_FRAME ({}), dropping remaining draws",
-                    MAX_DRAWS_PER_FRAME
-                );
-                break;
-            }
-
-            let Some(geometry) = world.get::<Geometry>(*geometry_entity) else {
+        for draw in &meshes {
+            let Some(buffers) = self.geometry_buffers.get(&draw.geometry) else {
                 continue;
             };
-
-            if geometry.positions.is_empty() || geometry.indices.is_empty() {
+            let instances = match draw.group {
+                Some(index) => buffers.groups[index].clone(),
+                None => 0..1,
+            };
+            if instances.is_empty() {
                 continue;
             }
 
             let uniform = DrawUniform {
-                model: mat4_to_cols_array(model_matrix),
+                model: mat4_to_cols_array(&draw.model_matrix),
                 view: mat4_to_cols_array(&view_matrix),
                 projection: mat4_to_cols_array(&projection),
-                color: *color,
+                color: draw.color,
             };
 
-            let offset = (i as u64) * self.uniform_stride;
+            let offset = (draws.len() as u64) * self.uniform_stride;
             self.queue
                 .write_buffer(&self.uniform_buffer, offset, bytemuck::bytes_of(&uniform));
 
-            let vb_size = (geometry.positions.len() * std::mem::size_of::<f32>()) as u64;
-            let vertex_buffer = self.device.create_buffer(&wgpu::BufferDescriptor {
-                label: Some("layout-viewer vertex buffer"),
-                size: vb_size,
-                usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
-                mapped_at_creation: false,
-            });
-            self.queue.write_buffer(
-                &vertex_buffer,
-                0,
-                bytemuck::cast_slice(geometry.positions.as_slice()),
-            );
-
-            let ib_size = (geometry.indices.len() * std::mem::size_of::<u32>()) as u64;
-            let index_buffer = self.device.create_buffer(&wgpu::BufferDescriptor {
-                label: Some("layout-viewer index buffer"),
-                size: ib_size,
-                usage: wgpu::BufferUsages::INDEX | wgpu::BufferUsages::COPY_DST,
-                mapped_at_creation: false,
-            });
-            self.queue.write_buffer(
-                &index_buffer,
-                0,
-                bytemuck::cast_slice(geometry.indices.as_slice()),
-            );
-
             draws.push(DrawGpu {
-                vertex_buffer,
-                index_buffer,
-                index_count: geometry.indices.len() as u32,
+                geometry: draw.geometry,
+                instances,
                 uniform_offset: offset as u32,
             });
         }
@@ -851,10 +1004,12 @@ fn fs_main() -> @location(0) vec4<f32> {
             rp.set_pipeline(&self.pipeline);
 
             for draw in &draws {
+                let buffers = &self.geometry_buffers[&draw.geometry];
                 rp.set_bind_group(0, &self.bind_group, &[draw.uniform_offset]);
-                rp.set_vertex_buffer(0, draw.vertex_buffer.slice(..));
-                rp.set_index_buffer(draw.index_buffer.slice(..), wgpu::IndexFormat::Uint32);
-                rp.draw_indexed(0..draw.index_count, 0, 0..1);
+                rp.set_vertex_buffer(0, buffers.vertex_buffer.slice(..));
+                rp.set_vertex_buffer(1, buffers.instance_buffer.slice(..));
+                rp.set_index_buffer(buffers.index_buffer.slice(..), wgpu::IndexFormat::Uint32);
+                rp.draw_indexed(0..buffers.index_count, 0, draw.instances.clone());
             }
         }
 
@@ -864,6 +1019,34 @@ fn fs_main() -> @location(0) vec4<f32> {
     }
 }
 
+fn create_uniform_buffer(device: &wgpu::Device, stride: u64, draw_capacity: usize) -> wgpu::Buffer {
+    device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("layout-viewer uniform buffer"),
+        size: stride * (draw_capacity as u64),
+        usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    })
+}
+
+fn create_bind_group(
+    device: &wgpu::Device,
+    layout: &wgpu::BindGroupLayout,
+    uniform_buffer: &wgpu::Buffer,
+) -> wgpu::BindGroup {
+    device.create_bind_group(&wgpu::BindGroupDescriptor {
+        label: Some("layout-viewer bind group"),
+        layout,
+        entries: &[wgpu::BindGroupEntry {
+            binding: 0,
+            resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+                buffer: uniform_buffer,
+                offset: 0,
+                size: NonZeroU64::new(std::mem::size_of::<DrawUniform>() as u64),
+            }),
+        }],
+    })
+}
+
 pub fn spawn_wgpu_window(world: World, theme: Theme) -> Result<()> {
     let mut world = world;
 
@@ -913,11 +1096,7 @@ pub fn spawn_wgpu_window(world: World, theme: Theme) -> Result<()> {
                     state.current_cursor_pos = Some(position);
                     state.handle_mouse_move(position.x as u32, position.y as u32);
                     state.update_hover_at_screen(&mut world, position.x as u32, position.y as u32);
-                    if state.is_dragging {
-                        window.request_redraw();
-                    } else {
-                        window.request_redraw();
-                    }
+                    window.request_redraw();
                 }
                 WindowEvent::MouseInput {
                     state: button_state,
@@ -0,0 +1,152 @@
+use bevy_ecs::entity::Entity;
+use bevy_ecs::world::World;
+
+use crate::core::cell_display::abstract_outlines;
+use crate::core::cell_display::AbstractOutline;
+use crate::graphics::camera::Camera;
+use crate::graphics::geometry::Geometry;
+use crate::graphics::material::BlendMode;
+use crate::graphics::material::Material;
+use crate::graphics::mesh::Mesh;
+use crate::graphics::text::text_width;
+use crate::graphics::text::HorizontalAlign;
+use crate::graphics::text::Label;
+use crate::graphics::text::TextBatch;
+use crate::graphics::text::VerticalAlign;
+use crate::graphics::vectors::*;
+
+/// Width of the outline in physical pixels.
+const OUTLINE_WIDTH_PX: f64 = 1.5;
+
+/// Height of the cell names in physical pixels.
+const NAME_HEIGHT_PX: f64 = 12.0;
+
+/// If more names than this are in view, none are drawn.
+const MAX_VISIBLE_NAMES: usize = 400;
+
+/// Draws abstract cell instances, and instances just below the hierarchy
+/// level range, as outlined boxes with their names.
+pub struct AbstractCells {
+    outline_mesh: Entity,
+    names: TextBatch,
+    outlines: Vec<AbstractOutline>,
+}
+
+impl AbstractCells {
+    pub fn new(world: &mut World) -> Self {
+        let mut material = Material::default();
+        material.set_blending(BlendMode::SourceOver);
+        let material = world.spawn(material).id();
+
+        let geometry = world.spawn(Geometry::new()).id();
+        let mut mesh = Mesh::new(geometry, material);
+        mesh.visible = false;
+
+        Self {
+            outline_mesh: world.spawn(mesh).id(),
+            names: TextBatch::new(world),
+            outlines: Vec::new(),
+        }
+    }
+
+    pub fn set_render_order(&self, world: &mut World, render_order: i32) {
+        let mut mesh = world.get_mut::<Mesh>(self.outline_mesh).unwrap();
+        mesh.render_order = render_order;
+        self.names.set_render_order(world, render_order + 1);
+    }
+
+    pub fn set_color(&self, world: &mut World, color: Vector4f) {
+        let mut mesh = world.get_mut::<Mesh>(self.outline_mesh).unwrap();
+        mesh.set_vec4("color", color);
+        self.names.set_color(world, color);
+    }
+
+    /// Gathers the instances to outline. Call this whenever the instance
+    /// tree, a cell's display mode or the hierarchy level range changes.
+    pub fn gather(&mut self, world: &mut World) {
+        self.outlines = abstract_outlines(world);
+    }
+
+    /// Rebuilds the outlines and names for the current camera. Outlines keep
+    /// a constant width on screen, and names are only drawn inside boxes that
+    /// are wide enough to hold them.
+    pub fn update(
+        &mut self,
+        world: &mut World,
+        gl: &glow::Context,
+        camera: &Camera,
+        window_size: (u32, u32),
+    ) {
+        let pixel_size = camera.width / window_size.0 as f64;
+        let half_width = 0.5 * OUTLINE_WIDTH_PX * pixel_size;
+        let name_height = NAME_HEIGHT_PX * pixel_size;
+        let view_bounds = camera.world_bounds();
+        let right = camera.right();
+
+        let mut geometry = Geometry::new();
+        let mut origin = None;
+        self.names.labels.clear();
+        for outline in &self.outlines {
+            let bounds = outline.world_bounds;
+            if !bounds.intersects(&view_bounds) {
+                continue;
+            }
+
+            // Vertices are relative to the corner of the first box.
+            let origin = *origin.get_or_insert(Point2d::new(bounds.min_x, bounds.min_y));
+            let outer = bounds.inflate(half_width);
+            let inner = bounds.inflate(-half_width);
+            let inner = if inner.is_empty() { outer } else { inner };
+
+            let base = geometry.positions.len() as u32 / 3;
+            for b in [outer, inner] {
+                for (x, y) in [
+                    (b.min_x, b.min_y),
+                    (b.max_x, b.min_y),
+                    (b.max_x, b.max_y),
+                    (b.min_x, b.max_y),
+                ] {
+                    geometry.positions.push((x - origin.x) as f32);
+                    geometry.positions.push((y - origin.y) as f32);
+                    geometry.positions.push(0.0);
+                }
+            }
+            for side in 0..4 {
+                let (o0, o1) = (base + side, base + (side + 1) % 4);
+                let (i0, i1) = (o0 + 4, o1 + 4);
+                geometry
+                    .indices
+                    .extend_from_slice(&[o0, o1, i1, o0, i1, i0]);
+            }
+
+            // Names run along the camera's right vector.
+            let room = right.x.abs() * bounds.width() + right.y.abs() * bounds.height();
+            if text_width(&outline.name, name_height) < room {
+                self.names.labels.push(Label {
+                    text: outline.name.clone(),
+                    position: Point2d::new(
+                        (bounds.min_x + bounds.max_x) / 2.0,
+                        (bounds.min_y + bounds.max_y) / 2.0,
+                    ),
+                    horizontal: HorizontalAlign::Center,
+                    vertical: VerticalAlign::Middle,
+                });
+            }
+        }
+
+        if self.names.labels.len() > MAX_VISIBLE_NAMES {
+            self.names.labels.clear();
+        }
+
+        let mut mesh = world.get_mut::<Mesh>(self.outline_mesh).unwrap();
+        mesh.visible = origin.is_some();
+        mesh.origin = origin.unwrap_or_else(Point2d::origin);
+        let geometry_entity = mesh.geometry;
+        geometry.replace(world, gl, geometry_entity);
+
+        self.names.right = Vector2d::new(right.x, right.y);
+        self.names.up = Vector2d::new(camera.up.x, camera.up.y);
+        self.names.height = name_height;
+        self.names.update(world, gl);
+    }
+}
@@ -1,18 +1,60 @@
+use std::collections::HashMap;
+use std::collections::HashSet;
+
 use bevy_ecs::entity::Entity;
 use bevy_ecs::query::QueryState;
+use bevy_ecs::query::With;
 use bevy_ecs::world::World;
-use geo::Contains;
+use geo::InteriorPoint;
 use rstar::RTree;
-use rstar::RTreeObject;
-
+use web_time::Duration;
+use web_time::Instant;
+
+use crate::core::abstract_cells::AbstractCells;
+use crate::core::bookmarks::Bookmarks;
+use crate::core::camera_history::CameraHistory;
+use crate::core::camera_history::CameraView;
+use crate::core::cell_display;
+use crate::core::cell_display::HierarchyLevels;
+use crate::core::cell_proxy::CellProxy;
+use crate::core::cell_proxy::CellProxyList;
+use crate::core::components::CellDefinition;
+use crate::core::components::CellDisplay;
+use crate::core::components::CellInstance;
 use crate::core::components::Hovered;
 use crate::core::components::Layer;
+use crate::core::components::LayerKey;
 use crate::core::components::LayerMaterial;
+use crate::core::components::LayerMesh;
+use crate::core::components::RootCellInstance;
+use crate::core::components::Selected;
 use crate::core::components::ShapeInstance;
+use crate::core::components::BACKGROUND_RENDER_ORDER;
+use crate::core::components::OVERLAY_RENDER_ORDER;
+use crate::core::grid_overlay::GridOverlay;
 use crate::core::hover_effect::HoverEffect;
 use crate::core::hover_effect::HoverParams;
+use crate::core::inspector::ShapeReport;
+use crate::core::instancer::InstanceLimits;
+use crate::core::instancer::Instancer;
+use crate::core::instancer::SkippedReference;
 use crate::core::layer_proxy::LayerProxy;
+use crate::core::minimap_overlay::MinimapOverlay;
+use crate::core::root_finder::RootFinder;
+use crate::core::rtree;
 use crate::core::rtree::RTreeItem;
+use crate::core::ruler;
+use crate::core::ruler::Ruler;
+use crate::core::ruler::Rulers;
+use crate::core::ruler_overlay::RulerOverlay;
+use crate::core::selection;
+use crate::core::selection::SelectedShape;
+use crate::core::selection::SelectionMode;
+use crate::core::selection_effect::SelectionEffect;
+use crate::core::split_view;
+use crate::core::text_labels::TextLabels;
+use crate::core::units::LibraryUnits;
+use crate::core::view_state::ViewState;
 use crate::graphics::bounds::BoundingBox;
 use crate::graphics::camera::Camera;
 use crate::graphics::geometry::Geometry;
@@ -22,6 +64,8 @@ use crate::graphics::mesh::Mesh;
 use crate::graphics::renderer::Renderer;
 use crate::graphics::vectors::*;
 use crate::graphics::viewport::Viewport;
+use crate::rsutils::colors::hex_to_rgb;
+use crate::rsutils::colors::rgb_to_hex;
 
 /// Encapsulates high-level application logic common to all platforms.
 pub struct AppController {
@@ -35,8 +79,49 @@ pub struct AppController {
     zoom_speed: f64,
     needs_render: bool,
     hover_effect: HoverEffect,
+    selection_effect: SelectionEffect,
+    text_labels: TextLabels,
+    abstract_cells: AbstractCells,
+    ruler_overlay: RulerOverlay,
+    ruler_state: RulerState,
+    grid_overlay: GridOverlay,
+    minimap: MinimapOverlay,
+
+    /// The view when a press on the minimap started. While set, dragging
+    /// moves the camera to the point under the pointer in the minimap.
+    minimap_press: Option<CameraView>,
+
+    /// Last known pointer position in physical pixels, or None if the
+    /// pointer is outside the window.
+    cursor_position: Option<(u32, u32)>,
+
+    /// Color of each layer when the world was loaded, as six hex digits, so
+    /// that a ViewState only records the colors that were changed.
+    default_layer_colors: HashMap<LayerKey, String>,
+
+    /// Views for back and forward navigation, and when the last wheel event
+    /// arrived, so that a burst of wheel events is recorded as one step.
+    history: CameraHistory,
+    last_wheel: Option<Instant>,
+    bookmarks: Bookmarks,
+
+    /// Applied whenever a new root cell is selected.
+    instance_limits: InstanceLimits,
+
+    /// Views of the layout side by side, of which there is at least one.
+    /// The camera, history and layer visibility of the active pane live in
+    /// `camera`, `history` and the layers themselves, so that everything
+    /// that works on a single view works on the active pane. The others are
+    /// parked here, and the entry of the active pane is unused.
+    panes: Vec<Pane>,
+    active_pane: usize,
+
+    /// While the cameras are linked, the view of the active pane when the
+    /// other panes last followed it.
+    linked_view: Option<CameraView>,
     rtree: RTree<RTreeItem>,
     pinch_state: Option<PinchState>,
+    press_state: Option<PressState>,
 }
 
 #[derive(Clone, Copy)]
@@ -57,6 +142,70 @@ impl Theme {
     }
 }
 
+/// Pointer movement in physical pixels below which a press and release is
+/// treated as a click rather than a drag.
+const CLICK_TOLERANCE: u32 = 4;
+
+/// Wheel events further apart than this start a new step in the history.
+const WHEEL_HISTORY_GAP: Duration = Duration::from_millis(500);
+
+/// Tracks a mouse press from the button going down until it is released.
+/// Coordinates are in physical pixels.
+struct PressState {
+    start: (u32, u32),
+    current: (u32, u32),
+
+    /// True if shift was held, which makes clicks toggle shapes in the
+    /// selection, and drags select a box instead of panning.
+    extend: bool,
+
+    /// True once the pointer has moved further than CLICK_TOLERANCE.
+    moved: bool,
+
+    /// The view when the press started, recorded in the history if the
+    /// press pans.
+    view: CameraView,
+}
+
+/// Color of the dividers between the panes of a split view.
+const DIVIDER_COLOR: (f32, f32, f32, f32) = (0.5, 0.5, 0.5, 1.0);
+
+/// The parked state of a pane of the split view. See AppController::panes.
+struct Pane {
+    camera: Camera,
+    history: CameraHistory,
+    hidden_layers: HashSet<LayerKey>,
+}
+
+impl Pane {
+    fn new(camera: Camera) -> Self {
+        Self {
+            camera,
+            history: CameraHistory::default(),
+            hidden_layers: HashSet::new(),
+        }
+    }
+}
+
+/// Distance in physical pixels within which ruler ends snap to shapes.
+const SNAP_TOLERANCE: f64 = 8.0;
+
+/// Tracks ruler mode and the ruler being placed. Points are in database
+/// units.
+#[derive(Default)]
+struct RulerState {
+    /// True if clicks place ruler ends instead of selecting shapes.
+    enabled: bool,
+
+    /// True if ruler ends snap to nearby vertices and edges.
+    snapping: bool,
+
+    /// The first end of the ruler being placed, and the pointer position
+    /// that the ruler follows until the second click.
+    start: Option<Point2d>,
+    cursor: Option<Point2d>,
+}
+
 /// All coordinates and distances are in world space.
 struct PinchState {
     start_center: Vector2d,
@@ -65,14 +214,36 @@ struct PinchState {
     start_camera_width: f64,
 }
 
+impl PinchState {
+    fn start_view(&self) -> CameraView {
+        CameraView {
+            center: Point2d::new(self.start_camera_position.x, self.start_camera_position.y),
+            width: self.start_camera_width,
+        }
+    }
+}
+
 impl AppController {
     pub fn new(renderer: Renderer, physical_width: u32, physical_height: u32) -> Self {
-        let camera = Camera::new(Point3d::new(0.0, 0.0, 0.0), 128.0, 128.0, -1.0, 1.0);
+        let new_camera = || Camera::new(Point3d::new(0.0, 0.0, 0.0), 128.0, 128.0, -1.0, 1.0);
+        let camera = new_camera();
 
         let mut world = World::new();
 
         let hover_effect = HoverEffect::new(&mut world);
 
+        let selection_effect = SelectionEffect::new(&mut world);
+
+        let text_labels = TextLabels::new(&mut world);
+
+        let abstract_cells = AbstractCells::new(&mut world);
+
+        let ruler_overlay = RulerOverlay::new(&mut world);
+
+        let grid_overlay = GridOverlay::new(&mut world);
+
+        let minimap = MinimapOverlay::new(&mut world);
+
         let queries = QueryBundle::new(&mut world);
 
         Self {
@@ -86,8 +257,29 @@ impl AppController {
             zoom_speed: 0.05,
             needs_render: true,
             hover_effect,
+            selection_effect,
+            text_labels,
+            abstract_cells,
+            ruler_overlay,
+            ruler_state: RulerState {
+                snapping: true,
+                ..Default::default()
+            },
+            grid_overlay,
+            minimap,
+            minimap_press: None,
+            cursor_position: None,
+            default_layer_colors: HashMap::new(),
+            history: CameraHistory::default(),
+            last_wheel: None,
+            bookmarks: Bookmarks::default(),
+            instance_limits: InstanceLimits::default(),
+            panes: vec![Pane::new(new_camera())],
+            active_pane: 0,
+            linked_view: None,
             rtree: RTree::new(),
             pinch_state: None,
+            press_state: None,
         }
     }
 
@@ -97,45 +289,251 @@ impl AppController {
         }
 
         self.hover_effect = HoverEffect::new(&mut world);
-        self.hover_effect.set_render_order(&mut world, 9999);
+        self.hover_effect
+            .set_render_order(&mut world, OVERLAY_RENDER_ORDER);
+        self.text_labels = TextLabels::new(&mut world);
+        self.text_labels
+            .set_render_order(&mut world, OVERLAY_RENDER_ORDER + 2);
+        self.text_labels.gather(&mut world);
+        self.selection_effect = SelectionEffect::new(&mut world);
+        self.selection_effect
+            .set_render_order(&mut world, OVERLAY_RENDER_ORDER + 3);
+        self.abstract_cells = AbstractCells::new(&mut world);
+        self.abstract_cells
+            .set_render_order(&mut world, OVERLAY_RENDER_ORDER + 5);
+        self.abstract_cells.gather(&mut world);
+        self.ruler_overlay = RulerOverlay::new(&mut world);
+        self.ruler_overlay
+            .set_render_order(&mut world, OVERLAY_RENDER_ORDER + 7);
+        self.ruler_state.start = None;
+        self.ruler_state.cursor = None;
+        let grid_visible = self.grid_overlay.grid_visible();
+        self.grid_overlay = GridOverlay::new(&mut world);
+        self.grid_overlay.set_grid_visible(grid_visible);
+        self.grid_overlay.set_render_order(
+            &mut world,
+            BACKGROUND_RENDER_ORDER,
+            OVERLAY_RENDER_ORDER + 9,
+        );
+        let minimap_visible = self.minimap.visible();
+        self.minimap = MinimapOverlay::new(&mut world);
+        self.minimap.set_visible(minimap_visible);
+        self.minimap_press = None;
+        self.press_state = None;
         self.renderer.on_new_world(&mut world);
         self.world = world;
         self.queries.update(&mut self.world);
-
-        let mut world_bounds = BoundingBox::new();
-        for layer in self.queries.layers.iter_mut(&mut self.world) {
-            world_bounds.encompass(&layer.world_bounds);
+        self.default_layer_colors = self
+            .queries
+            .layers
+            .iter(&self.world)
+            .map(|layer| (layer.key, layer_color_hex(layer)))
+            .collect();
+        for pane in &mut self.panes {
+            pane.hidden_layers.clear();
         }
 
-        self.camera.fit_to_bounds(self.window_size, world_bounds);
+        self.fit_panes();
 
         self.render();
 
-        let mut rtree_items = Vec::new();
-        for (entity, shape_instance) in self.queries.shapes.iter(&self.world) {
-            rtree_items.push(RTreeItem {
-                shape_instance: entity,
-                aabb: shape_instance.world_polygon.envelope(),
-            });
-        }
-        self.rtree = RTree::bulk_load(rtree_items);
+        self.rebuild_rtree();
+    }
+
+    /// Replaces the instance tree with one rooted at the given cell
+    /// definition, then refits every camera to the new tree. This clears the
+    /// camera history, since its views are in the old root's coordinates.
+    pub fn select_root(&mut self, cell_definition: Entity) -> Vec<SkippedReference> {
+        self.hover_effect.hide(&mut self.world);
+
+        let mut instancer = Instancer::new(&mut self.world);
+        instancer.set_limits(self.instance_limits);
+        instancer.select_root(&mut self.world, cell_definition);
+
+        self.text_labels.gather(&mut self.world);
+        self.abstract_cells.gather(&mut self.world);
+        self.rebuild_rtree();
+
+        // The old shape instances are gone, and the selection with them.
+        self.selection_effect
+            .update(&mut self.world, self.renderer.gl());
+
+        self.fit_panes();
+        self.render();
+
+        instancer.skipped_references().to_vec()
+    }
+
+    /// Sets the depth and instance limits for later calls to select_root.
+    pub fn set_instance_limits(&mut self, limits: InstanceLimits) {
+        self.instance_limits = limits;
+    }
+
+    /// Finds a cell definition by name, for use with select_root.
+    pub fn find_cell(&mut self, name: &str) -> Option<Entity> {
+        RootFinder::new(&mut self.world).find_cell(&self.world, name)
+    }
+
+    fn rebuild_rtree(&mut self) {
+        self.rtree = rtree::build_rtree(&mut self.world);
     }
 
-    pub fn handle_mouse_press(&mut self, x: u32, y: u32) {
+    /// Starts a click or drag. Dragging pans the view, unless `extend` is
+    /// true (typically because shift is held), in which case it drags out a
+    /// selection box.
+    pub fn handle_mouse_press(&mut self, x: u32, y: u32, extend: bool) {
         if self.pinch_state.is_some() {
             return;
         }
-        self.is_dragging = true;
-        self.last_mouse_pos = Some((x, y));
+        self.focus_pane_at(x, y);
+        if self.minimap.view().is_some_and(|view| view.contains(x, y)) {
+            self.minimap_press = Some(self.camera_view());
+            self.center_on_minimap(x, y);
+            return;
+        }
+        self.press_state = Some(PressState {
+            start: (x, y),
+            current: (x, y),
+            extend,
+            moved: false,
+            view: self.camera_view(),
+        });
+        if !extend {
+            self.is_dragging = true;
+            self.last_mouse_pos = Some((x, y));
+        }
     }
 
+    /// Ends a click or drag. A click selects the topmost shape under the
+    /// pointer, or toggles it if the press was extending. In ruler mode, a
+    /// click places an end of a ruler instead. A box drag adds the shapes
+    /// that intersect the box to the selection.
     pub fn handle_mouse_release(&mut self) {
-        self.pinch_state = None;
+        let press_state = self.press_state.take();
+        let pinch_state = self.pinch_state.take();
         self.is_dragging = false;
         self.last_mouse_pos = None;
+
+        if let Some(view) = self.minimap_press.take() {
+            self.history.push(view);
+            return;
+        }
+
+        if let Some(pinch_state) = pinch_state {
+            self.history.push(pinch_state.start_view());
+            return;
+        }
+        let Some(press_state) = press_state else {
+            return;
+        };
+        if press_state.moved && !press_state.extend {
+            self.history.push(press_state.view);
+        }
+
+        if !press_state.moved && self.ruler_state.enabled {
+            self.place_ruler_end(press_state.start.0, press_state.start.1);
+        } else if !press_state.moved {
+            let (world_x, world_y) = self.screen_to_world(press_state.start.0, press_state.start.1);
+            let hit: Vec<Entity> = self
+                .pick_cell(world_x, world_y)
+                .map(|hit| hit.shape_instance)
+                .into_iter()
+                .collect();
+            let mode = if press_state.extend {
+                SelectionMode::Toggle
+            } else {
+                SelectionMode::Replace
+            };
+            self.select_shapes(&hit, mode);
+        } else if press_state.extend {
+            self.selection_effect.hide_band(&mut self.world);
+            let bounds = self.press_bounds(&press_state);
+            let shapes = selection::shapes_in_box(&self.world, &self.rtree, &bounds);
+            self.select_shapes(&shapes, SelectionMode::Extend);
+        }
+    }
+
+    /// Returns the box spanned by a press, in database units.
+    fn press_bounds(&self, press_state: &PressState) -> BoundingBox {
+        let p0 = self.screen_to_world(press_state.start.0, press_state.start.1);
+        let p1 = self.screen_to_world(press_state.current.0, press_state.current.1);
+        BoundingBox {
+            min_x: p0.0.min(p1.0),
+            min_y: p0.1.min(p1.1),
+            max_x: p0.0.max(p1.0),
+            max_y: p0.1.max(p1.1),
+        }
+    }
+
+    /// Combines the given shape instances with the selection.
+    pub fn select_shapes(&mut self, shapes: &[Entity], mode: SelectionMode) {
+        if mode == SelectionMode::Replace {
+            for entity in self.selected_shapes() {
+                self.world.entity_mut(entity).remove::<Selected>();
+            }
+        }
+        for &entity in shapes {
+            let mut entity = self.world.entity_mut(entity);
+            if mode == SelectionMode::Toggle && entity.contains::<Selected>() {
+                entity.remove::<Selected>();
+            } else {
+                entity.insert(Selected);
+            }
+        }
+        self.selection_effect
+            .update(&mut self.world, self.renderer.gl());
+        self.render();
+    }
+
+    pub fn clear_selection(&mut self) {
+        self.select_shapes(&[], SelectionMode::Replace);
+    }
+
+    /// Returns the shape instances that are selected, in no particular order.
+    pub fn selected_shapes(&mut self) -> Vec<Entity> {
+        self.queries.selected.iter(&self.world).collect()
+    }
+
+    /// Describes each selected shape, sorted by layer and then by position.
+    pub fn describe_selection(&mut self) -> Vec<SelectedShape> {
+        let mut shapes: Vec<SelectedShape> = self
+            .selected_shapes()
+            .into_iter()
+            .filter_map(|entity| SelectedShape::new(&self.world, entity))
+            .collect();
+        shapes.sort_by(|a, b| {
+            a.layer_key
+                .cmp(&b.layer_key)
+                .then(a.world_bounds.min_x.total_cmp(&b.world_bounds.min_x))
+                .then(a.world_bounds.min_y.total_cmp(&b.world_bounds.min_y))
+        });
+        shapes
+    }
+
+    /// Returns the shape instance under the pointer, if any.
+    pub fn hovered_shape(&mut self) -> Option<Entity> {
+        self.world
+            .query_filtered::<Entity, With<Hovered>>()
+            .single(&self.world)
+            .ok()
+    }
+
+    /// Describes a shape instance for the inspector.
+    pub fn inspect(&self, shape_instance: Entity) -> Option<ShapeReport> {
+        ShapeReport::new(&self.world, shape_instance)
+    }
+
+    /// Formats the selection as CSV, with bounds in microns.
+    pub fn export_selection(&mut self) -> String {
+        let shapes = self.describe_selection();
+        selection::export_csv(&shapes, &self.units())
     }
 
     pub fn handle_pinch_start(&mut self, distance: f64, center: Vector2u) {
+        if self.press_state.take().is_some() {
+            self.selection_effect.hide_band(&mut self.world);
+        }
+        self.focus_pane_at(center.x, center.y);
         let (center_x, center_y) = self.screen_to_world(center.x, center.y);
         self.pinch_state = Some(PinchState {
             start_center: Vector2d::new(center_x, center_y),
@@ -181,13 +579,40 @@ impl AppController {
     }
 
     pub fn handle_pinch_release(&mut self) {
-        self.pinch_state = None;
+        if let Some(pinch_state) = self.pinch_state.take() {
+            self.history.push(pinch_state.start_view());
+        }
     }
 
     pub fn handle_mouse_move(&mut self, x: u32, y: u32) {
+        self.cursor_position = Some((x, y));
         if self.pinch_state.is_some() {
             return;
         }
+        if self.minimap_press.is_some() {
+            self.center_on_minimap(x, y);
+            return;
+        }
+        if let Some(press_state) = &mut self.press_state {
+            press_state.current = (x, y);
+            if press_state.start.0.abs_diff(x) > CLICK_TOLERANCE
+                || press_state.start.1.abs_diff(y) > CLICK_TOLERANCE
+            {
+                press_state.moved = true;
+            }
+            if press_state.extend && press_state.moved {
+                let press_state = self.press_state.take().unwrap();
+                let bounds = self.press_bounds(&press_state);
+                self.press_state = Some(press_state);
+                self.selection_effect
+                    .show_band(&bounds, &mut self.world, self.renderer.gl());
+                self.render();
+            }
+        }
+        if self.ruler_state.start.is_some() {
+            self.ruler_state.cursor = Some(self.ruler_point(x, y));
+            self.render();
+        }
         if self.is_dragging {
             if let Some((last_x, last_y)) = self.last_mouse_pos {
                 let p1 = self.screen_to_world(x, y);
@@ -216,7 +641,17 @@ impl AppController {
             .map(|(entity, _)| entity)
             .unwrap_or(Entity::PLACEHOLDER);
 
-        if let Some(hit) = self.pick_cell(world_x, world_y) {
+        // Shapes beneath the minimap cannot be seen, so they are not hovered.
+        // Neither are shapes in other panes, until a press or the wheel makes
+        // their pane active.
+        let hit = if self.minimap.view().is_some_and(|view| view.contains(x, y))
+            || self.pane_at(x, y) != Some(self.active_pane)
+        {
+            None
+        } else {
+            self.pick_cell(world_x, world_y)
+        };
+        if let Some(hit) = hit {
             if hit.shape_instance != hovered_entity {
                 if hovered_entity != Entity::PLACEHOLDER {
                     self.world.entity_mut(hovered_entity).remove::<Hovered>();
@@ -245,6 +680,16 @@ impl AppController {
         if delta.abs() < MIN_DELTA {
             return;
         }
+        self.focus_pane_at(x, y);
+
+        let now = Instant::now();
+        if self
+            .last_wheel
+            .is_none_or(|last_wheel| now.duration_since(last_wheel) > WHEEL_HISTORY_GAP)
+        {
+            self.history.push(self.camera_view());
+        }
+        self.last_wheel = Some(now);
 
         // Convert screen coordinates to world space before zoom
         let (world_x, world_y) = self.screen_to_world(x, y);
@@ -273,7 +718,15 @@ impl AppController {
     }
 
     pub fn handle_mouse_leave(&mut self) {
+        self.cursor_position = None;
         self.is_dragging = false;
+        if let Some(view) = self.minimap_press.take() {
+            self.history.push(view);
+        }
+        if self.press_state.take().is_some() {
+            self.selection_effect.hide_band(&mut self.world);
+            self.render();
+        }
 
         let hovered_entity = self
             .world
@@ -302,16 +755,72 @@ impl AppController {
             return false;
         }
 
-        let width = 5.0 * self.camera.width / self.window_size.0 as f64;
-        self.hover_effect
-            .update_stroke_width(width, &mut self.world, self.renderer.gl());
+        self.follow_linked_view();
+        let rects = self.pane_rects();
+        if rects.len() > 1 {
+            let (r, g, b, a) = DIVIDER_COLOR;
+            self.renderer.fill(r, g, b, a);
+        }
+
+        // Each pane is drawn while it is active, then the active pane is
+        // restored.
+        let active_pane = self.active_pane;
+        for (index, rect) in rects.iter().enumerate() {
+            self.activate_pane(index);
+            self.draw_pane(rect, index == active_pane);
+        }
+        self.activate_pane(active_pane);
 
-        self.renderer.render(&mut self.world, &self.camera);
         self.renderer.check_gl_error("Scene render");
         self.needs_render = false;
         true // Frame was rendered
     }
 
+    /// Draws the active pane into the given rectangle, which is in physical
+    /// pixels from the top left corner of the window, with the minimap if
+    /// requested.
+    fn draw_pane(&mut self, rect: &Viewport, with_minimap: bool) {
+        let pane_size = (rect.width as u32, rect.height as u32);
+        let width = 5.0 * self.camera.width / pane_size.0 as f64;
+        self.hover_effect
+            .update_stroke_width(width, &mut self.world, self.renderer.gl());
+        self.selection_effect
+            .update_stroke_width(width, &mut self.world, self.renderer.gl());
+        self.text_labels
+            .update(&mut self.world, self.renderer.gl(), &self.camera, pane_size);
+        self.abstract_cells
+            .update(&mut self.world, self.renderer.gl(), &self.camera, pane_size);
+        let mut rulers = self.rulers();
+        if let (Some(start), Some(cursor)) = (self.ruler_state.start, self.ruler_state.cursor) {
+            rulers.push(Ruler::new(start, cursor));
+        }
+        self.ruler_overlay.update(
+            &mut self.world,
+            self.renderer.gl(),
+            &self.camera,
+            pane_size,
+            &rulers,
+        );
+        self.grid_overlay
+            .update(&mut self.world, self.renderer.gl(), &self.camera, pane_size);
+        if with_minimap {
+            let world_bounds = self.world_bounds();
+            self.minimap.update(
+                &mut self.world,
+                self.renderer.gl(),
+                &self.camera,
+                rect,
+                &world_bounds,
+            );
+        }
+
+        self.renderer
+            .render_inset(&mut self.world, &self.camera, rect, i32::MIN..=i32::MAX);
+        if with_minimap {
+            self.minimap.render(&mut self.world, &mut self.renderer);
+        }
+    }
+
     pub fn resize(&mut self, physical_width: u32, physical_height: u32) {
         self.window_size = (physical_width, physical_height);
         self.renderer.set_viewport(Viewport {
@@ -320,11 +829,13 @@ impl AppController {
             width: physical_width as f64,
             height: physical_height as f64,
         });
-        let window_aspect = physical_width as f64 / physical_height as f64;
-        self.camera.height = self.camera.width / window_aspect;
+        self.fit_pane_aspect();
 
         self.renderer.render(&mut self.world, &self.camera);
         self.renderer.check_gl_error("Scene render");
+
+        // Lay out the panes, the overlays and the minimap for the new size.
+        self.render();
     }
 
     pub fn destroy(&mut self) {
@@ -351,13 +862,31 @@ impl AppController {
         let mut layer_meshes = Vec::new();
         for (_, mut layer) in self.queries.mut_layers.iter_mut(&mut self.world) {
             layer.color.w = alpha;
-            layer_meshes.push((layer.mesh, layer.visible, layer.color.w));
+            for mesh in &layer.meshes {
+                layer_meshes.push((*mesh, layer.visible, layer.color.w));
+            }
         }
 
         for (mesh, visible, alpha) in layer_meshes {
             self.update_layer_mesh(mesh, visible, alpha);
         }
 
+        let label_color = match theme {
+            Theme::Light => Vector4f::new(0.0, 0.0, 0.0, 0.8),
+            Theme::Dark => Vector4f::new(1.0, 1.0, 1.0, 0.8),
+        };
+        self.text_labels.set_color(&mut self.world, label_color);
+        self.abstract_cells.set_color(&mut self.world, label_color);
+        self.ruler_overlay
+            .set_label_color(&mut self.world, label_color);
+        let grid_color = match theme {
+            Theme::Light => Vector4f::new(0.0, 0.0, 0.0, 0.08),
+            Theme::Dark => Vector4f::new(1.0, 1.0, 1.0, 0.08),
+        };
+        self.grid_overlay
+            .set_colors(&mut self.world, grid_color, label_color);
+        self.minimap.set_border_color(&mut self.world, label_color);
+
         let material = self.queries.layer_material.single_mut(&mut self.world);
 
         match theme {
@@ -379,20 +908,633 @@ impl AppController {
     }
 
     fn update_layer_mesh(&mut self, mesh: Entity, visible: bool, alpha: f32) {
+        let opacity = self
+            .world
+            .get::<LayerMesh>(mesh)
+            .map_or(1.0, |layer_mesh| layer_mesh.opacity);
+        let alpha = alpha * opacity;
         let color = Vector4f::new(alpha, alpha, alpha, 1.0);
         let mut mesh = self.world.get_mut::<Mesh>(mesh).unwrap();
         mesh.set_vec4("color", color);
         mesh.visible = visible;
     }
 
+    /// Returns the units of the loaded library, or the GDS defaults if no
+    /// library has been loaded.
+    pub fn units(&self) -> LibraryUnits {
+        self.world
+            .get_resource::<LibraryUnits>()
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Returns the bounds of all layers in database units.
+    pub fn world_bounds(&mut self) -> BoundingBox {
+        let mut world_bounds = BoundingBox::new();
+        for layer in self.queries.layers.iter(&self.world) {
+            world_bounds.encompass(&layer.world_bounds);
+        }
+        world_bounds
+    }
+
+    pub fn world_bounds_in_microns(&mut self) -> BoundingBox {
+        let world_bounds = self.world_bounds();
+        self.units().bounds_to_microns(&world_bounds)
+    }
+
+    /// Describes the world position under the pointer in microns and
+    /// database units, as shown by the minimap or the pane under it. Returns
+    /// None if the pointer is outside the window.
+    pub fn cursor_readout(&self) -> Option<String> {
+        let (x, y) = self.cursor_position?;
+        let point = match self.minimap.view().filter(|view| view.contains(x, y)) {
+            Some(view) => view.screen_to_world(x, y),
+            None => {
+                let pane = self.pane_at(x, y).unwrap_or(self.active_pane);
+                let camera = if pane == self.active_pane {
+                    &self.camera
+                } else {
+                    &self.panes[pane].camera
+                };
+                split_view::screen_to_world(camera, &self.pane_rects()[pane], x, y)
+            }
+        };
+        Some(self.units().describe_point((point.x, point.y)))
+    }
+
+    /// Shows or hides the background grid. The scale bar is always shown.
+    pub fn set_grid_visible(&mut self, visible: bool) {
+        self.grid_overlay.set_grid_visible(visible);
+        self.render();
+    }
+
+    pub fn grid_visible(&self) -> bool {
+        self.grid_overlay.grid_visible()
+    }
+
+    /// Shows or hides the overview of the whole layout in the top right
+    /// corner. Clicking or dragging in it moves the camera.
+    pub fn set_minimap_visible(&mut self, visible: bool) {
+        self.minimap.set_visible(visible);
+        self.render();
+    }
+
+    pub fn minimap_visible(&self) -> bool {
+        self.minimap.visible()
+    }
+
+    /// Centers the camera on the point shown by the minimap at the given
+    /// position in physical pixels.
+    fn center_on_minimap(&mut self, x: u32, y: u32) {
+        if let Some(point) = self.minimap.view().map(|view| view.screen_to_world(x, y)) {
+            self.camera.position.x = point.x;
+            self.camera.position.y = point.y;
+            self.render();
+        }
+    }
+
+    /// Splits the window into the given number of panes side by side, each
+    /// with its own camera, history and layer visibility. New panes start
+    /// with the view and layers of the active pane. When panes are removed,
+    /// the active pane is kept.
+    pub fn set_pane_count(&mut self, count: usize) {
+        let count = count.max(1);
+        self.follow_linked_view();
+        if self.active_pane >= count {
+            self.panes.swap(self.active_pane, count - 1);
+            self.active_pane = count - 1;
+        }
+        self.panes.truncate(count);
+        while self.panes.len() < count {
+            let camera = &self.camera;
+            let camera = Camera::new(
+                camera.position,
+                camera.width,
+                camera.height,
+                camera.near,
+                camera.far,
+            );
+            let mut pane = Pane::new(camera);
+            pane.hidden_layers = self.hidden_layers();
+            self.panes.push(pane);
+        }
+        self.fit_pane_aspect();
+        self.render();
+    }
+
+    pub fn pane_count(&self) -> usize {
+        self.panes.len()
+    }
+
+    /// Returns the index of the pane, from the left, that the layer
+    /// settings, navigation and view state apply to. A press or the wheel
+    /// makes the pane under the pointer active.
+    pub fn active_pane(&self) -> usize {
+        self.active_pane
+    }
+
+    /// Links the cameras of the panes, so that panning or zooming the active
+    /// pane pans and zooms the others by the same fraction of their panes.
+    pub fn set_cameras_linked(&mut self, linked: bool) {
+        self.linked_view = linked.then(|| self.camera_view());
+    }
+
+    pub fn cameras_linked(&self) -> bool {
+        self.linked_view.is_some()
+    }
+
+    fn pane_rects(&self) -> Vec<Viewport> {
+        split_view::pane_rects(self.window_size, self.panes.len())
+    }
+
+    fn pane_at(&self, x: u32, y: u32) -> Option<usize> {
+        split_view::pane_at(&self.pane_rects(), x, y)
+    }
+
+    /// Returns the size of each pane in physical pixels.
+    fn pane_size(&self) -> (u32, u32) {
+        let rect = self.pane_rects()[self.active_pane];
+        (rect.width as u32, rect.height as u32)
+    }
+
+    fn pane_aspect(&self) -> f64 {
+        let (width, height) = self.pane_size();
+        width as f64 / height as f64
+    }
+
+    /// Makes the pane under the given position in physical pixels active.
+    fn focus_pane_at(&mut self, x: u32, y: u32) {
+        let Some(pane) = self.pane_at(x, y) else {
+            return;
+        };
+        if pane == self.active_pane {
+            return;
+        }
+        self.follow_linked_view();
+        self.activate_pane(pane);
+        if self.linked_view.is_some() {
+            self.linked_view = Some(self.camera_view());
+        }
+        self.render();
+    }
+
+    /// Parks the camera, history and layer visibility of the active pane,
+    /// and restores those of the given pane.
+    fn activate_pane(&mut self, pane: usize) {
+        if pane == self.active_pane {
+            return;
+        }
+        let hidden_layers = self.hidden_layers();
+        let parked = &mut self.panes[self.active_pane];
+        std::mem::swap(&mut self.camera, &mut parked.camera);
+        std::mem::swap(&mut self.history, &mut parked.history);
+        parked.hidden_layers = hidden_layers;
+
+        let restored = &mut self.panes[pane];
+        std::mem::swap(&mut self.camera, &mut restored.camera);
+        std::mem::swap(&mut self.history, &mut restored.history);
+        let hidden_layers = std::mem::take(&mut restored.hidden_layers);
+        self.active_pane = pane;
+        self.set_hidden_layers(&hidden_layers);
+    }
+
+    /// Moves the other panes by the pan and zoom of the active pane since
+    /// they last followed it, if the cameras are linked.
+    fn follow_linked_view(&mut self) {
+        let Some(before) = self.linked_view else {
+            return;
+        };
+        let after = self.camera_view();
+        if after == before {
+            return;
+        }
+        let aspect = self.pane_aspect();
+        for (index, pane) in self.panes.iter_mut().enumerate() {
+            if index != self.active_pane {
+                let view = split_view::follow(view_of(&pane.camera), before, after);
+                set_view(&mut pane.camera, view, aspect);
+            }
+        }
+        self.linked_view = Some(after);
+    }
+
+    /// Fits every camera to the world and clears their histories.
+    fn fit_panes(&mut self) {
+        let world_bounds = self.world_bounds();
+        let pane_size = self.pane_size();
+        self.camera.fit_to_bounds(pane_size, world_bounds);
+        self.history.clear();
+        for pane in &mut self.panes {
+            pane.camera.fit_to_bounds(pane_size, world_bounds);
+            pane.history.clear();
+        }
+        if self.linked_view.is_some() {
+            self.linked_view = Some(self.camera_view());
+        }
+    }
+
+    /// Adjusts the height of every camera to the shape of the panes.
+    fn fit_pane_aspect(&mut self) {
+        let aspect = self.pane_aspect();
+        self.camera.height = self.camera.width / aspect;
+        for pane in &mut self.panes {
+            pane.camera.height = pane.camera.width / aspect;
+        }
+        if self.linked_view.is_some() {
+            self.linked_view = Some(self.camera_view());
+        }
+    }
+
+    /// Returns the layers of the active pane that are hidden.
+    fn hidden_layers(&mut self) -> HashSet<LayerKey> {
+        self.queries
+            .layers
+            .iter(&self.world)
+            .filter(|layer| !layer.visible)
+            .map(|layer| layer.key)
+            .collect()
+    }
+
+    /// Shows every layer except the given ones, touching only the layers
+    /// whose visibility changes.
+    fn set_hidden_layers(&mut self, hidden_layers: &HashSet<LayerKey>) {
+        let mut layer_meshes = Vec::new();
+        for (_, mut layer) in self.queries.mut_layers.iter_mut(&mut self.world) {
+            let visible = !hidden_layers.contains(&layer.key);
+            if layer.visible == visible {
+                continue;
+            }
+            layer.visible = visible;
+            for mesh in &layer.meshes {
+                layer_meshes.push((*mesh, visible, layer.color.w));
+            }
+        }
+        for (mesh, visible, alpha) in layer_meshes {
+            self.update_layer_mesh(mesh, visible, alpha);
+        }
+    }
+
+    /// Converts a position in physical pixels to microns.
+    pub fn screen_to_microns(&self, screen_x: u32, screen_y: u32) -> (f64, f64) {
+        let world = self.screen_to_world(screen_x, screen_y);
+        self.units().point_to_microns(world)
+    }
+
     pub fn create_layer_proxies(&mut self) -> Vec<LayerProxy> {
         let mut layer_proxies = Vec::new();
         for (entity, layer) in self.queries.mut_layers.iter(&self.world) {
             layer_proxies.push(LayerProxy::from_layer(entity, layer));
         }
+        layer_proxies.sort_by_key(|proxy| proxy.key);
         layer_proxies
     }
 
+    /// Sets how instances of a cell definition are drawn. Hidden and
+    /// abstract cells are also skipped by picking, selection and labels.
+    pub fn set_cell_display(&mut self, cell_definition: Entity, display: CellDisplay) {
+        cell_display::set_cell_display(&mut self.world, cell_definition, display);
+        self.refresh_concealed();
+    }
+
+    /// Returns the range of hierarchy levels whose shapes are drawn.
+    pub fn hierarchy_levels(&self) -> HierarchyLevels {
+        cell_display::hierarchy_levels(&self.world)
+    }
+
+    /// Restricts drawing to instances whose depth is within the given range.
+    /// Instances one level below the range are drawn as outlined boxes.
+    pub fn set_hierarchy_levels(&mut self, levels: HierarchyLevels) {
+        cell_display::set_hierarchy_levels(&mut self.world, levels);
+        self.refresh_concealed();
+    }
+
+    /// Returns the depth of the deepest cell instance, where the root is at
+    /// depth 0.
+    pub fn deepest_level(&mut self) -> usize {
+        cell_display::deepest_level(&mut self.world)
+    }
+
+    /// Drops the hover and selection of shapes that are no longer drawn, and
+    /// regathers the overlays that depend on which instances are concealed.
+    fn refresh_concealed(&mut self) {
+        self.hover_effect.hide(&mut self.world);
+        if let Some(hovered) = self.hovered_shape() {
+            self.world.entity_mut(hovered).remove::<Hovered>();
+        }
+
+        let concealed: Vec<Entity> = self
+            .selected_shapes()
+            .into_iter()
+            .filter(|shape| {
+                let cell_instance = self
+                    .world
+                    .get::<ShapeInstance>(*shape)
+                    .unwrap()
+                    .cell_instance;
+                cell_display::is_concealed(&self.world, cell_instance)
+            })
+            .collect();
+        for shape in concealed {
+            self.world.entity_mut(shape).remove::<Selected>();
+        }
+        self.selection_effect
+            .update(&mut self.world, self.renderer.gl());
+
+        self.text_labels.gather(&mut self.world);
+        self.abstract_cells.gather(&mut self.world);
+        self.render();
+    }
+
+    /// In ruler mode, the first click starts a ruler and the second click
+    /// finishes it. Leaving ruler mode drops an unfinished ruler.
+    pub fn set_ruler_mode(&mut self, enabled: bool) {
+        self.ruler_state.enabled = enabled;
+        self.ruler_state.start = None;
+        self.ruler_state.cursor = None;
+        self.render();
+    }
+
+    pub fn ruler_mode(&self) -> bool {
+        self.ruler_state.enabled
+    }
+
+    /// Makes ruler ends snap to the nearest vertex or edge of a visible
+    /// shape near the pointer.
+    pub fn set_ruler_snapping(&mut self, enabled: bool) {
+        self.ruler_state.snapping = enabled;
+    }
+
+    pub fn ruler_snapping(&self) -> bool {
+        self.ruler_state.snapping
+    }
+
+    /// Returns the finished rulers, oldest first.
+    pub fn rulers(&self) -> Vec<Ruler> {
+        self.world
+            .get_resource::<Rulers>()
+            .map_or_else(Vec::new, |rulers| rulers.0.clone())
+    }
+
+    /// Removes every ruler, including one that is being placed.
+    pub fn clear_rulers(&mut self) {
+        self.world.remove_resource::<Rulers>();
+        self.ruler_state.start = None;
+        self.ruler_state.cursor = None;
+        self.render();
+    }
+
+    /// Starts a ruler at the given position in physical pixels, or finishes
+    /// the ruler that is being placed.
+    fn place_ruler_end(&mut self, x: u32, y: u32) {
+        let point = self.ruler_point(x, y);
+        match self.ruler_state.start.take() {
+            None => {
+                self.ruler_state.start = Some(point);
+                self.ruler_state.cursor = Some(point);
+            }
+            Some(start) => {
+                self.ruler_state.cursor = None;
+                self.world
+                    .get_resource_or_init::<Rulers>()
+                    .0
+                    .push(Ruler::new(start, point));
+            }
+        }
+        self.render();
+    }
+
+    /// Converts a position in physical pixels to database units, snapped to
+    /// nearby shapes if snapping is enabled.
+    fn ruler_point(&self, x: u32, y: u32) -> Point2d {
+        let (world_x, world_y) = self.screen_to_world(x, y);
+        let point = Point2d::new(world_x, world_y);
+        if !self.ruler_state.snapping {
+            return point;
+        }
+        let tolerance = SNAP_TOLERANCE * self.camera.width / self.pane_size().0 as f64;
+        ruler::snap_point(&self.world, &self.rtree, point, tolerance)
+    }
+
+    /// Returns the name of the cell definition at the root of the instance
+    /// tree.
+    pub fn root_cell_name(&mut self) -> Option<String> {
+        self.world
+            .query_filtered::<&CellInstance, With<RootCellInstance>>()
+            .single(&self.world)
+            .ok()
+            .map(|root| CellDefinition::name_of(&self.world, root.cell_definition))
+    }
+
+    /// Captures the camera, root cell, layer settings and, if exactly one
+    /// shape is selected, the selection.
+    pub fn view_state(&mut self) -> ViewState {
+        let units = self.units();
+        let eye = self.camera.eye();
+
+        let cell = self.root_cell_name();
+
+        let mut hidden_layers = Vec::new();
+        let mut layer_colors = Vec::new();
+        for layer in self.queries.layers.iter(&self.world) {
+            if layer.shape_instances.is_empty() {
+                continue;
+            }
+            if !layer.visible {
+                hidden_layers.push(layer.key);
+            }
+            let color = layer_color_hex(layer);
+            if self.default_layer_colors.get(&layer.key) != Some(&color) {
+                layer_colors.push((layer.key, color));
+            }
+        }
+        hidden_layers.sort();
+        layer_colors.sort();
+
+        let selected = match self.selected_shapes()[..] {
+            [shape] => self.world.get::<ShapeInstance>(shape).and_then(|shape| {
+                let point = shape.world_polygon(&self.world).interior_point()?;
+                Some((shape.layer_key, units.point_to_microns(point.x_y())))
+            }),
+            _ => None,
+        };
+
+        ViewState {
+            center: Some(units.point_to_microns((eye.x, eye.y))),
+            width: Some(units.to_microns(self.camera.width)),
+            cell,
+            hidden_layers,
+            layer_colors,
+            selected,
+        }
+    }
+
+    /// Restores the layer settings, camera and selection of a ViewState. The
+    /// root cell is left to the caller, who should select it first.
+    pub fn apply_view_state(&mut self, state: &ViewState) {
+        let units = self.units();
+
+        let colors: HashMap<LayerKey, &String> = state
+            .layer_colors
+            .iter()
+            .map(|(key, color)| (*key, color))
+            .collect();
+        let mut layer_meshes = Vec::new();
+        for (_, mut layer) in self.queries.mut_layers.iter_mut(&mut self.world) {
+            layer.visible = !state.hidden_layers.contains(&layer.key);
+            let color = colors
+                .get(&layer.key)
+                .copied()
+                .or_else(|| self.default_layer_colors.get(&layer.key));
+            if let Some(rgb) = color.and_then(|color| hex_to_rgb(color)) {
+                (layer.color.x, layer.color.y, layer.color.z) = rgb;
+            }
+            for mesh in &layer.meshes {
+                layer_meshes.push((*mesh, layer.visible, layer.color.w));
+            }
+        }
+        for (mesh, visible, alpha) in layer_meshes {
+            self.update_layer_mesh(mesh, visible, alpha);
+        }
+
+        if state.width.is_some() || state.center.is_some() {
+            self.history.push(self.camera_view());
+        }
+        if let Some(width) = state.width {
+            let aspect = self.pane_aspect();
+            self.camera.width = units.from_microns(width);
+            self.camera.height = self.camera.width / aspect;
+        }
+        if let Some((x, y)) = state.center {
+            self.camera.position.x = units.from_microns(x);
+            self.camera.position.y = units.from_microns(y);
+        }
+
+        if let Some((layer_key, (x, y))) = state.selected {
+            let point = geo::Point::new(units.from_microns(x), units.from_microns(y));
+            let hit = self
+                .rtree
+                .locate_all_at_point(&point)
+                .map(|item| item.shape_instance)
+                .find(|shape| {
+                    self.world
+                        .get::<ShapeInstance>(*shape)
+                        .is_some_and(|shape| {
+                            shape.layer_key == layer_key
+                                && !cell_display::is_concealed(&self.world, shape.cell_instance)
+                                && shape.contains(&self.world, &point)
+                        })
+                });
+            if let Some(hit) = hit {
+                self.select_shapes(&[hit], SelectionMode::Replace);
+            }
+        }
+
+        self.render();
+    }
+
+    /// Returns to the view before the last pan, zoom or jump. Returns false
+    /// if there is nothing to go back to.
+    pub fn go_back(&mut self) -> bool {
+        let Some(view) = self.history.back(self.camera_view()) else {
+            return false;
+        };
+        self.set_camera_view(view);
+        true
+    }
+
+    /// Undoes the last go_back. Returns false if there is nothing to undo.
+    pub fn go_forward(&mut self) -> bool {
+        let Some(view) = self.history.forward(self.camera_view()) else {
+            return false;
+        };
+        self.set_camera_view(view);
+        true
+    }
+
+    pub fn can_go_back(&self) -> bool {
+        self.history.can_go_back()
+    }
+
+    pub fn can_go_forward(&self) -> bool {
+        self.history.can_go_forward()
+    }
+
+    fn camera_view(&self) -> CameraView {
+        view_of(&self.camera)
+    }
+
+    fn set_camera_view(&mut self, view: CameraView) {
+        let aspect = self.pane_aspect();
+        set_view(&mut self.camera, view, aspect);
+        self.render();
+    }
+
+    pub fn bookmarks(&self) -> &Bookmarks {
+        &self.bookmarks
+    }
+
+    /// Replaces the bookmarks, typically with ones loaded from storage.
+    pub fn set_bookmarks(&mut self, bookmarks: Bookmarks) {
+        self.bookmarks = bookmarks;
+    }
+
+    /// Bookmarks the camera, root cell and layer settings under the given
+    /// name, replacing any bookmark with that name. Returns false if the
+    /// name is blank.
+    pub fn add_bookmark(&mut self, name: &str) -> bool {
+        let view = ViewState {
+            selected: None,
+            ..self.view_state()
+        };
+        self.bookmarks.set(name, view)
+    }
+
+    pub fn remove_bookmark(&mut self, name: &str) -> bool {
+        self.bookmarks.remove(name)
+    }
+
+    /// Restores a bookmark, first switching to its root cell if needed.
+    /// Returns false if there is no bookmark with the given name.
+    pub fn go_to_bookmark(&mut self, name: &str) -> bool {
+        let Some(bookmark) = self.bookmarks.get(name) else {
+            return false;
+        };
+        let view = bookmark.view.clone();
+        if let Some(cell) = &view.cell {
+            if self.root_cell_name().as_ref() != Some(cell) {
+                if let Some(cell_definition) = self.find_cell(cell) {
+                    self.select_root(cell_definition);
+                }
+            }
+        }
+        self.apply_view_state(&view);
+        true
+    }
+
+    /// Lists the cell instance tree for the hierarchy browser.
+    pub fn create_cell_proxies(&mut self) -> CellProxyList {
+        CellProxyList::new(CellProxy::gather(&mut self.world))
+    }
+
+    /// Fits the camera to the given bounds, which are in database units,
+    /// leaving a small margin. Empty or degenerate bounds are ignored.
+    pub fn zoom_to_bounds(&mut self, bounds: &BoundingBox) {
+        if bounds.is_empty() || (bounds.width() <= 0.0 && bounds.height() <= 0.0) {
+            return;
+        }
+        let margin = 0.05 * bounds.width().max(bounds.height());
+        self.history.push(self.camera_view());
+        self.camera
+            .fit_to_bounds(self.pane_size(), bounds.inflate(margin));
+        self.render();
+    }
+
+    /// Fits the camera to the shapes of a cell instance and its descendants.
+    pub fn zoom_to_cell(&mut self, cell_instance: Entity) {
+        let bounds = cell_display::subtree_bounds(&self.world, cell_instance);
+        self.zoom_to_bounds(&bounds);
+    }
+
     pub fn update_layer(&mut self, layer_proxy: LayerProxy) {
         let mut layer = self
             .queries
@@ -402,21 +1544,23 @@ impl AppController {
             .1;
         layer_proxy.to_layer(&mut layer);
 
-        let mesh = layer.mesh;
+        let meshes = layer.meshes.clone();
         let visible = layer.visible;
         let alpha = layer.color.w;
 
-        self.update_layer_mesh(mesh, visible, alpha);
+        for mesh in meshes {
+            self.update_layer_mesh(mesh, visible, alpha);
+        }
     }
 
     fn pick_cell(&self, x: f64, y: f64) -> Option<RTreeItem> {
         let point = geo::Point::new(x, y);
         let items = self.rtree.locate_all_at_point(&point);
         let mut result: Option<RTreeItem> = None;
-        let mut result_layer_index = -i16::MAX;
+        let mut result_layer_key: Option<LayerKey> = None;
 
         // Of all items whose AABB overlaps the query point, pick the one with
-        // the highest layer index, but only if its layer is visible, and if its
+        // the highest layer key, but only if its layer is visible, and if its
         // polygon actually contains the point.
 
         for item in items {
@@ -425,7 +1569,7 @@ impl AppController {
                 .get::<ShapeInstance>(item.shape_instance)
                 .unwrap();
 
-            if shape_instance.layer_index < result_layer_index {
+            if result_layer_key.is_some_and(|key| shape_instance.layer_key < key) {
                 continue;
             }
 
@@ -435,20 +1579,25 @@ impl AppController {
                 continue;
             }
 
-            if !shape_instance.world_polygon.contains(&point) {
+            if cell_display::is_concealed(&self.world, shape_instance.cell_instance) {
+                continue;
+            }
+
+            if !shape_instance.contains(&self.world, &point) {
                 continue;
             }
 
             result = Some(item.clone());
-            result_layer_index = shape_instance.layer_index;
+            result_layer_key = Some(shape_instance.layer_key);
         }
         result
     }
 
-    fn screen_to_world(&self, screen_x: u32, screen_y: u32) -> (f64, f64) {
-        let ndc_x = (screen_x as f64 / self.window_size.0 as f64) * 2.0 - 1.0;
-        let ndc_y = -((screen_y as f64 / self.window_size.1 as f64) * 2.0 - 1.0);
-        let world = self.camera.unproject(Point3d::new(ndc_x, ndc_y, 0.0));
+    /// Converts a position in physical pixels to database units, as seen
+    /// through the camera of the active pane.
+    pub fn screen_to_world(&self, screen_x: u32, screen_y: u32) -> (f64, f64) {
+        let rect = self.pane_rects()[self.active_pane];
+        let world = split_view::screen_to_world(&self.camera, &rect, screen_x, screen_y);
         (world.x, world.y)
     }
 }
@@ -463,7 +1612,7 @@ impl Drop for AppController {
 struct QueryBundle {
     mut_layers: QueryState<(Entity, &'static mut Layer)>,
     layers: QueryState<&'static Layer>,
-    shapes: QueryState<(Entity, &'static ShapeInstance)>,
+    selected: QueryState<Entity, With<Selected>>,
     geometries: QueryState<&'static mut Geometry>,
     materials: QueryState<&'static mut Material>,
     layer_material: QueryState<(&'static mut Material, &'static LayerMaterial)>,
@@ -474,7 +1623,7 @@ impl QueryBundle {
         Self {
             mut_layers: QueryState::new(world),
             layers: QueryState::new(world),
-            shapes: QueryState::new(world),
+            selected: QueryState::new(world),
             geometries: QueryState::new(world),
             materials: QueryState::new(world),
             layer_material: QueryState::new(world),
@@ -485,3 +1634,25 @@ impl QueryBundle {
         *self = Self::new(world);
     }
 }
+
+fn view_of(camera: &Camera) -> CameraView {
+    CameraView {
+        center: camera.eye(),
+        width: camera.width,
+    }
+}
+
+/// Moves a camera to a view, keeping the given aspect ratio.
+fn set_view(camera: &mut Camera, view: CameraView, aspect: f64) {
+    camera.position.x = view.center.x;
+    camera.position.y = view.center.y;
+    camera.width = view.width;
+    camera.height = view.width / aspect;
+}
+
+/// Formats the color of a layer as six hex digits.
+fn layer_color_hex(layer: &Layer) -> String {
+    rgb_to_hex(layer.color.x, layer.color.y, layer.color.z)
+        .trim_start_matches('#')
+        .to_string()
+}
@@ -0,0 +1,72 @@
+use crate::core::view_state::ViewState;
+
+/// A named view of the layout.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Bookmark {
+    pub name: String,
+    pub view: ViewState,
+}
+
+/// Bookmarks in the order they were added. Names are unique.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Bookmarks(pub Vec<Bookmark>);
+
+impl Bookmarks {
+    /// Adds a bookmark, or replaces the view of the bookmark with the same
+    /// name. Runs of whitespace in the name are collapsed into single
+    /// spaces. Returns false if the name is blank.
+    pub fn set(&mut self, name: &str, view: ViewState) -> bool {
+        let name = name.split_whitespace().collect::<Vec<_>>().join(" ");
+        if name.is_empty() {
+            return false;
+        }
+        match self.0.iter_mut().find(|bookmark| bookmark.name == name) {
+            Some(bookmark) => bookmark.view = view,
+            None => self.0.push(Bookmark { name, view }),
+        }
+        true
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Bookmark> {
+        self.0.iter().find(|bookmark| bookmark.name == name)
+    }
+
+    /// Returns false if there is no bookmark with the given name.
+    pub fn remove(&mut self, name: &str) -> bool {
+        let count = self.0.len();
+        self.0.retain(|bookmark| bookmark.name != name);
+        self.0.len() != count
+    }
+
+    pub fn names(&self) -> Vec<String> {
+        self.0
+            .iter()
+            .map(|bookmark| bookmark.name.clone())
+            .collect()
+    }
+
+    /// Writes one bookmark per line, as the name and the view's query
+    /// string separated by a tab. The format is meant to be easy to edit by
+    /// hand, for example to rename bookmarks.
+    pub fn to_text(&self) -> String {
+        self.0
+            .iter()
+            .map(|bookmark| format!("{}\t{}\n", bookmark.name, bookmark.view.to_query_string()))
+            .collect()
+    }
+
+    /// Reads bookmarks written by to_text. Blank lines, lines starting with
+    /// `#` and lines without a tab are skipped.
+    pub fn from_text(text: &str) -> Self {
+        let mut bookmarks = Self::default();
+        for line in text.lines() {
+            if line.starts_with('#') {
+                continue;
+            }
+            if let Some((name, query)) = line.split_once('\t') {
+                bookmarks.set(name, ViewState::from_query_string(query));
+            }
+        }
+        bookmarks
+    }
+}
@@ -0,0 +1,69 @@
+use crate::graphics::vectors::*;
+
+/// The history keeps at most this many views in each direction.
+pub const MAX_HISTORY: usize = 100;
+
+/// The part of the camera that navigation changes, in database units.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CameraView {
+    pub center: Point2d,
+    pub width: f64,
+}
+
+/// Back and forward stacks of camera views, like those of a web browser.
+/// Views are recorded when they are left behind, so the current view is
+/// never on either stack.
+#[derive(Clone, Debug, Default)]
+pub struct CameraHistory {
+    back: Vec<CameraView>,
+    forward: Vec<CameraView>,
+}
+
+impl CameraHistory {
+    /// Records a view that is being navigated away from. This drops the
+    /// forward stack, since it no longer follows from the current view.
+    pub fn push(&mut self, view: CameraView) {
+        self.forward.clear();
+        if self.back.last() == Some(&view) {
+            return;
+        }
+        push_capped(&mut self.back, view);
+    }
+
+    /// Returns the previous view, and records `current` so that forward
+    /// can return to it.
+    pub fn back(&mut self, current: CameraView) -> Option<CameraView> {
+        let view = self.back.pop()?;
+        push_capped(&mut self.forward, current);
+        Some(view)
+    }
+
+    /// Returns the view that back left, and records `current` so that back
+    /// can return to it.
+    pub fn forward(&mut self, current: CameraView) -> Option<CameraView> {
+        let view = self.forward.pop()?;
+        push_capped(&mut self.back, current);
+        Some(view)
+    }
+
+    pub fn can_go_back(&self) -> bool {
+        !self.back.is_empty()
+    }
+
+    pub fn can_go_forward(&self) -> bool {
+        !self.forward.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.back.clear();
+        self.forward.clear();
+    }
+}
+
+/// Pushes a view, dropping the oldest one if the stack is full.
+fn push_capped(stack: &mut Vec<CameraView>, view: CameraView) {
+    if stack.len() == MAX_HISTORY {
+        stack.remove(0);
+    }
+    stack.push(view);
+}
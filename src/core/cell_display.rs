@@ -0,0 +1,200 @@
+use bevy_ecs::entity::Entity;
+use bevy_ecs::query::With;
+use bevy_ecs::resource::Resource;
+use bevy_ecs::world::World;
+
+use crate::core::components::CellDefinition;
+use crate::core::components::CellDisplay;
+use crate::core::components::CellInstance;
+use crate::core::components::RootCellInstance;
+use crate::core::components::ShapeInstance;
+use crate::core::instancer::add_mesh_instances;
+use crate::core::instancer::clear_mesh_instances;
+use crate::graphics::bounds::BoundingBox;
+
+/// Range of depths in the instance tree whose shapes are drawn, stored as a
+/// World resource. The root is at depth 0. Instances one level below the
+/// range are drawn as outlined boxes in place of their contents.
+#[derive(Resource, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct HierarchyLevels {
+    pub min: usize,
+    pub max: usize,
+}
+
+impl HierarchyLevels {
+    pub fn new(min: usize, max: usize) -> Self {
+        Self { min, max }
+    }
+
+    pub fn contains(&self, depth: usize) -> bool {
+        self.min <= depth && depth <= self.max
+    }
+}
+
+impl Default for HierarchyLevels {
+    /// Draws every level.
+    fn default() -> Self {
+        Self::new(0, usize::MAX)
+    }
+}
+
+/// An instance that is drawn as an outlined box with its cell's name, either
+/// because its cell is abstract or because it lies just below the
+/// HierarchyLevels range.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AbstractOutline {
+    pub cell_instance: Entity,
+    pub name: String,
+
+    /// Bounds of the shapes of the instance and its descendants, in database
+    /// units.
+    pub world_bounds: BoundingBox,
+}
+
+/// Sets how instances of a cell definition are drawn, then updates the
+/// instance tree to match.
+pub fn set_cell_display(world: &mut World, cell_definition: Entity, display: CellDisplay) {
+    let Some(mut cell_def) = world.get_mut::<CellDefinition>(cell_definition) else {
+        return;
+    };
+    if cell_def.display == display {
+        return;
+    }
+    cell_def.display = display;
+    apply_cell_display(world);
+}
+
+/// Sets the range of levels whose shapes are drawn, then updates the instance
+/// tree to match.
+pub fn set_hierarchy_levels(world: &mut World, levels: HierarchyLevels) {
+    if hierarchy_levels(world) == levels {
+        return;
+    }
+    world.insert_resource(levels);
+    apply_cell_display(world);
+}
+
+/// Returns the range of levels whose shapes are drawn.
+pub fn hierarchy_levels(world: &World) -> HierarchyLevels {
+    world
+        .get_resource::<HierarchyLevels>()
+        .copied()
+        .unwrap_or_default()
+}
+
+/// Recomputes the concealed flag of every CellInstance from the display mode
+/// of its cell and its ancestors and from the HierarchyLevels range, and
+/// rebuilds the instances of every LayerMesh so that only unconcealed cells
+/// are drawn.
+pub fn apply_cell_display(world: &mut World) {
+    clear_mesh_instances(world);
+    let levels = hierarchy_levels(world);
+
+    let Ok(root) = world
+        .query_filtered::<Entity, With<RootCellInstance>>()
+        .single(world)
+    else {
+        return;
+    };
+
+    // The flag carried down the tree only reflects display modes, because
+    // instances below the range are drawn even when their ancestors are not.
+    let mut stack = vec![(root, false)];
+    while let Some((entity, parent_concealed)) = stack.pop() {
+        let Some(instance) = world.get::<CellInstance>(entity) else {
+            continue;
+        };
+        let cell_def = world.get::<CellDefinition>(instance.cell_definition);
+        let display = cell_def.map_or(CellDisplay::Contents, |cell_def| cell_def.display);
+        let meshes = cell_def.map_or_else(Vec::new, |cell_def| cell_def.meshes.clone());
+        let transform = instance.world_transform;
+        let depth = instance.depth;
+        let concealed = parent_concealed || display != CellDisplay::Contents;
+
+        for child in instance.child_instances.iter().rev() {
+            stack.push((*child, concealed));
+        }
+
+        let concealed = concealed || !levels.contains(depth);
+
+        world.get_mut::<CellInstance>(entity).unwrap().concealed = concealed;
+        if !concealed {
+            add_mesh_instances(world, &meshes, &transform);
+        }
+    }
+}
+
+/// Returns true if the given CellInstance is hidden or abstract, is inside
+/// one that is, or lies outside the HierarchyLevels range.
+pub fn is_concealed(world: &World, cell_instance: Entity) -> bool {
+    world
+        .get::<CellInstance>(cell_instance)
+        .is_some_and(|instance| instance.concealed)
+}
+
+/// Lists the instances to draw as outlines: abstract instances that are not
+/// inside another hidden or abstract instance, and the instances one level
+/// below the HierarchyLevels range. Outlines are in depth-first order.
+pub fn abstract_outlines(world: &mut World) -> Vec<AbstractOutline> {
+    let Ok(root) = world
+        .query_filtered::<Entity, With<RootCellInstance>>()
+        .single(world)
+    else {
+        return Vec::new();
+    };
+    let levels = hierarchy_levels(world);
+
+    let mut outlines = Vec::new();
+    let mut stack = vec![root];
+    while let Some(entity) = stack.pop() {
+        let Some(instance) = world.get::<CellInstance>(entity) else {
+            continue;
+        };
+        let Some(cell_def) = world.get::<CellDefinition>(instance.cell_definition) else {
+            continue;
+        };
+        match cell_def.display {
+            CellDisplay::Hidden => continue,
+            CellDisplay::Abstract => {}
+            CellDisplay::Contents if instance.depth <= levels.max => {
+                stack.extend(instance.child_instances.iter().rev());
+                continue;
+            }
+            CellDisplay::Contents => {}
+        }
+        outlines.push(AbstractOutline {
+            cell_instance: entity,
+            name: cell_def.name.clone(),
+            world_bounds: subtree_bounds(world, entity),
+        });
+    }
+    outlines
+}
+
+/// Returns the depth of the deepest CellInstance.
+pub fn deepest_level(world: &mut World) -> usize {
+    world
+        .query::<&CellInstance>()
+        .iter(world)
+        .map(|instance| instance.depth)
+        .max()
+        .unwrap_or(0)
+}
+
+/// Returns the bounds of the shapes of an instance and its descendants.
+pub fn subtree_bounds(world: &World, cell_instance: Entity) -> BoundingBox {
+    let mut bounds = BoundingBox::new();
+    let mut stack = vec![cell_instance];
+    while let Some(entity) = stack.pop() {
+        let Some(instance) = world.get::<CellInstance>(entity) else {
+            continue;
+        };
+        for shape in &instance.shape_instances {
+            if let Some(shape) = world.get::<ShapeInstance>(*shape) {
+                bounds.encompass(&shape.world_bounds(world));
+            }
+        }
+        stack.extend_from_slice(&instance.child_instances);
+    }
+    bounds
+}
@@ -0,0 +1,126 @@
+use std::collections::HashMap;
+use std::ops::Deref;
+use std::rc::Rc;
+
+use bevy_ecs::entity::Entity;
+use bevy_ecs::query::With;
+use bevy_ecs::world::World;
+
+use crate::core::components::CellDefinition;
+use crate::core::components::CellDisplay;
+use crate::core::components::CellInstance;
+use crate::core::components::RootCellInstance;
+
+/// Represents a cell instance in the hierarchy browser.
+#[derive(Clone, PartialEq)]
+pub struct CellProxy {
+    pub instance: Entity,
+    pub cell_definition: Entity,
+
+    /// Name of the cell definition, shared by all of its instances.
+    pub name: Rc<str>,
+
+    /// Number of ancestors between this instance and the root.
+    pub depth: usize,
+
+    /// Number of instances below this one, which immediately follow it in
+    /// the list returned by `gather`.
+    pub descendant_count: usize,
+
+    /// Number of instances of this cell definition in the whole tree.
+    pub instance_count: usize,
+
+    /// How instances of this cell definition are drawn.
+    pub display: CellDisplay,
+}
+
+impl CellProxy {
+    /// Lists every cell instance in depth-first order, starting with the
+    /// root. Returns an empty list if there is no instance tree.
+    pub fn gather(world: &mut World) -> Vec<CellProxy> {
+        let Ok(root) = world
+            .query_filtered::<Entity, With<RootCellInstance>>()
+            .single(world)
+        else {
+            return Vec::new();
+        };
+
+        // Depth-first traversal that records the index of each proxy's
+        // parent, so that descendants can be folded into their ancestors.
+        let mut proxies: Vec<CellProxy> = Vec::new();
+        let mut parents: Vec<Option<usize>> = Vec::new();
+        let mut names: HashMap<Entity, Rc<str>> = HashMap::new();
+        let mut stack: Vec<(Entity, usize, Option<usize>)> = vec![(root, 0, None)];
+        while let Some((entity, depth, parent)) = stack.pop() {
+            let Some(instance) = world.get::<CellInstance>(entity) else {
+                continue;
+            };
+
+            let cell_def = world.get::<CellDefinition>(instance.cell_definition);
+            let name = names
+                .entry(instance.cell_definition)
+                .or_insert_with(|| CellDefinition::name_of(world, instance.cell_definition).into())
+                .clone();
+
+            let mut ancestor = parent;
+            while let Some(index) = ancestor {
+                proxies[index].descendant_count += 1;
+                ancestor = parents[index];
+            }
+
+            let index = proxies.len();
+            parents.push(parent);
+            proxies.push(CellProxy {
+                instance: entity,
+                cell_definition: instance.cell_definition,
+                name,
+                display: cell_def
+                    .map(|cell_def| cell_def.display)
+                    .unwrap_or_default(),
+                depth,
+                descendant_count: 0,
+                instance_count: 0,
+            });
+
+            for child in instance.child_instances.iter().rev() {
+                stack.push((*child, depth + 1, Some(index)));
+            }
+        }
+
+        let mut counts: HashMap<Entity, usize> = HashMap::new();
+        for proxy in &proxies {
+            *counts.entry(proxy.cell_definition).or_default() += 1;
+        }
+        for proxy in &mut proxies {
+            proxy.instance_count = counts[&proxy.cell_definition];
+        }
+
+        proxies
+    }
+}
+
+/// Shared list of cell proxies, as returned by CellProxy::gather. Clones are
+/// cheap, and two lists are equal only if they are the same list, so that
+/// components do not compare every instance when they re-render.
+#[derive(Clone, Default)]
+pub struct CellProxyList(Rc<Vec<CellProxy>>);
+
+impl CellProxyList {
+    pub fn new(proxies: Vec<CellProxy>) -> Self {
+        Self(Rc::new(proxies))
+    }
+}
+
+impl PartialEq for CellProxyList {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Deref for CellProxyList {
+    type Target = [CellProxy];
+
+    fn deref(&self) -> &[CellProxy] {
+        &self.0
+    }
+}
@@ -1,13 +1,36 @@
+use std::fmt;
+use std::str::FromStr;
+
 use bevy_ecs::component::Component;
 use bevy_ecs::entity::Entity;
+use bevy_ecs::world::World;
+use geo::AffineOps;
 use geo::AffineTransform;
+use geo::BoundingRect;
+use geo::Contains;
 
 use crate::core::triangulation::Triangulation;
 use crate::graphics::bounds::BoundingBox;
 use crate::graphics::material::Material;
 use crate::graphics::mesh::Mesh;
+use crate::graphics::text::HorizontalAlign;
+use crate::graphics::text::VerticalAlign;
 use crate::graphics::vectors::*;
 
+/// Render orders at or above this value are reserved for overlays, which are
+/// drawn on top of every layer.
+pub const OVERLAY_RENDER_ORDER: i32 = i32::MAX - 1024;
+
+/// Render order of backdrops such as the grid, which are drawn beneath every
+/// layer.
+pub const BACKGROUND_RENDER_ORDER: i32 = i32::MIN;
+
+/// Render orders from this value up to OVERLAY_RENDER_ORDER are reserved for
+/// the minimap. They are above every layer, since layer render orders come
+/// from 16 bit layer numbers and datatypes, so the minimap can draw its
+/// layers and its own marks with one range.
+pub const MINIMAP_RENDER_ORDER: i32 = OVERLAY_RENDER_ORDER - 16;
+
 #[derive(Component)]
 pub struct Selected;
 
@@ -23,15 +46,71 @@ pub struct Hovered;
 #[require(CellInstance)]
 pub struct RootCellInstance;
 
+/// A mesh that holds the triangles of one spatial tile of a cell definition
+/// on one layer, in the cell's local coordinates. It is drawn once per
+/// instance of the cell.
+///
+/// Every tile has a detailed mesh and a density quad; the renderer picks one
+/// based on how large the tile's shapes appear on screen.
 #[derive(Component)]
 #[require(Mesh)]
-pub struct LayerMesh;
+pub struct LayerMesh {
+    pub layer: Entity,
+
+    /// Bounds of the tile in the cell's local coordinates.
+    pub local_bounds: BoundingBox,
+
+    /// Point in the cell's local coordinates that the vertices are relative
+    /// to, which is the center of the tile.
+    pub origin: Point2d,
+
+    /// Typical extent of the tile's shapes in the cell's local coordinates.
+    pub feature_size: f64,
+
+    /// Fraction of the layer color applied to this mesh. For density quads,
+    /// this is the fraction of the tile covered by shapes.
+    pub opacity: f32,
+}
 
 #[derive(Component)]
 pub struct CellDefinition {
     pub name: String,
     pub shape_defs: Vec<Entity>,
+    pub text_defs: Vec<Entity>,
     pub cell_refs: Vec<CellReference>,
+
+    /// The LayerMesh tiles of every layer that this cell has shapes on.
+    pub meshes: Vec<Entity>,
+
+    /// How instances of this cell are drawn. Set it with
+    /// `cell_display::set_cell_display` so that the instance tree follows.
+    pub display: CellDisplay,
+}
+
+impl CellDefinition {
+    /// Returns the name of the given cell definition, or an empty string if
+    /// the entity is not one.
+    pub fn name_of(world: &World, cell_definition: Entity) -> String {
+        world
+            .get::<CellDefinition>(cell_definition)
+            .map(|cell_def| cell_def.name.clone())
+            .unwrap_or_default()
+    }
+}
+
+/// How the instances of a cell definition are drawn, picked and exported.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CellDisplay {
+    /// The cell's shapes and descendants are drawn as usual.
+    #[default]
+    Contents,
+
+    /// Neither the cell's shapes nor those of its descendants are drawn.
+    Hidden,
+
+    /// The cell is drawn as an outlined box with its name, in place of its
+    /// shapes and descendants.
+    Abstract,
 }
 
 #[derive(Component)]
@@ -41,9 +120,25 @@ pub struct CellInstance {
     /// Must have same length as CellDefinition::shape_defs
     pub shape_instances: Vec<Entity>,
 
-    /// Must have same length as CellDefinition::cell_refs
+    /// Must have same length as CellDefinition::text_defs
+    pub text_instances: Vec<Entity>,
+
+    /// One per CellDefinition::cell_refs, except for references that the
+    /// Instancer skipped because they form a cycle or exceed the depth or
+    /// instance limit.
     pub child_instances: Vec<Entity>,
 
+    /// The instance whose cell references this one, or None for the root.
+    pub parent: Option<Entity>,
+
+    /// Number of ancestors between this instance and the root.
+    pub depth: usize,
+
+    /// True if this instance or one of its ancestors is hidden or abstract,
+    /// or if its depth is outside the HierarchyLevels range, in which case
+    /// its shapes and texts are neither drawn nor picked.
+    pub concealed: bool,
+
     /// Transforms this cell's coord system to the root coord system.
     pub world_transform: AffineTransform,
     // NOTE: consider storing a GeometryRange here for fast VBO updates.
@@ -60,22 +155,105 @@ pub struct ShapeDefinition {
 /// This component is referenced by the R-tree that we use for fast spatial
 /// lookups. Each node in the tree has:
 /// - this entity id
-/// - the aabb of the world_polygon
-/// - a copy of the layer index
+/// - the aabb of the world_bounds
+///
+/// The world space outline is not stored, since a cell can be placed
+/// millions of times. It is computed on demand from the ShapeDefinition and
+/// the transform of the owning CellInstance.
 #[derive(Component)]
 pub struct ShapeInstance {
     pub cell_instance: Entity,
-    pub world_polygon: Polygon,
-    pub layer_index: i16,
+    pub shape_definition: Entity,
+    pub layer_key: LayerKey,
+    pub layer: Entity,
+}
+
+impl ShapeInstance {
+    /// Transforms the shape's coord system to the root coord system.
+    pub fn world_transform(&self, world: &World) -> AffineTransform {
+        world
+            .get::<CellInstance>(self.cell_instance)
+            .expect("CellInstance not found")
+            .world_transform
+    }
+
+    /// The shape's outline in world space.
+    pub fn world_polygon(&self, world: &World) -> Polygon {
+        let shape_def = world
+            .get::<ShapeDefinition>(self.shape_definition)
+            .expect("ShapeDefinition not found");
+        shape_def
+            .local_polygon
+            .affine_transform(&self.world_transform(world))
+    }
+
+    /// True if the world space point is inside the shape. The point is
+    /// mapped into the shape's coord system, so no outline is transformed.
+    pub fn contains(&self, world: &World, point: &geo::Point<f64>) -> bool {
+        let Some(inverse) = self.world_transform(world).inverse() else {
+            return false;
+        };
+        let shape_def = world
+            .get::<ShapeDefinition>(self.shape_definition)
+            .expect("ShapeDefinition not found");
+        shape_def
+            .local_polygon
+            .contains(&point.affine_transform(&inverse))
+    }
+
+    /// The world space bounds of the shape's local bounding box. These fit
+    /// the outline exactly unless the shape is rotated by an angle that is
+    /// not a multiple of 90 degrees.
+    pub fn world_bounds(&self, world: &World) -> BoundingBox {
+        let shape_def = world
+            .get::<ShapeDefinition>(self.shape_definition)
+            .expect("ShapeDefinition not found");
+        shape_def
+            .local_polygon
+            .bounding_rect()
+            .map_or_else(BoundingBox::new, BoundingBox::from)
+            .transform(&self.world_transform(world))
+    }
+}
+
+/// A GDS text element, which is typically a pin name or net label.
+#[derive(Component)]
+pub struct TextDefinition {
+    pub layer: Entity,
+    pub string: String,
+    pub texttype: i16,
+    pub presentation: TextPresentation,
+
+    /// Places the text anchor in the coordinate system of the owning cell.
+    pub local_transform: AffineTransform,
+}
+
+/// Labels are drawn in screen space, so only the anchor point of the world
+/// transform affects rendering.
+#[derive(Component)]
+pub struct TextInstance {
+    pub cell_instance: Entity,
+    pub text_definition: Entity,
     pub layer: Entity,
+    pub world_transform: AffineTransform,
+}
+
+/// Identifies a layer by its GDS layer number and datatype. Boxes, nodes and
+/// texts use their boxtype, nodetype and texttype as the datatype.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct LayerKey {
+    pub layer: i16,
+    pub datatype: i16,
 }
 
 #[derive(Component)]
 pub struct Layer {
-    pub index: i16,
+    pub key: LayerKey,
     pub color: Vector4f,
     pub visible: bool,
-    pub mesh: Entity,
+
+    /// The LayerMesh of every cell definition with shapes on this layer.
+    pub meshes: Vec<Entity>,
     pub world_bounds: BoundingBox,
     pub shape_instances: Vec<Entity>,
 }
@@ -88,12 +266,111 @@ pub struct LayerMaterial;
 #[derive(Clone)]
 pub struct CellReference {
     pub cell_definition: Entity,
+
+    /// Reflection, magnification, rotation and translation from the STRANS.
+    /// For an array, this places the first element.
     pub local_transform: AffineTransform,
+
+    /// If set, the parent's magnification does not apply to this reference.
+    pub abs_mag: bool,
+
+    /// If set, the parent's rotation does not apply to this reference.
+    pub abs_angle: bool,
+
+    /// The grid of an AREF. Its elements are only expanded when instanced,
+    /// so that large arrays cost no more than one reference until then.
+    pub array: Option<CellArray>,
+}
+
+impl CellReference {
+    /// Number of instances that the reference places.
+    pub fn element_count(&self) -> usize {
+        self.array
+            .map_or(1, |array| array.cols as usize * array.rows as usize)
+    }
+
+    /// Local transform of the element at the given index, counting along
+    /// each row first.
+    pub fn element_transform(&self, index: usize) -> AffineTransform {
+        let Some(array) = self.array else {
+            return self.local_transform;
+        };
+        let col = (index % array.cols as usize) as f64;
+        let row = (index / array.cols as usize) as f64;
+        let offset = array.col_step * col + array.row_step * row;
+        self.local_transform
+            .compose(&AffineTransform::translate(offset.x, offset.y))
+    }
+}
+
+/// Columns and rows of an array reference, with the displacement between
+/// neighboring elements in the parent's coordinates.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CellArray {
+    pub cols: u16,
+    pub rows: u16,
+    pub col_step: Vector2d,
+    pub row_step: Vector2d,
+}
+
+/// Font and justification from the GDS PRESENTATION record.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct TextPresentation {
+    pub font: u8,
+    pub horizontal: HorizontalAlign,
+    pub vertical: VerticalAlign,
 }
 
 pub enum ShapeType {
     Polygon(Vec<Point2d>),
-    Path { width: f64, spine: Vec<Point2d> },
+    Path {
+        width: f64,
+        spine: Vec<Point2d>,
+    },
+
+    /// A GDS BOX, which is drawn as a filled polygon.
+    Box(Vec<Point2d>),
+
+    /// A GDS NODE, which describes electrical connectivity and is drawn as
+    /// an outline.
+    Node(Vec<Point2d>),
+}
+
+impl LayerKey {
+    pub fn new(layer: i16, datatype: i16) -> Self {
+        Self { layer, datatype }
+    }
+
+    /// Layers are drawn in order of layer number, then datatype.
+    pub fn render_order(&self) -> i32 {
+        ((self.layer as i32) << 16) + self.datatype as i32
+    }
+}
+
+impl fmt::Display for LayerKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.layer, self.datatype)
+    }
+}
+
+/// Parses the "layer/datatype" form written by Display.
+impl FromStr for LayerKey {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (layer, datatype) = s
+            .split_once('/')
+            .ok_or_else(|| format!("Expected layer/datatype, got '{}'", s))?;
+        let layer = layer
+            .trim()
+            .parse()
+            .map_err(|_| format!("Bad layer in '{}'", s))?;
+        let datatype = datatype
+            .trim()
+            .parse()
+            .map_err(|_| format!("Bad datatype in '{}'", s))?;
+        Ok(Self::new(layer, datatype))
+    }
 }
 
 impl Default for CellInstance {
@@ -101,7 +378,11 @@ impl Default for CellInstance {
         Self {
             cell_definition: Entity::PLACEHOLDER,
             shape_instances: Default::default(),
+            text_instances: Default::default(),
             child_instances: Default::default(),
+            parent: None,
+            depth: 0,
+            concealed: false,
             world_transform: Default::default(),
         }
     }
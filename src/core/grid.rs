@@ -0,0 +1,33 @@
+use crate::core::units::format_length;
+
+/// Returns the smallest length of the form 1, 2 or 5 times a power of ten
+/// that is at least `min`, which must be positive.
+pub fn nice_length_at_least(min: f64) -> f64 {
+    let power = 10f64.powf(min.log10().floor());
+    [1.0, 2.0, 5.0, 10.0]
+        .into_iter()
+        .map(|step| step * power)
+        .find(|length| *length >= min * (1.0 - 1e-9))
+        .unwrap_or(10.0 * power)
+}
+
+/// Returns the largest length of the form 1, 2 or 5 times a power of ten
+/// that is at most `max`, which must be positive.
+pub fn nice_length_at_most(max: f64) -> f64 {
+    let power = 10f64.powf(max.log10().floor());
+    [5.0, 2.0, 1.0]
+        .into_iter()
+        .map(|step| step * power)
+        .find(|length| *length <= max * (1.0 + 1e-9))
+        .unwrap_or(power)
+}
+
+/// Formats a length in microns for display, switching to millimeters from
+/// one millimeter up.
+pub fn format_microns(microns: f64) -> String {
+    if microns >= 1000.0 {
+        format!("{} mm", format_length(microns / 1000.0, 4))
+    } else {
+        format!("{} µm", format_length(microns, 4))
+    }
+}
@@ -0,0 +1,222 @@
+use bevy_ecs::entity::Entity;
+use bevy_ecs::world::World;
+
+use crate::core::grid::format_microns;
+use crate::core::grid::nice_length_at_least;
+use crate::core::grid::nice_length_at_most;
+use crate::core::units::LibraryUnits;
+use crate::graphics::camera::Camera;
+use crate::graphics::geometry::Geometry;
+use crate::graphics::material::BlendMode;
+use crate::graphics::material::Material;
+use crate::graphics::mesh::Mesh;
+use crate::graphics::ribbon::Ribbon;
+use crate::graphics::text::HorizontalAlign;
+use crate::graphics::text::Label;
+use crate::graphics::text::TextBatch;
+use crate::graphics::text::VerticalAlign;
+use crate::graphics::vectors::*;
+
+/// Grid lines are never closer together than this, in physical pixels.
+const MIN_GRID_SPACING_PX: f64 = 24.0;
+
+/// Width of the grid lines in physical pixels.
+const GRID_LINE_WIDTH_PX: f64 = 1.0;
+
+/// The scale bar is at most this long, in physical pixels.
+const MAX_SCALE_BAR_PX: f64 = 120.0;
+
+/// Distance of the scale bar from the bottom left corner of the window, and
+/// the size of its end ticks and line, in physical pixels.
+const SCALE_BAR_MARGIN_PX: f64 = 24.0;
+const SCALE_BAR_TICK_PX: f64 = 6.0;
+const SCALE_BAR_WIDTH_PX: f64 = 2.0;
+
+/// Height of the scale bar label in physical pixels.
+const LABEL_HEIGHT_PX: f64 = 12.0;
+
+/// Draws a background grid whose spacing follows the zoom level, and a
+/// labelled scale bar in the bottom left corner of the window.
+pub struct GridOverlay {
+    grid_mesh: Entity,
+    scale_bar: Ribbon,
+    scale_label: TextBatch,
+    grid_visible: bool,
+}
+
+impl GridOverlay {
+    pub fn new(world: &mut World) -> Self {
+        let mut material = Material::default();
+        material.set_blending(BlendMode::SourceOver);
+        let material = world.spawn(material).id();
+
+        let geometry = world.spawn(Geometry::new()).id();
+        let mut mesh = Mesh::new(geometry, material);
+        mesh.visible = false;
+
+        let mut scale_bar = Ribbon::new(world);
+        scale_bar.closed = false;
+
+        Self {
+            grid_mesh: world.spawn(mesh).id(),
+            scale_bar,
+            scale_label: TextBatch::new(world),
+            grid_visible: true,
+        }
+    }
+
+    /// The grid is drawn at `grid_order`, which should be below every layer,
+    /// and the scale bar at `scale_bar_order`.
+    pub fn set_render_order(&self, world: &mut World, grid_order: i32, scale_bar_order: i32) {
+        let mut mesh = world.get_mut::<Mesh>(self.grid_mesh).unwrap();
+        mesh.render_order = grid_order;
+        self.scale_bar.set_render_order(world, scale_bar_order);
+        self.scale_label
+            .set_render_order(world, scale_bar_order + 1);
+    }
+
+    pub fn set_colors(&self, world: &mut World, grid_color: Vector4f, scale_bar_color: Vector4f) {
+        let mut mesh = world.get_mut::<Mesh>(self.grid_mesh).unwrap();
+        mesh.set_vec4("color", grid_color);
+        self.scale_bar.set_color(world, scale_bar_color);
+        self.scale_label.set_color(world, scale_bar_color);
+    }
+
+    pub fn set_grid_visible(&mut self, visible: bool) {
+        self.grid_visible = visible;
+    }
+
+    pub fn grid_visible(&self) -> bool {
+        self.grid_visible
+    }
+
+    /// Rebuilds the grid and scale bar for the current camera.
+    pub fn update(
+        &mut self,
+        world: &mut World,
+        gl: &glow::Context,
+        camera: &Camera,
+        window_size: (u32, u32),
+    ) {
+        let pixel_size = camera.width / window_size.0 as f64;
+        if pixel_size <= 0.0 || !pixel_size.is_finite() {
+            return;
+        }
+        let units = world
+            .get_resource::<LibraryUnits>()
+            .copied()
+            .unwrap_or_default();
+
+        self.update_grid(world, gl, camera, pixel_size, &units);
+        self.update_scale_bar(world, gl, camera, pixel_size, &units);
+    }
+
+    fn update_grid(
+        &mut self,
+        world: &mut World,
+        gl: &glow::Context,
+        camera: &Camera,
+        pixel_size: f64,
+        units: &LibraryUnits,
+    ) {
+        if !self.grid_visible {
+            let mut mesh = world.get_mut::<Mesh>(self.grid_mesh).unwrap();
+            mesh.visible = false;
+            return;
+        }
+
+        let spacing = nice_length_at_least(units.to_microns(MIN_GRID_SPACING_PX * pixel_size));
+        let spacing = units.from_microns(spacing);
+        let bounds = camera.world_bounds();
+        let half_width = 0.5 * GRID_LINE_WIDTH_PX * pixel_size;
+
+        // Vertices are relative to the first grid intersection in view.
+        let origin = Point2d::new(
+            (bounds.min_x / spacing).floor() * spacing,
+            (bounds.min_y / spacing).floor() * spacing,
+        );
+
+        let mut geometry = Geometry::new();
+        let mut push_quad = |min: Point2d, max: Point2d| {
+            let base = geometry.positions.len() as u32 / 3;
+            for (x, y) in [
+                (min.x, min.y),
+                (max.x, min.y),
+                (max.x, max.y),
+                (min.x, max.y),
+            ] {
+                geometry.positions.push((x - origin.x) as f32);
+                geometry.positions.push((y - origin.y) as f32);
+                geometry.positions.push(0.0);
+            }
+            geometry.indices.extend_from_slice(&[
+                base,
+                base + 1,
+                base + 2,
+                base,
+                base + 2,
+                base + 3,
+            ]);
+        };
+
+        let mut x = origin.x;
+        while x <= bounds.max_x {
+            push_quad(
+                Point2d::new(x - half_width, bounds.min_y),
+                Point2d::new(x + half_width, bounds.max_y),
+            );
+            x += spacing;
+        }
+        let mut y = origin.y;
+        while y <= bounds.max_y {
+            push_quad(
+                Point2d::new(bounds.min_x, y - half_width),
+                Point2d::new(bounds.max_x, y + half_width),
+            );
+            y += spacing;
+        }
+
+        let mut mesh = world.get_mut::<Mesh>(self.grid_mesh).unwrap();
+        mesh.visible = true;
+        mesh.origin = origin;
+        let geometry_entity = mesh.geometry;
+        geometry.replace(world, gl, geometry_entity);
+    }
+
+    fn update_scale_bar(
+        &mut self,
+        world: &mut World,
+        gl: &glow::Context,
+        camera: &Camera,
+        pixel_size: f64,
+        units: &LibraryUnits,
+    ) {
+        let microns = nice_length_at_most(units.to_microns(MAX_SCALE_BAR_PX * pixel_size));
+        let length = units.from_microns(microns);
+
+        let right = camera.right();
+        let right = Vector2d::new(right.x, right.y);
+        let up = Vector2d::new(camera.up.x, camera.up.y);
+        let corner = camera.unproject(Point3d::new(-1.0, -1.0, 0.0));
+        let margin = SCALE_BAR_MARGIN_PX * pixel_size;
+        let start = Point2d::new(corner.x, corner.y) + (right + up) * margin;
+        let end = start + right * length;
+        let tick = up * (SCALE_BAR_TICK_PX * pixel_size);
+
+        self.scale_bar.width = SCALE_BAR_WIDTH_PX * pixel_size;
+        self.scale_bar.spine = vec![start + tick, start, end, end + tick];
+        self.scale_bar.update(world, gl);
+
+        self.scale_label.labels.clear();
+        self.scale_label.labels.push(Label {
+            text: format_microns(microns),
+            position: nalgebra::center(&start, &end) + tick * 0.5,
+            horizontal: HorizontalAlign::Center,
+            vertical: VerticalAlign::Bottom,
+        });
+        self.scale_label.right = right;
+        self.scale_label.up = up;
+        self.scale_label.height = LABEL_HEIGHT_PX * pixel_size;
+        self.scale_label.update(world, gl);
+    }
+}
@@ -76,10 +76,11 @@ impl HoverEffect {
         }: HoverParams,
     ) {
         let shape_instance = world.get::<ShapeInstance>(shape_instance).unwrap();
-        let triangles = shape_instance.world_polygon.earcut_triangles_raw();
+        let world_polygon = shape_instance.world_polygon(world);
+        let triangles = world_polygon.earcut_triangles_raw();
 
         let mut points = Vec::new();
-        for coord in shape_instance.world_polygon.exterior().points() {
+        for coord in world_polygon.exterior().points() {
             points.push(Point2d::new(coord.x(), coord.y()));
         }
 
@@ -87,7 +88,7 @@ impl HoverEffect {
         let mut color = layer.color;
         color.w *= 0.1;
 
-        self.stroke.spine = points;
+        self.stroke.spine = points.clone();
         self.stroke.update(world, gl);
 
         let mut geometry = Geometry::new();
@@ -95,9 +96,11 @@ impl HoverEffect {
         geometry.positions.reserve(3 * triangles.vertices.len() / 2);
         geometry.indices.reserve(triangles.triangle_indices.len());
 
+        // Vertices are relative to the first point of the polygon.
+        let origin = points.first().copied().unwrap_or_else(Point2d::origin);
         for coord in triangles.vertices.chunks(2) {
-            geometry.positions.push(coord[0] as f32);
-            geometry.positions.push(coord[1] as f32);
+            geometry.positions.push((coord[0] - origin.x) as f32);
+            geometry.positions.push((coord[1] - origin.y) as f32);
             geometry.positions.push(0.0);
         }
 
@@ -107,6 +110,7 @@ impl HoverEffect {
 
         let mut mesh = world.get_mut::<Mesh>(self.fill_mesh).unwrap();
         mesh.visible = true;
+        mesh.origin = origin;
         mesh.set_vec4("color", color);
         let geometry_entity = mesh.geometry;
         geometry.replace(world, gl, geometry_entity);
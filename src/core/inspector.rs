@@ -0,0 +1,179 @@
+use std::fmt;
+
+use bevy_ecs::entity::Entity;
+use bevy_ecs::world::World;
+use geo::AffineTransform;
+use geo::Area;
+use geo::BoundingRect;
+
+use crate::core::components::CellDefinition;
+use crate::core::components::CellInstance;
+use crate::core::components::LayerKey;
+use crate::core::components::ShapeDefinition;
+use crate::core::components::ShapeInstance;
+use crate::core::components::ShapeType;
+use crate::core::units::LibraryUnits;
+use crate::graphics::bounds::BoundingBox;
+
+/// The kind of GDS element that a shape was loaded from.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ShapeKind {
+    Polygon,
+
+    Path {
+        /// In database units.
+        width: f64,
+    },
+    Box,
+    Node,
+}
+
+/// Everything the inspector shows about a shape instance. Lengths are in
+/// database units; use `rows` to format them in microns.
+#[derive(Clone, Debug)]
+pub struct ShapeReport {
+    pub layer_key: LayerKey,
+    pub kind: ShapeKind,
+
+    /// Number of points in the shape definition. For paths, this counts the
+    /// points of the spine.
+    pub vertex_count: usize,
+
+    /// Area of the shape in the root coordinate system.
+    pub area: f64,
+    pub world_bounds: BoundingBox,
+
+    /// Name of the cell definition that owns the shape.
+    pub cell_name: String,
+
+    /// Names of the cell definitions of every instance from the root down to
+    /// the instance that owns the shape.
+    pub instance_path: Vec<String>,
+
+    /// Transforms the owning cell's coord system to the root coord system.
+    pub world_transform: AffineTransform,
+}
+
+impl ShapeReport {
+    /// Gathers a report for the given ShapeInstance entity, or returns None if
+    /// the entity or any of its definitions is missing.
+    pub fn new(world: &World, shape_instance: Entity) -> Option<Self> {
+        let shape = world.get::<ShapeInstance>(shape_instance)?;
+        let shape_definition = world.get::<ShapeDefinition>(shape.shape_definition)?;
+        let cell_instance = world.get::<CellInstance>(shape.cell_instance)?;
+
+        let (kind, vertex_count) = match &shape_definition.shape_type {
+            ShapeType::Polygon(points) => (ShapeKind::Polygon, points.len()),
+            ShapeType::Path { width, spine } => (ShapeKind::Path { width: *width }, spine.len()),
+            ShapeType::Box(points) => (ShapeKind::Box, points.len()),
+            ShapeType::Node(points) => (ShapeKind::Node, points.len()),
+        };
+
+        let world_polygon = shape.world_polygon(world);
+        let world_bounds = world_polygon
+            .bounding_rect()
+            .map(BoundingBox::from)
+            .unwrap_or_default();
+
+        let mut instance_path = Vec::new();
+        let mut current = Some(shape.cell_instance);
+        while let Some(entity) = current {
+            let instance = world.get::<CellInstance>(entity)?;
+            instance_path.push(CellDefinition::name_of(world, instance.cell_definition));
+            current = instance.parent;
+        }
+        instance_path.reverse();
+
+        Some(Self {
+            layer_key: shape.layer_key,
+            kind,
+            vertex_count,
+            area: world_polygon.unsigned_area(),
+            world_bounds,
+            cell_name: CellDefinition::name_of(world, cell_instance.cell_definition),
+            instance_path,
+            world_transform: cell_instance.world_transform,
+        })
+    }
+
+    /// Formats the report as label and value pairs, with lengths in microns.
+    pub fn rows(&self, units: &LibraryUnits) -> Vec<(String, String)> {
+        let microns = |value: f64| round_microns(units.to_microns(value));
+        let bounds = units.bounds_to_microns(&self.world_bounds);
+        let (min_x, min_y) = (round_microns(bounds.min_x), round_microns(bounds.min_y));
+        let (max_x, max_y) = (round_microns(bounds.max_x), round_microns(bounds.max_y));
+        let kind = match self.kind {
+            ShapeKind::Polygon => "Polygon".to_string(),
+            ShapeKind::Path { width } => format!("Path, {} µm wide", microns(width)),
+            ShapeKind::Box => "Box".to_string(),
+            ShapeKind::Node => "Node".to_string(),
+        };
+        vec![
+            ("Layer".to_string(), self.layer_key.to_string()),
+            ("Type".to_string(), kind),
+            ("Vertices".to_string(), self.vertex_count.to_string()),
+            (
+                "Area".to_string(),
+                format!("{} µm²", units.to_microns(units.to_microns(self.area))),
+            ),
+            (
+                "Bounds".to_string(),
+                format!("({}, {}) to ({}, {}) µm", min_x, min_y, max_x, max_y),
+            ),
+            ("Cell".to_string(), self.cell_name.clone()),
+            ("Path".to_string(), self.instance_path.join(" / ")),
+            (
+                "Transform".to_string(),
+                TransformSummary::new(&self.world_transform, units).to_string(),
+            ),
+        ]
+    }
+}
+
+/// Describes an affine transform as the GDS STRANS terms that produce it.
+struct TransformSummary {
+    mirrored: bool,
+    angle: f64,
+    magnification: f64,
+
+    /// Translation in microns.
+    offset: (f64, f64),
+}
+
+impl TransformSummary {
+    fn new(transform: &AffineTransform, units: &LibraryUnits) -> Self {
+        let det = transform.a() * transform.e() - transform.b() * transform.d();
+        Self {
+            mirrored: det < 0.0,
+            angle: transform.d().atan2(transform.a()).to_degrees(),
+            magnification: det.abs().sqrt(),
+            offset: (
+                round_microns(units.to_microns(transform.xoff())),
+                round_microns(units.to_microns(transform.yoff())),
+            ),
+        }
+    }
+}
+
+impl fmt::Display for TransformSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "translate ({}, {}) µm", self.offset.0, self.offset.1)?;
+        if self.angle.abs() > 1e-9 {
+            write!(f, ", rotate {}°", self.angle)?;
+        }
+        if (self.magnification - 1.0).abs() > 1e-9 {
+            write!(f, ", magnify {}", self.magnification)?;
+        }
+        if self.mirrored {
+            write!(f, ", mirrored")?;
+        }
+        Ok(())
+    }
+}
+
+/// Rounds a length in microns to a picometre, which is finer than any
+/// database unit, so that rounding noise from rotations is not shown.
+fn round_microns(value: f64) -> f64 {
+    // Adding zero turns -0 into 0.
+    (value * 1e6).round() / 1e6 + 0.0
+}
@@ -1,61 +1,342 @@
 use bevy_ecs::entity::Entity;
+use bevy_ecs::query::Or;
 use bevy_ecs::query::QueryState;
+use bevy_ecs::query::With;
 use bevy_ecs::system::lifetimeless::Read;
 use bevy_ecs::world::World;
-use geo::AffineOps;
 use geo::AffineTransform;
 use geo::BoundingRect;
+use geo::Coord;
 
+use crate::core::cell_display::apply_cell_display;
 use crate::core::components::CellDefinition;
 use crate::core::components::CellInstance;
+use crate::core::components::CellReference;
 use crate::core::components::Layer;
+use crate::core::components::LayerMesh;
 use crate::core::components::RootCellInstance;
 use crate::core::components::ShapeDefinition;
 use crate::core::components::ShapeInstance;
-use crate::core::triangulation::Triangulation;
+use crate::core::components::TextDefinition;
+use crate::core::components::TextInstance;
 use crate::graphics::bounds::BoundingBox;
 use crate::graphics::geometry::Geometry;
 use crate::graphics::mesh::Mesh;
-use crate::graphics::vectors::*;
+
+/// Nesting depth beyond which cell references are skipped. Real designs are
+/// rarely more than a few dozen levels deep.
+pub const DEFAULT_MAX_DEPTH: usize = 64;
+
+/// Number of cell instances beyond which cell references are skipped. Each
+/// instance costs a few hundred bytes, along with its shape instances.
+pub const DEFAULT_MAX_INSTANCES: usize = 5_000_000;
+
+/// Side of the square regions that the instances of a tile are grouped by,
+/// in world units. Each group costs a draw call, so groups are only as small
+/// as f32 precision needs: offsets within a group are at most about 1e6,
+/// which f32 resolves to a few hundredths of a unit. A 10 mm die at 1 nm
+/// units is covered by at most 100 groups per tile.
+pub const INSTANCE_GROUP_SIZE: f64 = 1_000_000.0;
+
+/// Bounds the size of the instance tree, so that deep or heavily arrayed
+/// hierarchies cannot exhaust memory.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct InstanceLimits {
+    /// Number of nested cell references to follow below the root.
+    pub max_depth: usize,
+
+    /// Number of cell instances to create, including the root.
+    pub max_instances: usize,
+}
+
+impl Default for InstanceLimits {
+    fn default() -> Self {
+        Self {
+            max_depth: DEFAULT_MAX_DEPTH,
+            max_instances: DEFAULT_MAX_INSTANCES,
+        }
+    }
+}
+
+impl InstanceLimits {
+    /// Reads the `max_depth` and `max_instances` URL query parameters. Limits
+    /// that are missing or malformed keep their defaults.
+    pub fn from_query(query: &[(String, String)]) -> Self {
+        let mut limits = Self::default();
+        for (key, value) in query {
+            match key.as_str() {
+                "max_depth" => {
+                    if let Ok(max_depth) = value.parse() {
+                        limits.max_depth = max_depth;
+                    }
+                }
+                "max_instances" => {
+                    if let Ok(max_instances) = value.parse() {
+                        limits.max_instances = max_instances;
+                    }
+                }
+                _ => {}
+            }
+        }
+        limits
+    }
+}
+
+/// A cell reference that was not instantiated.
+#[derive(Clone, Debug, PartialEq)]
+pub enum SkippedReference {
+    /// The reference closes a cycle. Lists the names of the cells that form
+    /// the cycle, starting and ending with the same cell.
+    Cycle(Vec<String>),
+
+    /// The reference is nested more deeply than the depth limit allows.
+    DepthLimit { parent: String, name: String },
+
+    /// The tree already holds as many instances as the instance limit
+    /// allows. Only the first such reference is recorded, since the rest of
+    /// the traversal is abandoned.
+    InstanceLimit { parent: String, name: String },
+}
 
 /// Creates instance entities from definition entities.
 pub struct Instancer {
     root_query: QueryState<(Entity, Read<RootCellInstance>)>,
+    limits: InstanceLimits,
+    skipped_references: Vec<SkippedReference>,
+}
+
+/// A cell definition waiting to be instantiated, along with the instance
+/// that references it.
+struct PendingCell {
+    cell_definition: Entity,
+    transform: AffineTransform,
+    parent: Option<Entity>,
+    depth: usize,
+
+    /// For an element of an array, what is needed to queue the next element
+    /// once this one is instantiated.
+    array: Option<PendingArray>,
+}
+
+struct PendingArray {
+    cell_ref: CellReference,
+    parent_transform: AffineTransform,
+    index: usize,
+}
+
+impl PendingCell {
+    /// The element of a reference at the given index, below a parent
+    /// instance with the given world transform.
+    fn element(
+        cell_ref: &CellReference,
+        index: usize,
+        parent: Entity,
+        parent_transform: &AffineTransform,
+        depth: usize,
+    ) -> Self {
+        let array = cell_ref.array.map(|_| PendingArray {
+            cell_ref: cell_ref.clone(),
+            parent_transform: *parent_transform,
+            index,
+        });
+        PendingCell {
+            cell_definition: cell_ref.cell_definition,
+            transform: compose_with_parent(cell_ref, index, parent_transform),
+            parent: Some(parent),
+            depth,
+            array,
+        }
+    }
 }
 
 impl Instancer {
     pub fn new(world: &mut World) -> Self {
         Self {
             root_query: world.query(),
+            limits: InstanceLimits::default(),
+            skipped_references: Vec::new(),
         }
     }
 
+    /// Sets the number of nested cell references to follow below the root.
+    pub fn set_max_depth(&mut self, max_depth: usize) {
+        self.limits.max_depth = max_depth;
+    }
+
+    /// Sets the number of cell instances to create, including the root.
+    pub fn set_max_instances(&mut self, max_instances: usize) {
+        self.limits.max_instances = max_instances;
+    }
+
+    pub fn set_limits(&mut self, limits: InstanceLimits) {
+        self.limits = limits;
+    }
+
+    /// References that were skipped by the most recent call to select_root.
+    pub fn skipped_references(&self) -> &[SkippedReference] {
+        &self.skipped_references
+    }
+
     /// Selects a cell definition as the root of the instance tree, then
-    /// instantiates the entire tree of CellInstance entities.
+    /// instantiates the entire tree of CellInstance entities. Any existing
+    /// tree is cleared first.
+    ///
+    /// References that form a cycle or exceed the depth or instance limit
+    /// are skipped and recorded in skipped_references.
     pub fn select_root(&mut self, world: &mut World, cell_definition_id: Entity) {
-        let Some(cell_definition) = world.get::<CellDefinition>(cell_definition_id) else {
+        if world.get::<CellDefinition>(cell_definition_id).is_none() {
             panic!("Entity does not have a CellDefinition component");
-        };
+        }
 
         let existing_root = self.root_query.single(world);
         if existing_root.is_ok() {
-            panic!("Root cell instance already exists");
+            self.clear(world);
         }
 
+        let cell_definition = world.get::<CellDefinition>(cell_definition_id).unwrap();
         log::info!("Selecting {} as root.", cell_definition.name);
 
-        let identity = AffineTransform::identity();
-        let root = Instancer::instantiate(world, cell_definition_id, identity);
-        world.get_entity_mut(root).unwrap().insert(RootCellInstance);
+        self.skipped_references.clear();
+
+        // Depth-first traversal with an explicit stack. The path holds the
+        // cell definitions from the root down to the cell being instantiated,
+        // which is how cycles are detected.
+        let mut path: Vec<Entity> = Vec::new();
+        let mut stack = vec![PendingCell {
+            cell_definition: cell_definition_id,
+            transform: AffineTransform::identity(),
+            parent: None,
+            depth: 0,
+            array: None,
+        }];
+        let mut instance_count = 0;
+
+        while let Some(pending) = stack.pop() {
+            path.truncate(pending.depth);
+
+            if let Some(start) = path.iter().position(|e| *e == pending.cell_definition) {
+                let mut cells: Vec<String> = path[start..]
+                    .iter()
+                    .map(|e| CellDefinition::name_of(world, *e))
+                    .collect();
+                cells.push(CellDefinition::name_of(world, pending.cell_definition));
+                log::warn!("Skipping reference cycle: {}", cells.join(" -> "));
+                self.skipped_references.push(SkippedReference::Cycle(cells));
+                continue;
+            }
+
+            if pending.depth > self.limits.max_depth {
+                let parent = CellDefinition::name_of(world, *path.last().unwrap());
+                let name = CellDefinition::name_of(world, pending.cell_definition);
+                log::warn!(
+                    "Skipping reference from {} to {} beyond depth {}",
+                    parent,
+                    name,
+                    self.limits.max_depth
+                );
+                self.skipped_references
+                    .push(SkippedReference::DepthLimit { parent, name });
+                continue;
+            }
+
+            if pending.parent.is_some() && instance_count >= self.limits.max_instances {
+                let parent = CellDefinition::name_of(world, *path.last().unwrap());
+                let name = CellDefinition::name_of(world, pending.cell_definition);
+                log::warn!(
+                    "Skipping reference from {} to {} and all that follow, beyond {} instances",
+                    parent,
+                    name,
+                    self.limits.max_instances
+                );
+                self.skipped_references
+                    .push(SkippedReference::InstanceLimit { parent, name });
+                break;
+            }
+            instance_count += 1;
+
+            // The next element of an array waits below this one's subtree.
+            // Elements are expanded one at a time, so the instance limit
+            // applies before a large array takes up any memory.
+            if let (Some(array), Some(parent)) = (&pending.array, pending.parent) {
+                let next = array.index + 1;
+                if next < array.cell_ref.element_count() {
+                    stack.push(PendingCell::element(
+                        &array.cell_ref,
+                        next,
+                        parent,
+                        &array.parent_transform,
+                        pending.depth,
+                    ));
+                }
+            }
+
+            let (cell_instance_id, cell_refs) = Instancer::instantiate(
+                world,
+                pending.cell_definition,
+                pending.transform,
+                pending.parent,
+                pending.depth,
+            );
+
+            match pending.parent {
+                Some(parent) => {
+                    let mut parent = world.get_mut::<CellInstance>(parent).unwrap();
+                    parent.child_instances.push(cell_instance_id);
+                }
+                None => {
+                    world
+                        .get_entity_mut(cell_instance_id)
+                        .unwrap()
+                        .insert(RootCellInstance);
+                }
+            }
+
+            path.push(pending.cell_definition);
+
+            // Push in reverse so that children are instantiated in order.
+            for cell_ref in cell_refs.iter().rev() {
+                stack.push(PendingCell::element(
+                    cell_ref,
+                    0,
+                    cell_instance_id,
+                    &pending.transform,
+                    pending.depth + 1,
+                ));
+            }
+        }
+
+        // Flag the concealed instances and add the others to the meshes.
+        apply_cell_display(world);
     }
 
-    /// Recursively creates cell instances and returns the instance corresponding
-    /// to the given cell_definition_id.
+    /// Despawns all cell, shape and text instances, and empties the instance
+    /// transforms and bounds of every layer. Definitions are left untouched.
+    pub fn clear(&mut self, world: &mut World) {
+        let instances: Vec<Entity> = world
+            .query_filtered::<Entity, Or<(With<CellInstance>, With<ShapeInstance>, With<TextInstance>)>>()
+            .iter(world)
+            .collect();
+        for entity in instances {
+            world.despawn(entity);
+        }
+
+        for mut layer in world.query::<&mut Layer>().iter_mut(world) {
+            layer.shape_instances.clear();
+            layer.world_bounds = BoundingBox::new();
+        }
+        clear_mesh_instances(world);
+    }
+
+    /// Creates a cell instance along with its shape and text instances. Child
+    /// instances are left for the caller to create from the returned cell
+    /// references, and mesh instances are added once the tree is complete.
     fn instantiate(
         world: &mut World,
         cell_definition_id: Entity,
         transform: AffineTransform,
-    ) -> Entity {
+        parent: Option<Entity>,
+        depth: usize,
+    ) -> (Entity, Vec<CellReference>) {
         let Some(cell_definition) = world.get::<CellDefinition>(cell_definition_id) else {
             panic!("Entity does not have a CellDefinition component");
         };
@@ -63,28 +344,41 @@ impl Instancer {
         // Phase 1: Gathering (immutable access to world)
 
         struct ShapePrototype {
+            shape_definition: Entity,
             layer: Entity,
-            world_polygon: Polygon,
-            world_triangles: Triangulation,
+            world_bounds: BoundingBox,
         }
 
         let mut shape_prototypes = Vec::new();
-        for shape_def in &cell_definition.shape_defs {
-            let shape_def = world.get::<ShapeDefinition>(*shape_def);
+        for shape_def_id in &cell_definition.shape_defs {
+            let shape_def = world.get::<ShapeDefinition>(*shape_def_id);
             let Some(shape_def) = shape_def else {
                 log::error!("Shape definition not found");
                 continue;
             };
             let layer = shape_def.layer;
-            let world_polygon = shape_def.local_polygon.affine_transform(&transform);
-            let world_triangles = shape_def.local_triangles.affine_transform(&transform);
+            let world_bounds = shape_def
+                .local_polygon
+                .bounding_rect()
+                .map_or_else(BoundingBox::new, BoundingBox::from)
+                .transform(&transform);
             shape_prototypes.push(ShapePrototype {
+                shape_definition: *shape_def_id,
                 layer,
-                world_polygon,
-                world_triangles,
+                world_bounds,
             });
         }
 
+        let mut text_prototypes = Vec::new();
+        for text_def_id in &cell_definition.text_defs {
+            let Some(text_def) = world.get::<TextDefinition>(*text_def_id) else {
+                log::error!("Text definition not found");
+                continue;
+            };
+            let world_transform = text_def.local_transform.compose(&transform);
+            text_prototypes.push((*text_def_id, text_def.layer, world_transform));
+        }
+
         let shape_prototypes = shape_prototypes;
         let cell_prototypes = cell_definition.cell_refs.clone();
 
@@ -95,41 +389,39 @@ impl Instancer {
 
         let mut shape_instances = Vec::with_capacity(shape_prototypes.len());
         for prototype in shape_prototypes {
-            let layer = world.get_mut::<Layer>(prototype.layer).unwrap();
-            let layer_index = layer.index;
-            let mesh = layer.mesh;
-            let bbox = prototype.world_polygon.bounding_rect();
+            let layer_key = world.get::<Layer>(prototype.layer).unwrap().key;
             let shape_instance = ShapeInstance {
                 cell_instance: cell_instance_id,
-                world_polygon: prototype.world_polygon,
-                layer_index,
+                shape_definition: prototype.shape_definition,
+                layer_key,
                 layer: prototype.layer,
             };
             let shape_instance_id = world.spawn(shape_instance).id();
             shape_instances.push(shape_instance_id);
             let mut layer = world.get_mut::<Layer>(prototype.layer).unwrap();
             layer.shape_instances.push(shape_instance_id);
-            if let Some(bbox) = bbox {
-                let bbox = BoundingBox::from(bbox);
-                layer.world_bounds.encompass(&bbox);
-            }
-            let geo = world.get::<Mesh>(mesh).unwrap().geometry;
-            let mut geo = world.get_mut::<Geometry>(geo).unwrap();
-            prototype.world_triangles.append_to(&mut geo);
+            layer.world_bounds.encompass(&prototype.world_bounds);
         }
 
-        let mut child_instances = Vec::with_capacity(cell_prototypes.len());
-        for cell_ref in cell_prototypes {
-            let transform = cell_ref.local_transform.compose(&parent_transform);
-            let child_definition = cell_ref.cell_definition;
-            let child = Instancer::instantiate(world, child_definition, transform);
-            child_instances.push(child);
+        let mut text_instances = Vec::with_capacity(text_prototypes.len());
+        for (text_definition, layer, world_transform) in text_prototypes {
+            let text_instance = TextInstance {
+                cell_instance: cell_instance_id,
+                text_definition,
+                layer,
+                world_transform,
+            };
+            text_instances.push(world.spawn(text_instance).id());
         }
 
         let cell_instance = CellInstance {
             cell_definition: cell_definition_id,
             shape_instances,
-            child_instances,
+            text_instances,
+            child_instances: Vec::with_capacity(cell_prototypes.len()),
+            parent,
+            depth,
+            concealed: false,
             world_transform: parent_transform,
         };
 
@@ -138,6 +430,113 @@ impl Instancer {
             .unwrap()
             .insert(cell_instance);
 
-        cell_instance_id
+        (cell_instance_id, cell_prototypes)
+    }
+}
+
+/// Adds an instance with the given transform to each of the given LayerMesh
+/// tiles, growing their bounds and level of detail to match. Instances are
+/// grouped by where they land, so that each group can be culled on its own
+/// and keeps its instance data small.
+pub(crate) fn add_mesh_instances(
+    world: &mut World,
+    meshes: &[Entity],
+    transform: &AffineTransform,
+) {
+    let scale = (transform.a() * transform.e() - transform.b() * transform.d())
+        .abs()
+        .sqrt();
+
+    for &mesh in meshes {
+        let layer_mesh = world.get::<LayerMesh>(mesh).unwrap();
+        let local_bounds = layer_mesh.local_bounds;
+        let feature_size = layer_mesh.feature_size * scale;
+
+        // The tile's vertices are relative to its origin.
+        let origin = layer_mesh.origin;
+        let placement = AffineTransform::translate(origin.x, origin.y).compose(transform);
+
+        let geometry = world.get::<Mesh>(mesh).unwrap().geometry;
+        let [mut mesh, mut geometry] = world.entity_mut([mesh, geometry]);
+        let mut mesh = mesh.get_mut::<Mesh>().unwrap();
+        let mut geometry = geometry.get_mut::<Geometry>().unwrap();
+
+        let instance_bounds = local_bounds.transform(transform);
+        let bounds = mesh.bounds.get_or_insert_with(BoundingBox::new);
+        bounds.encompass(&instance_bounds);
+
+        // The largest instance decides when the tile shows full detail.
+        let lod = mesh.level_of_detail;
+        if lod.feature_size().is_some_and(|size| feature_size > size) {
+            mesh.level_of_detail = lod.with_feature_size(feature_size);
+        }
+
+        let key = (
+            (placement.xoff() / INSTANCE_GROUP_SIZE).floor() as i64,
+            (placement.yoff() / INSTANCE_GROUP_SIZE).floor() as i64,
+        );
+        geometry.push_instance(key, &placement, &instance_bounds);
+    }
+}
+
+/// Removes every instance from every LayerMesh tile and empties their bounds.
+pub(crate) fn clear_mesh_instances(world: &mut World) {
+    let mut meshes = Vec::new();
+    for layer in world.query::<&Layer>().iter(world) {
+        meshes.extend_from_slice(&layer.meshes);
     }
+    for mesh in meshes {
+        let mut mesh = world.get_mut::<Mesh>(mesh).unwrap();
+        mesh.bounds = Some(BoundingBox::new());
+        mesh.level_of_detail = mesh.level_of_detail.with_feature_size(0.0);
+        let geometry = mesh.geometry;
+        world
+            .get_mut::<Geometry>(geometry)
+            .unwrap()
+            .clear_instances();
+    }
+}
+
+/// Composes the local transform of an element of a cell reference with the
+/// world transform of its parent instance.
+///
+/// When the reference has an absolute magnification or angle, the parent's
+/// magnification or rotation is stripped before composing, so that only the
+/// local value takes effect. The parent's reflection and translation always
+/// apply.
+fn compose_with_parent(
+    cell_ref: &CellReference,
+    index: usize,
+    parent: &AffineTransform,
+) -> AffineTransform {
+    let local = cell_ref.element_transform(index);
+    if !cell_ref.abs_mag && !cell_ref.abs_angle {
+        return local.compose(parent);
+    }
+
+    // Decompose the parent's linear part as rotate * scale * reflect.
+    let det = parent.a() * parent.e() - parent.b() * parent.d();
+    let mag = det.abs().sqrt();
+    let angle = parent.d().atan2(parent.a()).to_degrees();
+
+    let mut linear = AffineTransform::identity();
+    if det < 0.0 {
+        linear = AffineTransform::scale(1.0, -1.0, Coord::zero());
+    }
+    if !cell_ref.abs_mag {
+        linear = linear.compose(&AffineTransform::scale(mag, mag, Coord::zero()));
+    }
+    if !cell_ref.abs_angle {
+        linear = linear.compose(&AffineTransform::rotate(angle, Coord::zero()));
+    }
+
+    // The reference's origin is placed by the full parent transform.
+    let origin = parent.apply(Coord {
+        x: local.xoff(),
+        y: local.yoff(),
+    });
+    let local_linear = AffineTransform::new(local.a(), local.b(), 0.0, local.d(), local.e(), 0.0);
+    let translate = AffineTransform::translate(origin.x, origin.y);
+
+    local_linear.compose(&linear).compose(&translate)
 }
@@ -1,6 +1,7 @@
 use bevy_ecs::entity::Entity;
 
 use crate::core::components::Layer;
+use crate::core::components::LayerKey;
 use crate::rsutils::colors::hex_to_rgb;
 use crate::rsutils::colors::rgb_to_hex;
 
@@ -8,7 +9,7 @@ use crate::rsutils::colors::rgb_to_hex;
 #[derive(Clone, PartialEq)]
 pub struct LayerProxy {
     pub entity: Entity,
-    pub index: i16,
+    pub key: LayerKey,
     pub visible: bool,
     pub opacity: f32,
     pub color: String,
@@ -19,7 +20,7 @@ impl LayerProxy {
     pub fn from_layer(entity: Entity, layer: &Layer) -> Self {
         Self {
             entity,
-            index: layer.index,
+            key: layer.key,
             visible: layer.visible,
             opacity: layer.color.w,
             color: rgb_to_hex(layer.color.x, layer.color.y, layer.color.z),
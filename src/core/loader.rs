@@ -1,28 +1,48 @@
+use crate::core::components::CellArray;
 use crate::core::components::CellDefinition;
 use crate::core::components::CellReference;
 use crate::core::components::Layer;
+use crate::core::components::LayerKey;
 use crate::core::components::LayerMaterial;
 use crate::core::components::LayerMesh;
 use crate::core::components::ShapeDefinition;
 use crate::core::components::ShapeType;
+use crate::core::components::TextDefinition;
+use crate::core::components::TextPresentation;
+use crate::core::path_outline::create_node_outline;
 use crate::core::path_outline::create_path_outline;
 use crate::core::path_outline::PathType;
+use crate::core::tiles;
+use crate::core::tiles::TileItem;
+use crate::core::tiles::TILE_VERTEX_BUDGET;
 use crate::core::triangulation::Triangulation;
+use crate::core::units::LibraryUnits;
 use crate::graphics::bounds::BoundingBox;
 use crate::graphics::geometry::Geometry;
+use crate::graphics::mesh::LevelOfDetail;
 use crate::graphics::mesh::Mesh;
+use crate::graphics::text::HorizontalAlign;
+use crate::graphics::text::VerticalAlign;
 use crate::graphics::vectors::*;
 use std::collections::BTreeMap;
+use std::fmt;
 
 use bevy_ecs::entity::Entity;
 use bevy_ecs::query::QueryState;
 use bevy_ecs::world::World;
+use gds21::GdsArrayRef;
 use gds21::GdsBoundary;
+use gds21::GdsBox;
 use gds21::GdsLibrary;
+use gds21::GdsNode;
 use gds21::GdsPath;
 use gds21::GdsPoint;
+use gds21::GdsStrans;
 use gds21::GdsStructRef;
+use gds21::GdsTextElem;
+use gds21::GdsUnits;
 use geo::AffineTransform;
+use geo::BoundingRect;
 use geo::Coord;
 use geo::LineString;
 use web_time::Instant;
@@ -31,17 +51,52 @@ type NameTable = BTreeMap<String, Entity>;
 
 const BUDGET_MS: u128 = 15;
 
+/// Width of the outline drawn for GDS nodes, relative to the node's extent.
+///
+/// Nodes have no width of their own. At 2%, the outline is a few pixels wide
+/// when the whole node fits in the window, and leaves the enclosed area
+/// visible. Outlines are at least one database unit wide so that tiny nodes
+/// do not triangulate to slivers.
+const NODE_OUTLINE_FRACTION: f64 = 0.02;
+
 pub struct Progress {
     phase: String,
     percent: f32,
     world: Option<World>,
 }
 
+/// Reasons why a GDS file could not be turned into a World.
+#[derive(Debug, Clone, PartialEq)]
+pub enum LoadError {
+    /// The file is not a valid GDSII stream.
+    Parse(String),
+
+    /// A structure references a cell name that the library does not define.
+    UnknownCell { parent: String, name: String },
+
+    /// The library does not contain any structures.
+    EmptyLibrary,
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoadError::Parse(message) => write!(f, "Unable to parse GDS file: {}", message),
+            LoadError::UnknownCell { parent, name } => {
+                write!(f, "Cell '{}' references unknown cell '{}'", parent, name)
+            }
+            LoadError::EmptyLibrary => write!(f, "GDS library contains no cells"),
+        }
+    }
+}
+
+impl std::error::Error for LoadError {}
+
 /// Reads a GDS file, creates a World, and populates it with definition
 /// entities.
 ///
 /// Has an iterator interface to allow progress reporting and
-/// periodic yielding to the UI.
+/// periodic yielding to the UI. Iteration ends after the first error.
 ///
 /// Does not create instance entities; for that see `Instancer`.
 pub struct Loader {
@@ -56,13 +111,17 @@ impl Loader {
 }
 
 impl Iterator for Loader {
-    type Item = Progress;
+    type Item = Result<Progress, LoadError>;
 
-    fn next(&mut self) -> Option<Progress> {
+    fn next(&mut self) -> Option<Self::Item> {
         let state = self.state.take()?;
-        let (progress, state) = state.next()?;
-        self.state = Some(state);
-        Some(progress)
+        match state.next()? {
+            Ok((progress, state)) => {
+                self.state = Some(state);
+                Some(Ok(progress))
+            }
+            Err(error) => Some(Err(error)),
+        }
     }
 }
 
@@ -82,43 +141,62 @@ impl Progress {
 
 enum LoaderState {
     ParsingFile(Vec<u8>),
-    GatheringNames(GdsLibrary),
+    GatheringNames(GdsLibrary, Vec<u8>),
     GeneratingWorld(Box<WorldGenerator>),
+    BuildingMeshes(Box<WorldGenerator>),
     YieldingWorld(Box<World>),
     Done,
 }
 
 impl LoaderState {
-    fn next(self) -> Option<(Progress, Self)> {
+    fn next(self) -> Option<Result<(Progress, Self), LoadError>> {
         match self {
             LoaderState::ParsingFile(data) => {
-                let library = GdsLibrary::from_bytes(data).unwrap();
-                next_state("Parsing file", LoaderState::GatheringNames(library))
+                let presentations = scan_text_presentations(&data);
+                let library = match GdsLibrary::from_bytes(data) {
+                    Ok(library) => library,
+                    Err(error) => return Some(Err(LoadError::Parse(error.to_string()))),
+                };
+                if library.structs.is_empty() {
+                    return Some(Err(LoadError::EmptyLibrary));
+                }
+                next_state(
+                    "Parsing file",
+                    LoaderState::GatheringNames(library, presentations),
+                )
             }
-            LoaderState::GatheringNames(library) => {
+            LoaderState::GatheringNames(library, presentations) => {
                 let mut world = World::new();
+                world.insert_resource(load_units(&library.units));
                 let mut map = BTreeMap::new();
                 let mut count = 0;
                 for gds_struct in &library.structs {
                     let cell_def = CellDefinition {
                         name: gds_struct.name.clone(),
                         shape_defs: vec![],
+                        text_defs: vec![],
                         cell_refs: vec![],
+                        meshes: vec![],
+                        display: Default::default(),
                     };
                     let cell_def = world.spawn(cell_def).id();
                     map.insert(gds_struct.name.clone(), cell_def);
                     count += gds_struct.elems.len();
                 }
-                let generator = WorldGenerator::new(world, library, map, count);
+                let generator = WorldGenerator::new(world, library, presentations, map, count);
                 next_state("Generating world", LoaderState::GeneratingWorld(generator))
             }
             LoaderState::GeneratingWorld(mut generator) => {
                 let start = Instant::now();
                 for _ in 0..generator.chunk_size {
-                    generator.process_element();
+                    if let Err(error) = generator.process_element() {
+                        return Some(Err(error));
+                    }
                     if generator.is_done() {
-                        let world = Box::new(generator.world);
-                        return next_state("Done", LoaderState::YieldingWorld(world));
+                        return next_state(
+                            "Building meshes",
+                            LoaderState::BuildingMeshes(generator),
+                        );
                     }
                 }
                 let end = Instant::now();
@@ -129,7 +207,22 @@ impl LoaderState {
                     generator.chunk_size += 1;
                 }
                 let progress = generator.progress();
-                Some((progress, LoaderState::GeneratingWorld(generator)))
+                Some(Ok((progress, LoaderState::GeneratingWorld(generator))))
+            }
+            LoaderState::BuildingMeshes(mut generator) => {
+                let start = Instant::now();
+                while !generator.meshes_done() {
+                    generator.build_next_cell_meshes();
+                    if start.elapsed().as_millis() > BUDGET_MS {
+                        break;
+                    }
+                }
+                if generator.meshes_done() {
+                    let world = Box::new(generator.world);
+                    return next_state("Done", LoaderState::YieldingWorld(world));
+                }
+                let progress = generator.mesh_progress();
+                Some(Ok((progress, LoaderState::BuildingMeshes(generator))))
             }
             LoaderState::YieldingWorld(world) => {
                 // Move the world from LoaderState to Progress so that the
@@ -139,7 +232,7 @@ impl LoaderState {
                     percent: 100.0,
                     world: Some(*world),
                 };
-                Some((progress, LoaderState::Done))
+                Some(Ok((progress, LoaderState::Done)))
             }
             LoaderState::Done => None,
         }
@@ -150,6 +243,12 @@ struct WorldGenerator {
     world: World,
     library: GdsLibrary,
     name_to_cell_def: NameTable,
+
+    /// PRESENTATION bits of every TEXT element in stream order, which is
+    /// also the order in which elements are processed.
+    text_presentations: Vec<u8>,
+    text_index: usize,
+
     struct_index: usize,
     element_index: usize,
     total_element_count: usize,
@@ -158,6 +257,11 @@ struct WorldGenerator {
     layer_query: QueryState<(Entity, &'static Layer)>,
     layer_material_query: QueryState<(Entity, &'static LayerMaterial)>,
 
+    /// Cell definitions whose shapes are grouped into tile meshes once all
+    /// elements have been processed.
+    cells_to_mesh: Vec<Entity>,
+    mesh_cell_index: usize,
+
     /// Controls the maximum number of GDS elements to process before yielding.
     /// Higher numbers might speed up loading time, but could reduce interactivity
     /// and frequency of status updates in the UI.
@@ -168,6 +272,7 @@ impl WorldGenerator {
     fn new(
         mut world: World,
         library: GdsLibrary,
+        text_presentations: Vec<u8>,
         name_to_cell_def: NameTable,
         total_element_count: usize,
     ) -> Box<Self> {
@@ -177,7 +282,11 @@ impl WorldGenerator {
         Box::new(WorldGenerator {
             world,
             library,
+            cells_to_mesh: name_to_cell_def.values().copied().collect(),
+            mesh_cell_index: 0,
             name_to_cell_def,
+            text_presentations,
+            text_index: 0,
             struct_index: 0,
             element_index: 0,
             total_element_count,
@@ -197,6 +306,19 @@ impl WorldGenerator {
         }
     }
 
+    fn mesh_progress(&self) -> Progress {
+        let fraction = self.mesh_cell_index as f32 / self.cells_to_mesh.len() as f32;
+        Progress {
+            phase: self.status.clone(),
+            percent: fraction * 100.0,
+            world: None,
+        }
+    }
+
+    fn meshes_done(&self) -> bool {
+        self.mesh_cell_index >= self.cells_to_mesh.len()
+    }
+
     fn is_done(&self) -> bool {
         self.processed_element_count >= self.total_element_count
     }
@@ -205,13 +327,13 @@ impl WorldGenerator {
         (self.processed_element_count as f32) / (self.total_element_count as f32)
     }
 
-    fn process_element(&mut self) {
-        let gds_struct = &self.library.structs[self.struct_index];
-        if self.element_index >= gds_struct.elems.len() {
+    fn process_element(&mut self) -> Result<(), LoadError> {
+        // Skip finished structures, and any empty ones that follow them.
+        while self.element_index >= self.library.structs[self.struct_index].elems.len() {
             self.struct_index += 1;
             self.element_index = 0;
             if self.struct_index >= self.library.structs.len() {
-                return;
+                return Ok(());
             }
         }
         let gds_struct = &self.library.structs[self.struct_index];
@@ -220,72 +342,114 @@ impl WorldGenerator {
         let element = &gds_struct.elems[self.element_index];
         match element {
             gds21::GdsElement::GdsStructRef(sref) => {
-                let cell_ref = self.load_struct_ref(&sref.clone());
+                let cell_ref = self.load_struct_ref(&sref.clone())?;
                 let mut cell_def = self.world.get_mut::<CellDefinition>(cell_def).unwrap();
                 cell_def.cell_refs.push(cell_ref);
             }
-            gds21::GdsElement::GdsArrayRef(_) => {
-                // TODO: array refs are not yet implemented, hide them for now
+            gds21::GdsElement::GdsArrayRef(aref) => {
+                let cell_ref = self.load_array_ref(&aref.clone())?;
+                let mut cell_def = self.world.get_mut::<CellDefinition>(cell_def).unwrap();
+                cell_def.cell_refs.extend(cell_ref);
             }
             gds21::GdsElement::GdsBoundary(boundary) => {
                 let shape_def = self.load_boundary(&boundary.clone());
-                let mut cell_def = self.world.get_mut::<CellDefinition>(cell_def).unwrap();
-                cell_def.shape_defs.push(shape_def);
+                self.add_shape(cell_def, shape_def);
             }
             gds21::GdsElement::GdsPath(path) => {
                 let shape_def = self.load_path(&path.clone());
-                let mut cell_def = self.world.get_mut::<CellDefinition>(cell_def).unwrap();
-                cell_def.shape_defs.push(shape_def);
+                self.add_shape(cell_def, shape_def);
             }
-            gds21::GdsElement::GdsTextElem(_) => {
-                // We do not support text elements yet, but they do
-                // occur so let's not spam the console with warnings.
+            gds21::GdsElement::GdsTextElem(text) => {
+                let text_def = self.load_text(&text.clone());
+                let mut cell_def = self.world.get_mut::<CellDefinition>(cell_def).unwrap();
+                cell_def.text_defs.push(text_def);
             }
-            gds21::GdsElement::GdsNode(_) => {
-                log::warn!("Node elements are not supported");
+            gds21::GdsElement::GdsNode(node) => {
+                if let Some(shape_def) = self.load_node(&node.clone()) {
+                    self.add_shape(cell_def, shape_def);
+                }
             }
-            gds21::GdsElement::GdsBox(_) => {
-                log::warn!("Box elements are not supported");
+            gds21::GdsElement::GdsBox(gds_box) => {
+                let shape_def = self.load_box(&gds_box.clone());
+                self.add_shape(cell_def, shape_def);
             }
         }
         self.element_index += 1;
         self.processed_element_count += 1;
+        Ok(())
     }
 
-    fn load_struct_ref(&mut self, sref: &GdsStructRef) -> CellReference {
-        let cell_definition = self.name_to_cell_def[&sref.name];
+    fn add_shape(&mut self, cell_def: Entity, shape_def: Entity) {
+        let mut cell_def = self.world.get_mut::<CellDefinition>(cell_def).unwrap();
+        cell_def.shape_defs.push(shape_def);
+    }
 
-        let translate = AffineTransform::translate(sref.xy.x as f64, sref.xy.y as f64);
+    fn find_cell_definition(&self, name: &str) -> Result<Entity, LoadError> {
+        self.name_to_cell_def
+            .get(name)
+            .copied()
+            .ok_or_else(|| LoadError::UnknownCell {
+                parent: self.library.structs[self.struct_index].name.clone(),
+                name: name.to_string(),
+            })
+    }
 
-        let parent_transform = AffineTransform::identity();
+    fn load_struct_ref(&mut self, sref: &GdsStructRef) -> Result<CellReference, LoadError> {
+        let cell_definition = self.find_cell_definition(&sref.name)?;
 
-        let mut rotate = AffineTransform::identity();
-        let mut scale = AffineTransform::identity();
+        let translate = AffineTransform::translate(sref.xy.x as f64, sref.xy.y as f64);
+        let local_transform = strans_to_transform(&sref.strans).compose(&translate);
+        let (abs_mag, abs_angle) = strans_abs_flags(&sref.strans);
 
-        if let Some(local_transform) = &sref.strans {
-            if let Some(angle) = &local_transform.angle {
-                rotate = AffineTransform::rotate(*angle, Coord::zero());
-            }
-            if local_transform.reflected {
-                scale = AffineTransform::scale(1.0, -1.0, Coord::zero());
-            }
-            if local_transform.mag.unwrap_or(1.0) != 1.0 {
-                eprintln!("Magnification not supported.");
-            }
-            if local_transform.abs_mag || local_transform.abs_angle {
-                eprintln!("Absolute transform not supported.");
-            }
+        Ok(CellReference {
+            cell_definition,
+            local_transform,
+            abs_mag,
+            abs_angle,
+            array: None,
+        })
+    }
+
+    /// Loads an AREF as a single CellReference with a grid, which the
+    /// Instancer expands one element at a time.
+    ///
+    /// The three AREF points are the array origin, the origin displaced by
+    /// `cols` column pitches, and the origin displaced by `rows` row pitches.
+    /// These are already in the parent coordinate system, so the STRANS is
+    /// applied to each instance before it is translated into place.
+    fn load_array_ref(&mut self, aref: &GdsArrayRef) -> Result<Option<CellReference>, LoadError> {
+        if aref.cols <= 0 || aref.rows <= 0 {
+            log::warn!(
+                "Ignoring array of {} with {} columns and {} rows",
+                aref.name,
+                aref.cols,
+                aref.rows
+            );
+            return Ok(None);
         }
 
-        let local_transform = scale
-            .compose(&rotate)
-            .compose(&translate)
-            .compose(&parent_transform);
+        let cell_definition = self.find_cell_definition(&aref.name)?;
+        let strans = strans_to_transform(&aref.strans);
+        let (abs_mag, abs_angle) = strans_abs_flags(&aref.strans);
 
-        CellReference {
+        let [origin, col_end, row_end] = &aref.xy;
+        let origin = gds_point_to_array(origin);
+        let col_pitch = (gds_point_to_array(col_end) - origin) / aref.cols as f64;
+        let row_pitch = (gds_point_to_array(row_end) - origin) / aref.rows as f64;
+
+        let translate = AffineTransform::translate(origin.x, origin.y);
+        Ok(Some(CellReference {
             cell_definition,
-            local_transform,
-        }
+            local_transform: strans.compose(&translate),
+            abs_mag,
+            abs_angle,
+            array: Some(CellArray {
+                cols: aref.cols as u16,
+                rows: aref.rows as u16,
+                col_step: col_pitch,
+                row_step: row_pitch,
+            }),
+        }))
     }
 
     fn load_boundary(&mut self, boundary: &GdsBoundary) -> Entity {
@@ -293,7 +457,7 @@ impl WorldGenerator {
         let array_points: Vec<_> = boundary.xy.iter().map(gds_point_to_array).collect();
         let local_polygon = Polygon::new(LineString::from(geo_points), vec![]);
         let local_triangles = Triangulation::from_polygon(&local_polygon);
-        let layer = self.get_or_create_layer(boundary.layer);
+        let layer = self.get_or_create_layer(LayerKey::new(boundary.layer, boundary.datatype));
         let shape_definition = ShapeDefinition {
             layer,
             shape_type: ShapeType::Polygon(array_points),
@@ -316,7 +480,7 @@ impl WorldGenerator {
         let outline_points = create_path_outline(&path.xy, half_width, path_type);
         let local_polygon = Polygon::new(LineString::from(outline_points), vec![]);
         let local_triangles = Triangulation::from_polygon(&local_polygon);
-        let layer = self.get_or_create_layer(path.layer);
+        let layer = self.get_or_create_layer(LayerKey::new(path.layer, path.datatype));
         let shape_definition = ShapeDefinition {
             layer,
             shape_type: ShapeType::Path { width, spine },
@@ -326,16 +490,198 @@ impl WorldGenerator {
         self.world.spawn(shape_definition).id()
     }
 
-    fn get_or_create_layer(&mut self, index: i16) -> Entity {
+    fn load_box(&mut self, gds_box: &GdsBox) -> Entity {
+        let geo_points: Vec<_> = gds_box.xy.iter().map(gds_to_geo_point).collect();
+        let array_points: Vec<_> = gds_box.xy.iter().map(gds_point_to_array).collect();
+        let local_polygon = Polygon::new(LineString::from(geo_points), vec![]);
+        let local_triangles = Triangulation::from_polygon(&local_polygon);
+        let layer = self.get_or_create_layer(LayerKey::new(gds_box.layer, gds_box.boxtype));
+        let shape_definition = ShapeDefinition {
+            layer,
+            shape_type: ShapeType::Box(array_points),
+            local_polygon,
+            local_triangles,
+        };
+        self.world.spawn(shape_definition).id()
+    }
+
+    /// Nodes have no width, so the outline width is a fraction of the node's
+    /// extent. The local polygon is the area enclosed by the node points,
+    /// which is what picking and SVG export use.
+    fn load_node(&mut self, node: &GdsNode) -> Option<Entity> {
+        if node.xy.len() < 2 {
+            log::warn!("Ignoring node with fewer than two points");
+            return None;
+        }
+
+        let geo_points: Vec<_> = node.xy.iter().map(gds_to_geo_point).collect();
+        let array_points: Vec<_> = node.xy.iter().map(gds_point_to_array).collect();
+        let local_polygon = Polygon::new(LineString::from(geo_points), vec![]);
+
+        let extent = local_polygon
+            .bounding_rect()
+            .map(|rect| rect.width().max(rect.height()))
+            .unwrap_or(0.0);
+        let half_width = (NODE_OUTLINE_FRACTION * extent).max(1.0) / 2.0;
+
+        let mut rings = create_node_outline(&node.xy, half_width).into_iter();
+        let exterior = LineString::from(rings.next().unwrap_or_default());
+        let interiors = rings.map(LineString::from).collect();
+        let outline = Polygon::new(exterior, interiors);
+
+        let local_triangles = Triangulation::from_polygon(&outline);
+        let layer = self.get_or_create_layer(LayerKey::new(node.layer, node.nodetype));
+        let shape_definition = ShapeDefinition {
+            layer,
+            shape_type: ShapeType::Node(array_points),
+            local_polygon,
+            local_triangles,
+        };
+        Some(self.world.spawn(shape_definition).id())
+    }
+
+    fn load_text(&mut self, text: &GdsTextElem) -> Entity {
+        let translate = AffineTransform::translate(text.xy.x as f64, text.xy.y as f64);
+        let local_transform = strans_to_transform(&text.strans).compose(&translate);
+        let layer = self.get_or_create_layer(LayerKey::new(text.layer, text.texttype));
+        let presentation = self.text_presentations.get(self.text_index).copied();
+        self.text_index += 1;
+        let text_definition = TextDefinition {
+            layer,
+            string: text.string.clone(),
+            texttype: text.texttype,
+            presentation: decode_presentation(presentation.unwrap_or(0)),
+            local_transform,
+        };
+        self.world.spawn(text_definition).id()
+    }
+
+    fn get_or_create_layer(&mut self, key: LayerKey) -> Entity {
         let layer = self
             .layer_query
             .iter(&self.world)
-            .find(|(_, layer)| layer.index == index);
+            .find(|(_, layer)| layer.key == key);
 
         if let Some((entity, _)) = layer {
             return entity;
         }
 
+        let layer = Layer {
+            key,
+            color: Vector4f::new(0.0, 0.0, 0.0, 1.0),
+            visible: true,
+            meshes: vec![],
+            world_bounds: BoundingBox::new(),
+            shape_instances: vec![],
+        };
+
+        self.world.spawn(layer).id()
+    }
+
+    fn build_next_cell_meshes(&mut self) {
+        let cell_def = self.cells_to_mesh[self.mesh_cell_index];
+        self.mesh_cell_index += 1;
+        let cell_definition = self.world.get::<CellDefinition>(cell_def).unwrap();
+        self.status = cell_definition.name.clone();
+
+        // Group the shapes by layer, in a stable order.
+        let mut shapes_by_layer: BTreeMap<Entity, Vec<Entity>> = BTreeMap::new();
+        for shape_def in &cell_definition.shape_defs {
+            let layer = self.world.get::<ShapeDefinition>(*shape_def).unwrap().layer;
+            shapes_by_layer.entry(layer).or_default().push(*shape_def);
+        }
+
+        for (layer, shape_defs) in shapes_by_layer {
+            let items: Vec<TileItem> = shape_defs
+                .iter()
+                .map(|shape_def| {
+                    let shape_def = self.world.get::<ShapeDefinition>(*shape_def).unwrap();
+                    let triangles = &shape_def.local_triangles;
+                    TileItem {
+                        bounds: triangles.bounds(),
+                        vertex_count: triangles.vertices.len(),
+                    }
+                })
+                .collect();
+            for tile in tiles::partition(&items, TILE_VERTEX_BUDGET) {
+                let shape_defs = tile.items.iter().map(|i| shape_defs[*i]);
+                let tile_shapes: Vec<Entity> = shape_defs.collect();
+                self.spawn_tile_mesh(cell_def, layer, &tile_shapes, tile.bounds);
+            }
+        }
+    }
+
+    /// Spawns the detailed mesh of a tile, along with a density quad that
+    /// replaces it when its shapes are too small to see. Both are drawn once
+    /// per instance of the cell.
+    fn spawn_tile_mesh(
+        &mut self,
+        cell_def: Entity,
+        layer: Entity,
+        shape_defs: &[Entity],
+        local_bounds: BoundingBox,
+    ) {
+        // Vertices are stored relative to the center of the tile.
+        let origin = if local_bounds.is_empty() {
+            Point2d::origin()
+        } else {
+            Point2d::new(
+                (local_bounds.min_x + local_bounds.max_x) / 2.0,
+                (local_bounds.min_y + local_bounds.max_y) / 2.0,
+            )
+        };
+
+        let mut geometry = Geometry::new_instanced();
+        let mut extents = Vec::with_capacity(shape_defs.len());
+        let mut area = 0.0;
+        for shape_def in shape_defs {
+            let shape_def = self.world.get::<ShapeDefinition>(*shape_def).unwrap();
+            let triangles = &shape_def.local_triangles;
+            triangles.append_to(&mut geometry, &origin);
+            let bounds = triangles.bounds();
+            extents.push(bounds.width().max(bounds.height()));
+            area += triangles.area();
+        }
+
+        // The median shape extent decides when the tile is too small to see.
+        extents.sort_by(|a, b| a.total_cmp(b));
+        let feature_size = extents.get(extents.len() / 2).copied().unwrap_or(0.0);
+
+        let detail = LayerMesh {
+            layer,
+            local_bounds,
+            origin,
+            feature_size,
+            opacity: 1.0,
+        };
+        let level_of_detail = LevelOfDetail::Detail { feature_size: 0.0 };
+        self.spawn_layer_mesh(cell_def, geometry, detail, level_of_detail);
+
+        let tile_area = local_bounds.width() * local_bounds.height();
+        let coverage = if tile_area > 0.0 {
+            (area / tile_area).min(1.0)
+        } else {
+            1.0
+        };
+        let density = LayerMesh {
+            layer,
+            local_bounds,
+            origin,
+            feature_size,
+            opacity: coverage as f32,
+        };
+        let level_of_detail = LevelOfDetail::Coarse { feature_size: 0.0 };
+        let geometry = density_quad(&local_bounds, &origin);
+        self.spawn_layer_mesh(cell_def, geometry, density, level_of_detail);
+    }
+
+    fn spawn_layer_mesh(
+        &mut self,
+        cell_def: Entity,
+        geometry: Geometry,
+        layer_mesh: LayerMesh,
+        level_of_detail: LevelOfDetail,
+    ) {
         let layer_material_result = self.layer_material_query.single(&self.world);
 
         let layer_material = match layer_material_result {
@@ -343,23 +689,136 @@ impl WorldGenerator {
             Ok((entity, _)) => entity,
         };
 
-        let geometry = self.world.spawn(Geometry::new()).id();
+        let geometry = self.world.spawn(geometry).id();
 
+        let layer = layer_mesh.layer;
+        let key = self.world.get::<Layer>(layer).unwrap().key;
         let mut mesh = Mesh::new(geometry, layer_material);
-        mesh.render_order = index as i32;
-        let mesh = self.world.spawn((mesh, LayerMesh)).id();
+        mesh.render_order = key.render_order();
+        mesh.bounds = Some(BoundingBox::new());
+        mesh.level_of_detail = level_of_detail;
+        let mesh = self.world.spawn((mesh, layer_mesh)).id();
+
+        self.world
+            .get_mut::<Layer>(layer)
+            .unwrap()
+            .meshes
+            .push(mesh);
+        let mut cell_definition = self.world.get_mut::<CellDefinition>(cell_def).unwrap();
+        cell_definition.meshes.push(mesh);
+    }
+}
 
-        let layer = Layer {
-            index,
-            color: Vector4f::new(0.0, 0.0, 0.0, 1.0),
-            visible: true,
-            mesh,
-            world_bounds: BoundingBox::new(),
-            shape_instances: vec![],
-        };
+/// The gds21 crate does not expose the raw values of the UNITS record, but its
+/// `user_unit` method returns their ratio, from which both can be recovered.
+fn load_units(units: &GdsUnits) -> LibraryUnits {
+    let db_unit = units.db_unit();
+    let db_unit_in_user_units = units.user_unit() * db_unit;
+    LibraryUnits::new(db_unit, db_unit_in_user_units)
+}
 
-        self.world.spawn(layer).id()
+/// Converts a GDS STRANS record into an affine transform. Per the GDSII spec,
+/// reflection about the x-axis is applied first, then magnification, then
+/// rotation. The absolute flags are not part of the local transform; they are
+/// honored by the Instancer when composing with the parent transform.
+fn strans_to_transform(strans: &Option<GdsStrans>) -> AffineTransform {
+    let Some(strans) = strans else {
+        return AffineTransform::identity();
+    };
+
+    let mut reflect = AffineTransform::identity();
+    if strans.reflected {
+        reflect = AffineTransform::scale(1.0, -1.0, Coord::zero());
+    }
+
+    let mag = strans.mag.unwrap_or(1.0);
+    let scale = AffineTransform::scale(mag, mag, Coord::zero());
+
+    let angle = strans.angle.unwrap_or(0.0);
+    let rotate = AffineTransform::rotate(angle, Coord::zero());
+
+    reflect.compose(&scale).compose(&rotate)
+}
+
+fn strans_abs_flags(strans: &Option<GdsStrans>) -> (bool, bool) {
+    strans
+        .as_ref()
+        .map_or((false, false), |s| (s.abs_mag, s.abs_angle))
+}
+
+/// GDSII record types, from the second header byte of each record.
+const TEXT_RECORD: u8 = 0x0C;
+const PRESENTATION_RECORD: u8 = 0x17;
+const ENDLIB_RECORD: u8 = 0x04;
+
+/// Returns the PRESENTATION bits of every TEXT element in the stream, in
+/// order, with zero for elements that have no PRESENTATION record.
+///
+/// gds21 parses these records but keeps the two bytes private, so they are
+/// read from the raw stream instead.
+fn scan_text_presentations(gds_content: &[u8]) -> Vec<u8> {
+    let mut presentations = Vec::new();
+    let mut offset = 0;
+    while let Some(header) = gds_content.get(offset..offset + 4) {
+        let length = u16::from_be_bytes([header[0], header[1]]) as usize;
+        let record_type = header[2];
+        if length < 4 || record_type == ENDLIB_RECORD {
+            break;
+        }
+        match record_type {
+            TEXT_RECORD => presentations.push(0),
+            PRESENTATION_RECORD => {
+                // The flags are in the low byte of a two byte bit array.
+                if let (Some(last), Some(&bits)) =
+                    (presentations.last_mut(), gds_content.get(offset + 5))
+                {
+                    *last = bits;
+                }
+            }
+            _ => {}
+        }
+        offset += length;
+    }
+    presentations
+}
+
+/// Decodes the font and justification bits of a PRESENTATION record.
+fn decode_presentation(bits: u8) -> TextPresentation {
+    let horizontal = match bits & 0b11 {
+        1 => HorizontalAlign::Center,
+        2 => HorizontalAlign::Right,
+        _ => HorizontalAlign::Left,
+    };
+    let vertical = match (bits >> 2) & 0b11 {
+        1 => VerticalAlign::Middle,
+        2 => VerticalAlign::Bottom,
+        _ => VerticalAlign::Top,
+    };
+
+    TextPresentation {
+        font: (bits >> 4) & 0b11,
+        horizontal,
+        vertical,
+    }
+}
+
+/// A single quad that stands in for all shapes of a tile, relative to the
+/// given origin.
+fn density_quad(bounds: &BoundingBox, origin: &Point2d) -> Geometry {
+    let mut geometry = Geometry::new_instanced();
+    for (x, y) in [
+        (bounds.min_x, bounds.min_y),
+        (bounds.max_x, bounds.min_y),
+        (bounds.max_x, bounds.max_y),
+        (bounds.min_x, bounds.max_y),
+    ] {
+        let (x, y) = (x - origin.x, y - origin.y);
+        geometry
+            .positions
+            .extend_from_slice(&[x as f32, y as f32, 0.0]);
     }
+    geometry.indices.extend_from_slice(&[0, 1, 2, 0, 2, 3]);
+    geometry
 }
 
 fn gds_to_geo_point(p: &GdsPoint) -> geo::Point<f64> {
@@ -370,11 +829,14 @@ fn gds_point_to_array(p: &GdsPoint) -> Point2d {
     Point2d::new(p.x as f64, p.y as f64)
 }
 
-fn next_state(phase: &str, state: LoaderState) -> Option<(Progress, LoaderState)> {
+fn next_state(
+    phase: &str,
+    state: LoaderState,
+) -> Option<Result<(Progress, LoaderState), LoadError>> {
     let progress = Progress {
         phase: phase.to_string(),
         percent: 0.0,
         world: None,
     };
-    Some((progress, state))
+    Some(Ok((progress, state)))
 }
@@ -0,0 +1,106 @@
+use crate::graphics::bounds::BoundingBox;
+use crate::graphics::camera::Camera;
+use crate::graphics::vectors::*;
+use crate::graphics::viewport::Viewport;
+
+/// The minimap is at most this fraction of the window in each direction.
+const MAX_SIZE_FRACTION: f64 = 0.25;
+
+/// Distance of the minimap from the top right corner of the window, in
+/// physical pixels.
+const MARGIN_PX: f64 = 16.0;
+
+/// Space around the layout inside the minimap, as a fraction of its size.
+const PADDING_FRACTION: f64 = 0.05;
+
+/// Where the minimap is drawn, and the camera that frames the whole layout
+/// within it.
+#[derive(Debug)]
+pub struct MinimapView {
+    /// Rectangle in physical pixels from the top left corner of the window.
+    pub inset: Viewport,
+    pub camera: Camera,
+}
+
+impl MinimapView {
+    /// Places the minimap in the top right corner of the window, shaped like
+    /// `world_bounds` as seen through the main camera. Returns None if the
+    /// bounds are empty or the window is too small.
+    pub fn new(
+        main_camera: &Camera,
+        window_size: (u32, u32),
+        world_bounds: &BoundingBox,
+    ) -> Option<Self> {
+        if world_bounds.is_empty() {
+            return None;
+        }
+
+        // The extent of the bounds along the screen axes depends on the
+        // orientation of the camera.
+        let extent = |axis: Vector3d| {
+            axis.x.abs() * world_bounds.width() + axis.y.abs() * world_bounds.height()
+        };
+        let (extent_x, extent_y) = (extent(main_camera.right()), extent(main_camera.up));
+        if extent_x <= 0.0 || extent_y <= 0.0 {
+            return None;
+        }
+
+        let max_width = MAX_SIZE_FRACTION * window_size.0 as f64;
+        let max_height = MAX_SIZE_FRACTION * window_size.1 as f64;
+        let scale = (max_width / extent_x).min(max_height / extent_y);
+        let (width, height) = ((extent_x * scale).round(), (extent_y * scale).round());
+        if width < 1.0 || height < 1.0 {
+            return None;
+        }
+        let inset = Viewport::new(
+            window_size.0 as f64 - MARGIN_PX - width,
+            MARGIN_PX,
+            width,
+            height,
+        );
+
+        let padding = 1.0 + 2.0 * PADDING_FRACTION;
+        let mut camera = Camera::new(
+            Point3d::new(
+                0.5 * (world_bounds.min_x + world_bounds.max_x),
+                0.5 * (world_bounds.min_y + world_bounds.max_y),
+                main_camera.position.z,
+            ),
+            extent_x * padding,
+            extent_y * padding,
+            main_camera.near,
+            main_camera.far,
+        );
+        camera.up = main_camera.up;
+        camera.gaze = main_camera.gaze;
+
+        Some(Self { inset, camera })
+    }
+
+    /// Returns true if the given position in physical pixels is on the
+    /// minimap.
+    pub fn contains(&self, x: u32, y: u32) -> bool {
+        let inset = &self.inset;
+        let (x, y) = (x as f64, y as f64);
+        x >= inset.left
+            && x < inset.left + inset.width
+            && y >= inset.top
+            && y < inset.top + inset.height
+    }
+
+    /// Converts a position in physical pixels to the world position that the
+    /// minimap shows there. Positions outside the minimap are extrapolated,
+    /// so that dragging can continue past its edges.
+    pub fn screen_to_world(&self, x: u32, y: u32) -> Point2d {
+        let inset = &self.inset;
+        let ndc_x = (x as f64 - inset.left) / inset.width * 2.0 - 1.0;
+        let ndc_y = -((y as f64 - inset.top) / inset.height * 2.0 - 1.0);
+        let world = self.camera.unproject(Point3d::new(ndc_x, ndc_y, 0.0));
+        Point2d::new(world.x, world.y)
+    }
+
+    /// Returns the size of a physical pixel in the minimap, in world units.
+    pub fn pixel_size(&self) -> f64 {
+        self.camera.width / self.inset.width
+    }
+}
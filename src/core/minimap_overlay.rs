@@ -0,0 +1,142 @@
+use bevy_ecs::world::World;
+
+use crate::core::components::BACKGROUND_RENDER_ORDER;
+use crate::core::components::MINIMAP_RENDER_ORDER;
+use crate::core::components::OVERLAY_RENDER_ORDER;
+use crate::core::minimap::MinimapView;
+use crate::graphics::bounds::BoundingBox;
+use crate::graphics::camera::Camera;
+use crate::graphics::renderer::Renderer;
+use crate::graphics::ribbon::Ribbon;
+use crate::graphics::vectors::*;
+use crate::graphics::viewport::Viewport;
+
+const FRAME_COLOR: Vector4f = Vector4f::new(1.0, 0.6, 0.0, 1.0);
+
+/// Width of the camera frame and of the border, in physical pixels.
+const FRAME_WIDTH_PX: f64 = 2.0;
+const BORDER_WIDTH_PX: f64 = 2.0;
+
+/// The camera frame is drawn at least this large, in physical pixels, so
+/// that it stays visible when zoomed in deep.
+const MIN_FRAME_PX: f64 = 8.0;
+
+/// Draws an overview of the whole layout into a scissored inset after the
+/// main view, with a frame around the part that the main camera sees. The
+/// layers are drawn at whatever level of detail suits the minimap's scale.
+pub struct MinimapOverlay {
+    frame: Ribbon,
+    border: Ribbon,
+
+    /// None if the minimap is hidden or there is nothing to show.
+    view: Option<MinimapView>,
+    visible: bool,
+}
+
+impl MinimapOverlay {
+    pub fn new(world: &mut World) -> Self {
+        let frame = Ribbon::new(world);
+        frame.set_color(world, FRAME_COLOR);
+        frame.set_render_order(world, MINIMAP_RENDER_ORDER + 1);
+        let border = Ribbon::new(world);
+        border.set_render_order(world, MINIMAP_RENDER_ORDER);
+        Self {
+            frame,
+            border,
+            view: None,
+            visible: true,
+        }
+    }
+
+    pub fn set_border_color(&self, world: &mut World, color: Vector4f) {
+        self.border.set_color(world, color);
+    }
+
+    pub fn set_visible(&mut self, visible: bool) {
+        self.visible = visible;
+    }
+
+    pub fn visible(&self) -> bool {
+        self.visible
+    }
+
+    /// Returns where the minimap was last drawn.
+    pub fn view(&self) -> Option<&MinimapView> {
+        self.view.as_ref()
+    }
+
+    /// Lays out the minimap around `world_bounds` in the top right corner of
+    /// a pane, which is in physical pixels from the top left corner of the
+    /// window, and rebuilds the frame around the view of the pane's camera.
+    pub fn update(
+        &mut self,
+        world: &mut World,
+        gl: &glow::Context,
+        main_camera: &Camera,
+        pane: &Viewport,
+        world_bounds: &BoundingBox,
+    ) {
+        self.view = None;
+        if !self.visible {
+            return;
+        }
+        let pane_size = (pane.width as u32, pane.height as u32);
+        let Some(mut view) = MinimapView::new(main_camera, pane_size, world_bounds) else {
+            return;
+        };
+        view.inset.left += pane.left;
+        view.inset.top += pane.top;
+        let pixel_size = view.pixel_size();
+
+        self.border.width = BORDER_WIDTH_PX * pixel_size;
+        self.border.spine = corners(&view.camera, 1.0);
+        self.border.update(world, gl);
+
+        // Grow tiny frames around their center.
+        let frame_px = main_camera.width.min(main_camera.height) / pixel_size;
+        let grow = if frame_px > 0.0 {
+            (MIN_FRAME_PX / frame_px).max(1.0)
+        } else {
+            1.0
+        };
+        self.frame.width = FRAME_WIDTH_PX * pixel_size;
+        self.frame.spine = corners(main_camera, grow);
+        self.frame.update(world, gl);
+
+        // Both are only shown while render draws the inset.
+        self.frame.hide(world);
+        self.border.hide(world);
+        self.view = Some(view);
+    }
+
+    /// Draws the minimap over the main view.
+    pub fn render(&self, world: &mut World, renderer: &mut Renderer) {
+        let Some(view) = &self.view else {
+            return;
+        };
+        self.frame.show(world);
+        self.border.show(world);
+
+        // Skip the grid and the overlays, which are sized for the main view.
+        renderer.render_inset(
+            world,
+            &view.camera,
+            &view.inset,
+            BACKGROUND_RENDER_ORDER + 1..=OVERLAY_RENDER_ORDER - 1,
+        );
+
+        self.frame.hide(world);
+        self.border.hide(world);
+    }
+}
+
+/// Returns the corners of the camera's view, scaled about its center.
+fn corners(camera: &Camera, scale: f64) -> Vec<Point2d> {
+    [(-1.0, -1.0), (1.0, -1.0), (1.0, 1.0), (-1.0, 1.0)]
+        .iter()
+        .map(|(x, y)| {
+            let corner = camera.unproject(Point3d::new(x * scale, y * scale, 0.0));
+            Point2d::new(corner.x, corner.y)
+        })
+        .collect()
+}
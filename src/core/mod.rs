@@ -1,11 +1,30 @@
 pub mod app_controller;
+pub mod bookmarks;
+pub mod camera_history;
+pub mod cell_display;
+pub mod cell_proxy;
 pub mod components;
+pub mod grid;
+pub mod inspector;
 pub mod instancer;
 pub mod layer_proxy;
 pub mod loader;
+pub mod minimap;
 pub mod root_finder;
+pub mod ruler;
+pub mod selection;
+pub mod split_view;
+pub mod tiles;
+pub mod units;
+pub mod view_state;
 
+mod abstract_cells;
+mod grid_overlay;
 mod hover_effect;
+mod minimap_overlay;
 mod path_outline;
 pub mod rtree;
+mod ruler_overlay;
+mod selection_effect;
+mod text_labels;
 mod triangulation;
@@ -48,6 +48,30 @@ pub fn create_path_outline(
     vec![]
 }
 
+/// Creates a closed band of the given width that traces the points of a GDS
+/// node. Returns the exterior and interior rings of the band. Nodes with two
+/// points are traced as an open segment instead.
+pub fn create_node_outline(points: &[GdsPoint], half_width: f64) -> Vec<Vec<Point>> {
+    let style = StrokeStyle::new(half_width * 2.0)
+        .line_join(LineJoin::Miter(1.0))
+        .start_cap(LineCap::Square)
+        .end_cap(LineCap::Square);
+
+    let is_closed_path = points.len() > 2;
+    let points: Vec<[f64; 2]> = points.iter().map(gds_point_to_array).collect();
+    let shapes: Vec<Vec<Vec<[f64; 2]>>> = points.stroke(style, is_closed_path);
+
+    if let Some(first_shape) = shapes.first() {
+        return first_shape
+            .iter()
+            .map(|contour| contour.iter().map(array_to_geo_point).collect())
+            .collect();
+    }
+
+    log::warn!("Empty contour for node.");
+    vec![]
+}
+
 impl From<i16> for PathType {
     fn from(value: i16) -> Self {
         match value {
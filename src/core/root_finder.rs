@@ -1,3 +1,4 @@
+use std::cmp::Reverse;
 use std::collections::HashSet;
 
 use bevy_ecs::entity::Entity;
@@ -22,7 +23,8 @@ impl RootFinder<'_> {
         }
     }
 
-    /// Finds CellDefinition entities that are not referenced by any other CellDefinition.
+    /// Finds CellDefinition entities that are not referenced by any other
+    /// CellDefinition, sorted by name.
     pub fn find_roots(&mut self, world: &World) -> Vec<Entity> {
         self.visited.clear();
         self.non_roots.clear();
@@ -38,11 +40,48 @@ impl RootFinder<'_> {
         }
 
         let mut roots = Vec::new();
-        for (entity, _) in self.query.iter(world) {
+        for (entity, cell) in self.query.iter(world) {
             if !self.non_roots.contains(&entity) {
-                roots.push(entity);
+                roots.push((cell.name.as_str(), entity));
+            }
+        }
+        roots.sort();
+        roots.into_iter().map(|(_, entity)| entity).collect()
+    }
+
+    /// Picks the root with the most distinct descendant cells. Ties are broken
+    /// by name so that a given file always opens on the same cell.
+    pub fn find_default_root(&mut self, world: &World) -> Option<Entity> {
+        let roots = self.find_roots(world);
+        roots.into_iter().max_by_key(|root| {
+            let name = CellDefinition::name_of(world, *root);
+            (count_descendants(world, *root), Reverse(name))
+        })
+    }
+
+    /// Finds a CellDefinition by name, whether or not it is a root.
+    pub fn find_cell(&mut self, world: &World, name: &str) -> Option<Entity> {
+        self.query
+            .iter(world)
+            .find(|(_, cell)| cell.name == name)
+            .map(|(entity, _)| entity)
+    }
+}
+
+/// Counts the distinct cell definitions reachable from the given cell.
+fn count_descendants(world: &World, cell_definition: Entity) -> usize {
+    let mut visited = HashSet::new();
+    let mut stack = vec![cell_definition];
+    while let Some(entity) = stack.pop() {
+        let Some(cell) = world.get::<CellDefinition>(entity) else {
+            continue;
+        };
+        for cell_ref in &cell.cell_refs {
+            if visited.insert(cell_ref.cell_definition) {
+                stack.push(cell_ref.cell_definition);
             }
         }
-        roots
     }
+    visited.remove(&cell_definition);
+    visited.len()
 }
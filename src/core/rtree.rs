@@ -1,9 +1,13 @@
 use bevy_ecs::entity::Entity;
+use bevy_ecs::world::World;
 use rstar::Envelope;
 use rstar::PointDistance;
+use rstar::RTree;
 use rstar::RTreeObject;
 use rstar::AABB;
 
+use crate::core::components::ShapeInstance;
+
 #[derive(Clone)]
 pub struct RTreeItem {
     pub shape_instance: Entity,
@@ -35,3 +39,26 @@ impl PointDistance for RTreeItem {
         self.aabb.contains_point(point)
     }
 }
+
+/// Indexes the world space bounds of every shape instance, for picking and
+/// box selection.
+pub fn build_rtree(world: &mut World) -> RTree<RTreeItem> {
+    let items = world
+        .query::<(Entity, &ShapeInstance)>()
+        .iter(world)
+        .filter_map(|(entity, shape_instance)| {
+            let bounds = shape_instance.world_bounds(world);
+            if bounds.is_empty() {
+                return None;
+            }
+            Some(RTreeItem {
+                shape_instance: entity,
+                aabb: AABB::from_corners(
+                    geo::Point::new(bounds.min_x, bounds.min_y),
+                    geo::Point::new(bounds.max_x, bounds.max_y),
+                ),
+            })
+        })
+        .collect();
+    RTree::bulk_load(items)
+}
@@ -0,0 +1,118 @@
+use bevy_ecs::resource::Resource;
+use bevy_ecs::world::World;
+use rstar::RTree;
+use rstar::AABB;
+
+use crate::core::cell_display::is_concealed;
+use crate::core::components::Layer;
+use crate::core::components::ShapeInstance;
+use crate::core::rtree::RTreeItem;
+use crate::core::units::format_length;
+use crate::core::units::LibraryUnits;
+use crate::graphics::vectors::*;
+
+/// A measurement between two points, in database units.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Ruler {
+    pub start: Point2d,
+    pub end: Point2d,
+}
+
+impl Ruler {
+    pub fn new(start: Point2d, end: Point2d) -> Self {
+        Self { start, end }
+    }
+
+    pub fn dx(&self) -> f64 {
+        self.end.x - self.start.x
+    }
+
+    pub fn dy(&self) -> f64 {
+        self.end.y - self.start.y
+    }
+
+    pub fn length(&self) -> f64 {
+        (self.end - self.start).norm()
+    }
+
+    pub fn midpoint(&self) -> Point2d {
+        nalgebra::center(&self.start, &self.end)
+    }
+
+    /// Formats the length in microns and database units, followed by the
+    /// horizontal and vertical extents in microns.
+    pub fn label(&self, units: &LibraryUnits) -> String {
+        format!(
+            "{} µm ({} dbu), dx {} µm, dy {} µm",
+            format_length(units.to_microns(self.length()), 4),
+            format_length(self.length(), 1),
+            format_length(units.to_microns(self.dx()), 4),
+            format_length(units.to_microns(self.dy()), 4),
+        )
+    }
+}
+
+/// Rulers that persist until cleared, stored as a World resource so that
+/// they are exported along with the layout.
+#[derive(Resource, Clone, Debug, Default)]
+pub struct Rulers(pub Vec<Ruler>);
+
+/// Moves a point onto the nearest vertex of a visible shape within
+/// `tolerance`, or failing that, onto the nearest edge within `tolerance`.
+/// Shapes in hidden or abstract cells are skipped. Returns the point
+/// unchanged if nothing is close enough.
+pub fn snap_point(
+    world: &World,
+    rtree: &RTree<RTreeItem>,
+    point: Point2d,
+    tolerance: f64,
+) -> Point2d {
+    let envelope = AABB::from_corners(
+        geo::Point::new(point.x - tolerance, point.y - tolerance),
+        geo::Point::new(point.x + tolerance, point.y + tolerance),
+    );
+
+    let mut vertex: Option<(f64, Point2d)> = None;
+    let mut edge: Option<(f64, Point2d)> = None;
+    let consider = |best: &mut Option<(f64, Point2d)>, candidate: Point2d| {
+        let distance = (candidate - point).norm();
+        if distance <= tolerance && best.is_none_or(|(d, _)| distance < d) {
+            *best = Some((distance, candidate));
+        }
+    };
+
+    for item in rtree.locate_in_envelope_intersecting(&envelope) {
+        let Some(shape) = world.get::<ShapeInstance>(item.shape_instance) else {
+            continue;
+        };
+        let visible = world
+            .get::<Layer>(shape.layer)
+            .is_some_and(|layer| layer.visible)
+            && !is_concealed(world, shape.cell_instance);
+        if !visible {
+            continue;
+        }
+
+        let polygon = shape.world_polygon(world);
+        for ring in std::iter::once(polygon.exterior()).chain(polygon.interiors()) {
+            for line in ring.lines() {
+                let a = Point2d::new(line.start.x, line.start.y);
+                let b = Point2d::new(line.end.x, line.end.y);
+                consider(&mut vertex, a);
+                consider(&mut edge, closest_point_on_segment(point, a, b));
+            }
+        }
+    }
+
+    vertex.or(edge).map_or(point, |(_, snapped)| snapped)
+}
+
+fn closest_point_on_segment(point: Point2d, a: Point2d, b: Point2d) -> Point2d {
+    let ab = b - a;
+    let length_squared = ab.norm_squared();
+    if length_squared == 0.0 {
+        return a;
+    }
+    let t = ((point - a).dot(&ab) / length_squared).clamp(0.0, 1.0);
+    a + ab * t
+}
@@ -0,0 +1,99 @@
+use bevy_ecs::world::World;
+
+use crate::core::ruler::Ruler;
+use crate::core::units::LibraryUnits;
+use crate::graphics::camera::Camera;
+use crate::graphics::ribbon::Ribbon;
+use crate::graphics::text::HorizontalAlign;
+use crate::graphics::text::Label;
+use crate::graphics::text::TextBatch;
+use crate::graphics::text::VerticalAlign;
+use crate::graphics::vectors::*;
+
+const RULER_COLOR: Vector4f = Vector4f::new(0.0, 0.8, 1.0, 1.0);
+
+/// Width of the ruler lines in physical pixels.
+const LINE_WIDTH_PX: f64 = 2.0;
+
+/// Height of the measurement labels in physical pixels.
+const LABEL_HEIGHT_PX: f64 = 12.0;
+
+/// Draws rulers as lines with their measurements. One ribbon is kept per
+/// ruler, and ribbons are reused as rulers come and go.
+pub struct RulerOverlay {
+    ribbons: Vec<Ribbon>,
+    labels: TextBatch,
+    render_order: i32,
+}
+
+impl RulerOverlay {
+    pub fn new(world: &mut World) -> Self {
+        Self {
+            ribbons: Vec::new(),
+            labels: TextBatch::new(world),
+            render_order: 0,
+        }
+    }
+
+    pub fn set_render_order(&mut self, world: &mut World, render_order: i32) {
+        self.render_order = render_order;
+        for ribbon in &self.ribbons {
+            ribbon.set_render_order(world, render_order);
+        }
+        self.labels.set_render_order(world, render_order + 1);
+    }
+
+    pub fn set_label_color(&self, world: &mut World, color: Vector4f) {
+        self.labels.set_color(world, color);
+    }
+
+    /// Rebuilds the lines and labels of the given rulers for the current
+    /// camera, so that both keep a constant size on screen.
+    pub fn update(
+        &mut self,
+        world: &mut World,
+        gl: &glow::Context,
+        camera: &Camera,
+        window_size: (u32, u32),
+        rulers: &[Ruler],
+    ) {
+        let pixel_size = camera.width / window_size.0 as f64;
+        let units = world
+            .get_resource::<LibraryUnits>()
+            .copied()
+            .unwrap_or_default();
+
+        while self.ribbons.len() < rulers.len() {
+            let ribbon = Ribbon::new(world);
+            ribbon.set_color(world, RULER_COLOR);
+            ribbon.set_render_order(world, self.render_order);
+            self.ribbons.push(ribbon);
+        }
+
+        self.labels.labels.clear();
+        for (index, ribbon) in self.ribbons.iter_mut().enumerate() {
+            // Zero-length rulers have no direction to draw along.
+            let Some(ruler) = rulers.get(index).filter(|ruler| ruler.length() > 0.0) else {
+                ribbon.hide(world);
+                continue;
+            };
+            ribbon.closed = false;
+            ribbon.width = LINE_WIDTH_PX * pixel_size;
+            ribbon.spine = vec![ruler.start, ruler.end];
+            ribbon.update(world, gl);
+
+            self.labels.labels.push(Label {
+                text: ruler.label(&units),
+                position: ruler.midpoint(),
+                horizontal: HorizontalAlign::Center,
+                vertical: VerticalAlign::Bottom,
+            });
+        }
+
+        let right = camera.right();
+        self.labels.right = Vector2d::new(right.x, right.y);
+        self.labels.up = Vector2d::new(camera.up.x, camera.up.y);
+        self.labels.height = LABEL_HEIGHT_PX * pixel_size;
+        self.labels.update(world, gl);
+    }
+}
@@ -0,0 +1,131 @@
+use std::fmt::Write;
+
+use bevy_ecs::entity::Entity;
+use bevy_ecs::world::World;
+use geo::BoundingRect;
+use geo::Intersects;
+use rstar::RTree;
+use rstar::AABB;
+
+use crate::core::cell_display::is_concealed;
+use crate::core::components::CellDefinition;
+use crate::core::components::CellInstance;
+use crate::core::components::Layer;
+use crate::core::components::LayerKey;
+use crate::core::components::ShapeInstance;
+use crate::core::rtree::RTreeItem;
+use crate::core::units::LibraryUnits;
+use crate::graphics::bounds::BoundingBox;
+
+/// How a pick or box query combines with the current selection.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SelectionMode {
+    /// The result becomes the new selection.
+    Replace,
+
+    /// The result is added to the selection.
+    Extend,
+
+    /// Each shape in the result flips between selected and unselected.
+    Toggle,
+}
+
+/// Describes a selected shape instance for display and export.
+#[derive(Clone, Debug)]
+pub struct SelectedShape {
+    pub shape_instance: Entity,
+    pub layer_key: LayerKey,
+
+    /// Name of the cell definition that owns the shape.
+    pub cell_name: String,
+
+    /// Bounds of the shape in the root coordinate system, in database units.
+    pub world_bounds: BoundingBox,
+}
+
+impl SelectedShape {
+    pub fn new(world: &World, shape_instance: Entity) -> Option<Self> {
+        let shape = world.get::<ShapeInstance>(shape_instance)?;
+        let cell_instance = world.get::<CellInstance>(shape.cell_instance)?;
+        let cell_definition = world.get::<CellDefinition>(cell_instance.cell_definition)?;
+
+        let mut world_bounds = BoundingBox::new();
+        if let Some(rect) = shape.world_polygon(world).bounding_rect() {
+            world_bounds = BoundingBox {
+                min_x: rect.min().x,
+                min_y: rect.min().y,
+                max_x: rect.max().x,
+                max_y: rect.max().y,
+            };
+        }
+
+        Some(Self {
+            shape_instance,
+            layer_key: shape.layer_key,
+            cell_name: cell_definition.name.clone(),
+            world_bounds,
+        })
+    }
+}
+
+/// Returns every shape instance on a visible layer whose polygon intersects
+/// the given box, which is in database units. Shapes in hidden or abstract
+/// cells are skipped.
+pub fn shapes_in_box(world: &World, rtree: &RTree<RTreeItem>, bounds: &BoundingBox) -> Vec<Entity> {
+    if bounds.is_empty() {
+        return Vec::new();
+    }
+
+    let envelope = AABB::from_corners(
+        geo::Point::new(bounds.min_x, bounds.min_y),
+        geo::Point::new(bounds.max_x, bounds.max_y),
+    );
+    let rect = geo::Rect::new(
+        geo::coord! { x: bounds.min_x, y: bounds.min_y },
+        geo::coord! { x: bounds.max_x, y: bounds.max_y },
+    );
+
+    let mut result = Vec::new();
+    for item in rtree.locate_in_envelope_intersecting(&envelope) {
+        let Some(shape) = world.get::<ShapeInstance>(item.shape_instance) else {
+            continue;
+        };
+        let visible = world
+            .get::<Layer>(shape.layer)
+            .is_some_and(|layer| layer.visible)
+            && !is_concealed(world, shape.cell_instance);
+        if visible && shape.world_polygon(world).intersects(&rect) {
+            result.push(item.shape_instance);
+        }
+    }
+    result
+}
+
+/// Formats the given shapes as CSV with one row per shape. Bounds are
+/// converted to microns.
+pub fn export_csv(shapes: &[SelectedShape], units: &LibraryUnits) -> String {
+    let mut csv = String::from("layer,datatype,cell,min_x,min_y,max_x,max_y\n");
+    for shape in shapes {
+        let bounds = units.bounds_to_microns(&shape.world_bounds);
+        let _ = writeln!(
+            csv,
+            "{},{},{},{},{},{},{}",
+            shape.layer_key.layer,
+            shape.layer_key.datatype,
+            escape_csv_field(&shape.cell_name),
+            bounds.min_x,
+            bounds.min_y,
+            bounds.max_x,
+            bounds.max_y,
+        );
+    }
+    csv
+}
+
+fn escape_csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
@@ -0,0 +1,116 @@
+use crate::core::components::Selected;
+use crate::core::components::ShapeInstance;
+use crate::graphics::bounds::BoundingBox;
+use crate::graphics::geometry::Geometry;
+use crate::graphics::material::BlendMode;
+use crate::graphics::material::Material;
+use crate::graphics::mesh::Mesh;
+use crate::graphics::ribbon::Ribbon;
+use crate::graphics::vectors::*;
+use bevy_ecs::entity::Entity;
+use bevy_ecs::query::With;
+use bevy_ecs::world::World;
+use geo::TriangulateEarcut;
+
+const SELECTION_COLOR: Vector4f = Vector4f::new(1.0, 0.6, 0.0, 0.5);
+const BAND_COLOR: Vector4f = Vector4f::new(1.0, 0.6, 0.0, 1.0);
+
+/// Manages graphics primitives that highlight the selected shapes, and the
+/// rubber band that is shown while dragging out a selection box.
+pub struct SelectionEffect {
+    fill_mesh: Entity,
+    band: Ribbon,
+}
+
+impl SelectionEffect {
+    pub fn new(world: &mut World) -> Self {
+        let mut material = Material::default();
+        material.set_blending(BlendMode::SourceOver);
+        let material = world.spawn(material).id();
+
+        let geometry = world.spawn(Geometry::new()).id();
+
+        let mut mesh = Mesh::new(geometry, material);
+        mesh.visible = false;
+        mesh.set_vec4("color", SELECTION_COLOR);
+
+        let band = Ribbon::new(world);
+        band.set_color(world, BAND_COLOR);
+
+        Self {
+            fill_mesh: world.spawn(mesh).id(),
+            band,
+        }
+    }
+
+    pub fn update_stroke_width(&mut self, width: f64, world: &mut World, gl: &glow::Context) {
+        if self.band.width != width {
+            self.band.width = width;
+            self.band.update(world, gl);
+        }
+    }
+
+    pub fn set_render_order(&mut self, world: &mut World, render_order: i32) {
+        let mut mesh = world.get_mut::<Mesh>(self.fill_mesh).unwrap();
+        mesh.render_order = render_order;
+
+        self.band.set_render_order(world, render_order + 1);
+    }
+
+    /// Rebuilds the highlight from the shape instances that have the Selected
+    /// component.
+    pub fn update(&mut self, world: &mut World, gl: &glow::Context) {
+        let mut query = world.query_filtered::<&ShapeInstance, With<Selected>>();
+
+        let mut geometry = Geometry::new();
+        let mut origin = None;
+        for shape_instance in query.iter(world) {
+            let triangles = shape_instance.world_polygon(world).earcut_triangles_raw();
+
+            // Vertices are relative to the first point of the first polygon.
+            let origin = *origin.get_or_insert_with(|| {
+                let first = triangles.vertices.first().copied().unwrap_or_default();
+                let second = triangles.vertices.get(1).copied().unwrap_or_default();
+                Point2d::new(first, second)
+            });
+
+            let base = geometry.positions.len() as u32 / 3;
+            for coord in triangles.vertices.chunks(2) {
+                geometry.positions.push((coord[0] - origin.x) as f32);
+                geometry.positions.push((coord[1] - origin.y) as f32);
+                geometry.positions.push(0.0);
+            }
+            for index in triangles.triangle_indices {
+                geometry.indices.push(base + index as u32);
+            }
+        }
+
+        let mut mesh = world.get_mut::<Mesh>(self.fill_mesh).unwrap();
+        mesh.visible = origin.is_some();
+        mesh.origin = origin.unwrap_or_else(Point2d::origin);
+        let geometry_entity = mesh.geometry;
+        geometry.replace(world, gl, geometry_entity);
+    }
+
+    /// Shows the rubber band around the given box, in database units.
+    pub fn show_band(&mut self, bounds: &BoundingBox, world: &mut World, gl: &glow::Context) {
+        // Ribbon joins are undefined for repeated points.
+        if bounds.width() <= 0.0 || bounds.height() <= 0.0 {
+            self.hide_band(world);
+            return;
+        }
+        self.band.spine = vec![
+            Point2d::new(bounds.min_x, bounds.min_y),
+            Point2d::new(bounds.max_x, bounds.min_y),
+            Point2d::new(bounds.max_x, bounds.max_y),
+            Point2d::new(bounds.min_x, bounds.max_y),
+            Point2d::new(bounds.min_x, bounds.min_y),
+        ];
+        self.band.update(world, gl);
+    }
+
+    pub fn hide_band(&mut self, world: &mut World) {
+        self.band.spine.clear();
+        self.band.hide(world);
+    }
+}
@@ -0,0 +1,61 @@
+use crate::core::camera_history::CameraView;
+use crate::graphics::camera::Camera;
+use crate::graphics::vectors::*;
+use crate::graphics::viewport::Viewport;
+
+/// Width of the divider between neighboring panes, in physical pixels.
+pub const DIVIDER_PX: u32 = 2;
+
+/// Divides the window into `count` panes side by side, separated by
+/// dividers. The rectangles are in physical pixels from the top left corner
+/// of the window. Any pixels that do not divide evenly go to the last pane.
+pub fn pane_rects(window_size: (u32, u32), count: usize) -> Vec<Viewport> {
+    let count = count.max(1) as u32;
+    let (window_width, window_height) = window_size;
+    let available = window_width.saturating_sub(DIVIDER_PX * (count - 1));
+    let width = available / count;
+    (0..count)
+        .map(|index| {
+            let left = index * (width + DIVIDER_PX);
+            let width = if index + 1 == count {
+                window_width.saturating_sub(left)
+            } else {
+                width
+            };
+            Viewport::new(left as f64, 0.0, width as f64, window_height as f64)
+        })
+        .collect()
+}
+
+/// Returns the index of the pane that contains the given position in
+/// physical pixels, or None if it is on a divider or outside the window.
+pub fn pane_at(panes: &[Viewport], x: u32, y: u32) -> Option<usize> {
+    let (x, y) = (x as f64, y as f64);
+    panes.iter().position(|pane| {
+        x >= pane.left && x < pane.left + pane.width && y >= pane.top && y < pane.top + pane.height
+    })
+}
+
+/// Converts a position in physical pixels from the top left corner of the
+/// window to the world position that the camera of a pane shows there.
+/// Positions outside the pane are extrapolated.
+pub fn screen_to_world(camera: &Camera, pane: &Viewport, x: u32, y: u32) -> Point2d {
+    let ndc_x = (x as f64 - pane.left) / pane.width * 2.0 - 1.0;
+    let ndc_y = -((y as f64 - pane.top) / pane.height * 2.0 - 1.0);
+    let world = camera.unproject(Point3d::new(ndc_x, ndc_y, 0.0));
+    Point2d::new(world.x, world.y)
+}
+
+/// Moves a linked view by the pan and zoom that took the leading view from
+/// `before` to `after`. The pan is scaled to the linked view's zoom, so that
+/// both move by the same fraction of their panes.
+pub fn follow(view: CameraView, before: CameraView, after: CameraView) -> CameraView {
+    if before.width <= 0.0 {
+        return view;
+    }
+    let scale = view.width / before.width;
+    CameraView {
+        center: view.center + (after.center - before.center) * scale,
+        width: after.width * scale,
+    }
+}
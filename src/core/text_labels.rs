@@ -0,0 +1,118 @@
+use bevy_ecs::entity::Entity;
+use bevy_ecs::world::World;
+use geo::AffineOps;
+use geo::Point;
+
+use crate::core::cell_display::is_concealed;
+use crate::core::components::Layer;
+use crate::core::components::TextDefinition;
+use crate::core::components::TextInstance;
+use crate::graphics::camera::Camera;
+use crate::graphics::text::Label;
+use crate::graphics::text::TextBatch;
+use crate::graphics::vectors::*;
+
+/// Height of label text in physical pixels.
+const LABEL_HEIGHT_PX: f64 = 12.0;
+
+/// If more labels than this are in view, the user is zoomed out too far for
+/// labels to be legible, so none are drawn.
+const MAX_VISIBLE_LABELS: usize = 400;
+
+struct Anchor {
+    text_instance: Entity,
+    layer: Entity,
+    position: Point2d,
+}
+
+/// Draws TextInstance entities as screen-space labels.
+pub struct TextLabels {
+    batch: TextBatch,
+    anchors: Vec<Anchor>,
+}
+
+impl TextLabels {
+    pub fn new(world: &mut World) -> Self {
+        Self {
+            batch: TextBatch::new(world),
+            anchors: Vec::new(),
+        }
+    }
+
+    pub fn set_render_order(&self, world: &mut World, render_order: i32) {
+        self.batch.set_render_order(world, render_order);
+    }
+
+    pub fn set_color(&self, world: &mut World, color: Vector4f) {
+        self.batch.set_color(world, color);
+    }
+
+    /// Gathers the world space anchor of every TextInstance, except those in
+    /// hidden or abstract cells.
+    pub fn gather(&mut self, world: &mut World) {
+        self.anchors.clear();
+        let mut query = world.query::<(Entity, &TextInstance)>();
+        for (entity, text_instance) in query.iter(world) {
+            if is_concealed(world, text_instance.cell_instance) {
+                continue;
+            }
+            let position = Point::new(0.0, 0.0).affine_transform(&text_instance.world_transform);
+            self.anchors.push(Anchor {
+                text_instance: entity,
+                layer: text_instance.layer,
+                position: Point2d::new(position.x(), position.y()),
+            });
+        }
+    }
+
+    /// Rebuilds the label geometry for the current camera. Labels keep a
+    /// constant size on screen and are culled when too many are in view.
+    pub fn update(
+        &mut self,
+        world: &mut World,
+        gl: &glow::Context,
+        camera: &Camera,
+        window_size: (u32, u32),
+    ) {
+        let bounds = camera.world_bounds();
+        let mut visible = Vec::new();
+        for anchor in &self.anchors {
+            let p = anchor.position;
+            if p.x < bounds.min_x || p.x > bounds.max_x {
+                continue;
+            }
+            if p.y < bounds.min_y || p.y > bounds.max_y {
+                continue;
+            }
+            if !world.get::<Layer>(anchor.layer).is_some_and(|l| l.visible) {
+                continue;
+            }
+            visible.push(anchor);
+            if visible.len() > MAX_VISIBLE_LABELS {
+                self.batch.labels.clear();
+                self.batch.update(world, gl);
+                return;
+            }
+        }
+
+        self.batch.labels.clear();
+        for anchor in visible {
+            let text_instance = world.get::<TextInstance>(anchor.text_instance).unwrap();
+            let text_def = world
+                .get::<TextDefinition>(text_instance.text_definition)
+                .unwrap();
+            self.batch.labels.push(Label {
+                text: text_def.string.clone(),
+                position: anchor.position,
+                horizontal: text_def.presentation.horizontal,
+                vertical: text_def.presentation.vertical,
+            });
+        }
+
+        let right = camera.right();
+        self.batch.right = Vector2d::new(right.x, right.y);
+        self.batch.up = Vector2d::new(camera.up.x, camera.up.y);
+        self.batch.height = LABEL_HEIGHT_PX * camera.width / window_size.0 as f64;
+        self.batch.update(world, gl);
+    }
+}
@@ -0,0 +1,115 @@
+use crate::graphics::bounds::BoundingBox;
+
+/// Tiles with more vertices than this are split into quadrants. Small tiles
+/// let the renderer skip geometry that is outside the view, and keep every
+/// vertex buffer far below WebGL size limits and the u32 index range.
+pub const TILE_VERTEX_BUDGET: usize = 1 << 16;
+
+/// Quadtree depth beyond which tiles are no longer split spatially. Shapes
+/// that are still over budget at this depth are chunked in order instead.
+const MAX_TILE_DEPTH: usize = 16;
+
+/// A shape to be placed in a tile, described by its bounds and the number of
+/// vertices in its triangulation.
+#[derive(Clone, Copy, Debug)]
+pub struct TileItem {
+    pub bounds: BoundingBox,
+    pub vertex_count: usize,
+}
+
+/// A group of shapes that share a vertex buffer.
+#[derive(Clone, Debug)]
+pub struct Tile {
+    /// Indices into the slice given to `partition`.
+    pub items: Vec<usize>,
+
+    /// Union of the bounds of the items.
+    pub bounds: BoundingBox,
+    pub vertex_count: usize,
+}
+
+/// Groups shapes into tiles of at most `budget` vertices with a quadtree over
+/// their bounds. Each shape goes to the quadrant that contains its center.
+///
+/// A shape that is over budget on its own gets a tile to itself.
+pub fn partition(items: &[TileItem], budget: usize) -> Vec<Tile> {
+    let mut tiles = Vec::new();
+    let indices: Vec<usize> = (0..items.len()).collect();
+    split(items, indices, budget, 0, &mut tiles);
+    tiles
+}
+
+fn split(
+    items: &[TileItem],
+    indices: Vec<usize>,
+    budget: usize,
+    depth: usize,
+    tiles: &mut Vec<Tile>,
+) {
+    if indices.is_empty() {
+        return;
+    }
+
+    let tile = make_tile(items, indices);
+    if tile.vertex_count <= budget || tile.items.len() == 1 {
+        tiles.push(tile);
+        return;
+    }
+
+    if depth >= MAX_TILE_DEPTH {
+        chunk(items, tile.items, budget, tiles);
+        return;
+    }
+
+    let mid_x = (tile.bounds.min_x + tile.bounds.max_x) / 2.0;
+    let mid_y = (tile.bounds.min_y + tile.bounds.max_y) / 2.0;
+    let mut quadrants: [Vec<usize>; 4] = Default::default();
+    for index in tile.items {
+        let (x, y) = center(&items[index].bounds);
+        let quadrant = (x >= mid_x) as usize + 2 * (y >= mid_y) as usize;
+        quadrants[quadrant].push(index);
+    }
+
+    for quadrant in quadrants {
+        split(items, quadrant, budget, depth + 1, tiles);
+    }
+}
+
+/// Splits items in order into tiles that fit the budget.
+fn chunk(items: &[TileItem], indices: Vec<usize>, budget: usize, tiles: &mut Vec<Tile>) {
+    let mut current = Vec::new();
+    let mut vertex_count = 0;
+    for index in indices {
+        let count = items[index].vertex_count;
+        if !current.is_empty() && vertex_count + count > budget {
+            tiles.push(make_tile(items, std::mem::take(&mut current)));
+            vertex_count = 0;
+        }
+        current.push(index);
+        vertex_count += count;
+    }
+    if !current.is_empty() {
+        tiles.push(make_tile(items, current));
+    }
+}
+
+fn make_tile(items: &[TileItem], indices: Vec<usize>) -> Tile {
+    let mut bounds = BoundingBox::new();
+    let mut vertex_count = 0;
+    for index in &indices {
+        bounds.encompass(&items[*index].bounds);
+        vertex_count += items[*index].vertex_count;
+    }
+    Tile {
+        items: indices,
+        bounds,
+        vertex_count,
+    }
+}
+
+fn center(bounds: &BoundingBox) -> (f64, f64) {
+    (
+        (bounds.min_x + bounds.max_x) / 2.0,
+        (bounds.min_y + bounds.max_y) / 2.0,
+    )
+}
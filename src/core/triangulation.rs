@@ -1,13 +1,16 @@
-use geo::AffineOps;
 use geo::AffineTransform;
+use geo::Coord;
 use geo::TriangulateEarcut;
 
+use crate::graphics::bounds::BoundingBox;
 use crate::graphics::geometry::Geometry;
 use crate::graphics::vectors::*;
 
+/// Vertices are kept in f64 so that they can be made relative to a nearby
+/// origin before they are converted to f32 for the GPU.
 pub struct Triangulation {
     pub indices: Vec<u32>,
-    pub vertices: Vec<Point2f>,
+    pub vertices: Vec<Point2d>,
 }
 
 impl Triangulation {
@@ -22,7 +25,7 @@ impl Triangulation {
         let earcut_result = polygon.earcut_triangles_raw();
         let mut vertices = Vec::with_capacity(earcut_result.vertices.len() / 2);
         for coord in earcut_result.vertices.chunks(2) {
-            vertices.push(Point2f::new(coord[0] as f32, coord[1] as f32));
+            vertices.push(Point2d::new(coord[0], coord[1]));
         }
         let mut indices = Vec::with_capacity(earcut_result.triangle_indices.len());
         for i in earcut_result.triangle_indices {
@@ -31,23 +34,52 @@ impl Triangulation {
         Self { indices, vertices }
     }
 
-    // TODO: Make this more streamlined by taking an f32 AffineTransform and avoiding
-    // the back-and-forth conversion to geo::Point.
     pub fn affine_transform(&self, transform: &AffineTransform) -> Self {
         let indices = self.indices.clone();
         let vertices = self
             .vertices
             .iter()
-            .map(|v| from_geo(to_geo(v).affine_transform(transform)))
+            .map(|v| {
+                let c = transform.apply(Coord { x: v.x, y: v.y });
+                Point2d::new(c.x, c.y)
+            })
             .collect();
         Self { indices, vertices }
     }
 
-    pub fn append_to(&self, geo: &mut Geometry) {
+    pub fn bounds(&self) -> BoundingBox {
+        let mut bounds = BoundingBox::new();
+        for v in &self.vertices {
+            bounds.encompass(&BoundingBox {
+                min_x: v.x,
+                min_y: v.y,
+                max_x: v.x,
+                max_y: v.y,
+            });
+        }
+        bounds
+    }
+
+    /// Total area of the triangles.
+    pub fn area(&self) -> f64 {
+        let mut area = 0.0;
+        for triangle in self.indices.chunks_exact(3) {
+            let a = self.vertices[triangle[0] as usize];
+            let b = self.vertices[triangle[1] as usize];
+            let c = self.vertices[triangle[2] as usize];
+            let cross = (b - a).perp(&(c - a));
+            area += cross.abs() / 2.0;
+        }
+        area
+    }
+
+    /// Appends the triangles to the geometry, relative to the given origin.
+    pub fn append_to(&self, geo: &mut Geometry, origin: &Point2d) {
         let start_index = (geo.positions.len() / 3) as u32;
         for vert in &self.vertices {
-            geo.positions.push(vert.x);
-            geo.positions.push(vert.y);
+            let v = vert - origin;
+            geo.positions.push(v.x as f32);
+            geo.positions.push(v.y as f32);
             geo.positions.push(0.0);
         }
         for index in &self.indices {
@@ -55,11 +87,3 @@ impl Triangulation {
         }
     }
 }
-
-fn to_geo(p: &Point2f) -> geo::Point<f64> {
-    geo::Point::new(p.x as f64, p.y as f64)
-}
-
-fn from_geo(p: geo::Point<f64>) -> Point2f {
-    Point2f::new(p.x() as f32, p.y() as f32)
-}
@@ -0,0 +1,96 @@
+use bevy_ecs::resource::Resource;
+
+use crate::graphics::bounds::BoundingBox;
+
+const MICRONS_PER_METER: f64 = 1e6;
+
+/// Units from the header of the GDS library, stored as a World resource.
+///
+/// All coordinates in the World are in database units. Use this resource to
+/// convert them into physical lengths.
+#[derive(Resource, Clone, Copy, Debug, PartialEq)]
+pub struct LibraryUnits {
+    /// Size of one database unit in meters.
+    pub db_unit: f64,
+
+    /// Size of one database unit in user units.
+    pub db_unit_in_user_units: f64,
+}
+
+impl LibraryUnits {
+    pub fn new(db_unit: f64, db_unit_in_user_units: f64) -> Self {
+        Self {
+            db_unit,
+            db_unit_in_user_units,
+        }
+    }
+
+    /// Size of one user unit in meters.
+    pub fn user_unit(&self) -> f64 {
+        self.db_unit / self.db_unit_in_user_units
+    }
+
+    pub fn microns_per_db_unit(&self) -> f64 {
+        self.db_unit * MICRONS_PER_METER
+    }
+
+    pub fn to_microns(&self, value: f64) -> f64 {
+        value * self.microns_per_db_unit()
+    }
+
+    pub fn from_microns(&self, microns: f64) -> f64 {
+        microns / self.microns_per_db_unit()
+    }
+
+    pub fn point_to_microns(&self, (x, y): (f64, f64)) -> (f64, f64) {
+        (self.to_microns(x), self.to_microns(y))
+    }
+
+    /// Describes a point in database units as microns, followed by the
+    /// database units rounded to whole numbers.
+    pub fn describe_point(&self, (x, y): (f64, f64)) -> String {
+        let (x_um, y_um) = self.point_to_microns((x, y));
+        format!(
+            "x {} µm, y {} µm ({}, {} dbu)",
+            format_length(x_um, 4),
+            format_length(y_um, 4),
+            format_length(x, 0),
+            format_length(y, 0)
+        )
+    }
+
+    pub fn bounds_to_microns(&self, bounds: &BoundingBox) -> BoundingBox {
+        if bounds.is_empty() {
+            return *bounds;
+        }
+        BoundingBox {
+            min_x: self.to_microns(bounds.min_x),
+            min_y: self.to_microns(bounds.min_y),
+            max_x: self.to_microns(bounds.max_x),
+            max_y: self.to_microns(bounds.max_y),
+        }
+    }
+}
+
+/// Matches the GDS defaults: 1 nm database units and 1 µm user units.
+impl Default for LibraryUnits {
+    fn default() -> Self {
+        Self::new(1e-9, 1e-3)
+    }
+}
+
+/// Formats a length with at most the given number of decimals, dropping
+/// trailing zeros.
+pub fn format_length(value: f64, decimals: usize) -> String {
+    let text = format!("{:.*}", decimals, value);
+    let text = if text.contains('.') {
+        text.trim_end_matches('0').trim_end_matches('.')
+    } else {
+        &text
+    };
+    if text == "-0" {
+        "0".to_string()
+    } else {
+        text.to_string()
+    }
+}
@@ -0,0 +1,193 @@
+use crate::core::components::LayerKey;
+use crate::core::units::format_length;
+
+/// Query parameters written by ViewState::to_query.
+pub const QUERY_KEYS: &[&str] = &["x", "y", "w", "cell", "hide", "color", "sel"];
+
+/// Camera, root cell, layer settings and selection, in a form that can be
+/// written to and read from URL query parameters. Positions and lengths are
+/// in microns so that they do not depend on the library's database unit.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ViewState {
+    /// Center of the view.
+    pub center: Option<(f64, f64)>,
+
+    /// Width of the view.
+    pub width: Option<f64>,
+
+    /// Name of the root cell.
+    pub cell: Option<String>,
+
+    pub hidden_layers: Vec<LayerKey>,
+
+    /// Layers whose color differs from the one assigned at load time, with
+    /// colors as six hex digits.
+    pub layer_colors: Vec<(LayerKey, String)>,
+
+    /// Layer of the selected shape, and a point inside it.
+    pub selected: Option<(LayerKey, (f64, f64))>,
+}
+
+impl ViewState {
+    /// Converts the state into query parameters. Unset fields are left out.
+    pub fn to_query(&self) -> Vec<(String, String)> {
+        let mut query = Vec::new();
+        let mut push = |key: &str, value: String| query.push((key.to_string(), value));
+        if let Some((x, y)) = self.center {
+            push("x", format_length(x, 6));
+            push("y", format_length(y, 6));
+        }
+        if let Some(width) = self.width {
+            push("w", format_length(width, 6));
+        }
+        if let Some(cell) = &self.cell {
+            push("cell", cell.clone());
+        }
+        if !self.hidden_layers.is_empty() {
+            let keys: Vec<String> = self
+                .hidden_layers
+                .iter()
+                .map(|key| key.to_string())
+                .collect();
+            push("hide", keys.join(","));
+        }
+        if !self.layer_colors.is_empty() {
+            let colors: Vec<String> = self
+                .layer_colors
+                .iter()
+                .map(|(key, color)| format!("{}:{}", key, color))
+                .collect();
+            push("color", colors.join(","));
+        }
+        if let Some((key, (x, y))) = self.selected {
+            push(
+                "sel",
+                format!("{}:{}:{}", key, format_length(x, 6), format_length(y, 6)),
+            );
+        }
+        query
+    }
+
+    /// Replaces the view state parameters of an existing query with those of
+    /// this state. Other parameters, such as the instance limits, are kept
+    /// in their original order ahead of the view state.
+    pub fn update_query(&self, query: &[(String, String)]) -> Vec<(String, String)> {
+        let mut updated: Vec<(String, String)> = query
+            .iter()
+            .filter(|(key, _)| !QUERY_KEYS.contains(&key.as_str()))
+            .cloned()
+            .collect();
+        updated.extend(self.to_query());
+        updated
+    }
+
+    /// Reads the state from query parameters. Unknown parameters and
+    /// malformed values are ignored, so that a damaged link still restores
+    /// as much as it can.
+    pub fn from_query(query: &[(String, String)]) -> Self {
+        let mut state = Self::default();
+        let mut x = None;
+        let mut y = None;
+        for (key, value) in query {
+            match key.as_str() {
+                "x" => x = value.parse::<f64>().ok().filter(|x| x.is_finite()),
+                "y" => y = value.parse::<f64>().ok().filter(|y| y.is_finite()),
+                "w" => {
+                    state.width = value
+                        .parse::<f64>()
+                        .ok()
+                        .filter(|width| width.is_finite() && *width > 0.0)
+                }
+                "cell" if !value.is_empty() => state.cell = Some(value.clone()),
+                "hide" => {
+                    state.hidden_layers = value
+                        .split(',')
+                        .filter_map(|key| key.parse().ok())
+                        .collect()
+                }
+                "color" => {
+                    state.layer_colors = value
+                        .split(',')
+                        .filter_map(|entry| {
+                            let (key, color) = entry.split_once(':')?;
+                            if color.len() != 6 || !color.chars().all(|c| c.is_ascii_hexdigit()) {
+                                return None;
+                            }
+                            Some((key.parse().ok()?, color.to_lowercase()))
+                        })
+                        .collect()
+                }
+                "sel" => {
+                    let mut parts = value.split(':');
+                    let key = parts.next().and_then(|key| key.parse().ok());
+                    let x = parts.next().and_then(|x| x.parse::<f64>().ok());
+                    let y = parts.next().and_then(|y| y.parse::<f64>().ok());
+                    if let (Some(key), Some(x), Some(y)) = (key, x, y) {
+                        state.selected = Some((key, (x, y)));
+                    }
+                }
+                _ => {}
+            }
+        }
+        if let (Some(x), Some(y)) = (x, y) {
+            state.center = Some((x, y));
+        }
+        state
+    }
+
+    /// Joins the query parameters into a single line, as in `x=1&y=2`.
+    pub fn to_query_string(&self) -> String {
+        let pairs: Vec<String> = self
+            .to_query()
+            .iter()
+            .map(|(key, value)| format!("{}={}", escape(key), escape(value)))
+            .collect();
+        pairs.join("&")
+    }
+
+    /// Reads a state written by to_query_string.
+    pub fn from_query_string(query: &str) -> Self {
+        let query: Vec<(String, String)> = query
+            .split('&')
+            .filter_map(|pair| {
+                let (key, value) = pair.split_once('=')?;
+                Some((unescape(key), unescape(value)))
+            })
+            .collect();
+        Self::from_query(&query)
+    }
+}
+
+/// Percent-encodes the characters that would break up a query string or a
+/// line of text.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '%' | '&' | '=' | '+' | '\t' | '\n' | '\r' => {
+                escaped.push_str(&format!("%{:02X}", c as u8))
+            }
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn unescape(text: &str) -> String {
+    let mut bytes = Vec::with_capacity(text.len());
+    let mut rest = text.as_bytes();
+    while let Some((&byte, tail)) = rest.split_first() {
+        let hex = tail.get(..2).and_then(|hex| std::str::from_utf8(hex).ok());
+        match hex.and_then(|hex| u8::from_str_radix(hex, 16).ok()) {
+            Some(decoded) if byte == b'%' => {
+                bytes.push(decoded);
+                rest = &tail[2..];
+            }
+            _ => {
+                bytes.push(byte);
+                rest = tail;
+            }
+        }
+    }
+    String::from_utf8_lossy(&bytes).into_owned()
+}
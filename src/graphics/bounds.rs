@@ -1,6 +1,8 @@
+use geo::AffineTransform;
+use geo::Coord;
 use geo::Rect;
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct BoundingBox {
     pub min_x: f64,
     pub min_y: f64,
@@ -45,6 +47,40 @@ impl BoundingBox {
     pub fn is_empty(&self) -> bool {
         self.min_x > self.max_x || self.min_y > self.max_y
     }
+
+    /// Returns true if the boxes overlap or touch. Empty boxes intersect
+    /// nothing.
+    pub fn intersects(&self, other: &BoundingBox) -> bool {
+        !self.is_empty()
+            && !other.is_empty()
+            && self.min_x <= other.max_x
+            && other.min_x <= self.max_x
+            && self.min_y <= other.max_y
+            && other.min_y <= self.max_y
+    }
+
+    /// Returns the bounds of this box after it has been transformed.
+    pub fn transform(&self, transform: &AffineTransform) -> Self {
+        if self.is_empty() {
+            return *self;
+        }
+        let mut bounds = BoundingBox::new();
+        for (x, y) in [
+            (self.min_x, self.min_y),
+            (self.max_x, self.min_y),
+            (self.max_x, self.max_y),
+            (self.min_x, self.max_y),
+        ] {
+            let p = transform.apply(Coord { x, y });
+            bounds.encompass(&BoundingBox {
+                min_x: p.x,
+                min_y: p.y,
+                max_x: p.x,
+                max_y: p.y,
+            });
+        }
+        bounds
+    }
 }
 
 impl Default for BoundingBox {
@@ -60,6 +60,18 @@ impl Camera {
         Mat4::look_at_rh(&self.position, &target, &self.up)
     }
 
+    /// Returns the view matrix for vertices that are already relative to the
+    /// camera's x and y position. See Mesh::model_matrix.
+    pub fn get_relative_view_matrix(&self) -> Mat4 {
+        let eye = Point3d::new(0.0, 0.0, self.position.z);
+        Mat4::look_at_rh(&eye, &(eye + self.gaze), &self.up)
+    }
+
+    /// Returns the camera position in the xy plane.
+    pub fn eye(&self) -> Point2d {
+        Point2d::new(self.position.x, self.position.y)
+    }
+
     /// Projects a world space point to NDC space
     pub fn project(&self, point: Point3d) -> Point3d {
         let view_matrix = self.get_view_matrix();
@@ -80,6 +92,26 @@ impl Camera {
         Point3d::new(world.x, world.y, world.z)
     }
 
+    /// Returns the world space direction that points to the right of the screen.
+    pub fn right(&self) -> Vector3d {
+        self.gaze.cross(&self.up)
+    }
+
+    /// Returns the world space rectangle that is visible through the camera.
+    pub fn world_bounds(&self) -> BoundingBox {
+        let mut bounds = BoundingBox::new();
+        for (x, y) in [(-1.0, -1.0), (1.0, -1.0), (1.0, 1.0), (-1.0, 1.0)] {
+            let p = self.unproject(Point3d::new(x, y, 0.0));
+            bounds.encompass(&BoundingBox {
+                min_x: p.x,
+                min_y: p.y,
+                max_x: p.x,
+                max_y: p.y,
+            });
+        }
+        bounds
+    }
+
     /// Sets the world space width and height of the near projection quad.
     pub fn set_size(&mut self, width: f64, height: f64) {
         self.width = width;
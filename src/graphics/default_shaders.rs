@@ -1,6 +1,8 @@
 #[cfg(target_arch = "wasm32")]
 pub const VERTEX_SHADER: &str = r#"#version 100
 attribute vec3 position;
+attribute vec3 instance_row0;
+attribute vec3 instance_row1;
 
 uniform mat4 model;
 uniform mat4 view;
@@ -10,7 +12,10 @@ uniform vec4 color;
 varying vec4 v_color;
 
 void main() {
-    gl_Position = projection * view * model * vec4(position, 1.0);
+    // Each instance places the cell's local geometry in world space.
+    vec3 local = vec3(position.xy, 1.0);
+    vec2 world = vec2(dot(instance_row0, local), dot(instance_row1, local));
+    gl_Position = projection * view * model * vec4(world, position.z, 1.0);
     v_color = color;
 }
 "#;
@@ -18,6 +23,8 @@ void main() {
 #[cfg(not(target_arch = "wasm32"))]
 pub const VERTEX_SHADER: &str = r#"#version 330
 layout (location = 0) in vec3 position;
+layout (location = 1) in vec3 instance_row0;
+layout (location = 2) in vec3 instance_row1;
 
 uniform mat4 model;
 uniform mat4 view;
@@ -27,7 +34,10 @@ uniform vec4 color;
 out vec4 v_color;
 
 void main() {
-    gl_Position = projection * view * model * vec4(position, 1.0);
+    // Each instance places the cell's local geometry in world space.
+    vec3 local = vec3(position.xy, 1.0);
+    vec2 world = vec2(dot(instance_row0, local), dot(instance_row1, local));
+    gl_Position = projection * view * model * vec4(world, position.z, 1.0);
     v_color = color;
 }
 "#;
@@ -1,17 +1,69 @@
 use bevy_ecs::component::Component;
 use bevy_ecs::entity::Entity;
 use bevy_ecs::world::World;
+use geo::AffineTransform;
 use glow::HasContext;
+use std::collections::HashMap;
 
+use crate::graphics::bounds::BoundingBox;
+use crate::graphics::vectors::*;
+
+/// Vertex attribute locations shared by all shaders.
+pub const POSITION_LOCATION: u32 = 0;
+pub const INSTANCE_ROW0_LOCATION: u32 = 1;
+pub const INSTANCE_ROW1_LOCATION: u32 = 2;
+
+/// Number of floats per instance: the two rows of a 2D affine transform.
+const INSTANCE_STRIDE: usize = 6;
+
+/// Instances that are drawn relative to a shared origin, and skipped
+/// together when their bounds are outside the view. Nearby instances are
+/// grouped, so that their offsets from the origin stay small in f32.
+pub struct InstanceGroup {
+    /// World space position that the instance transforms are relative to,
+    /// which is the translation of the group's first instance.
+    pub origin: Point2d,
+
+    /// World space bounds of everything the group's instances draw.
+    pub bounds: BoundingBox,
+
+    instances: Vec<f32>,
+
+    /// Index of the group's first instance in the uploaded buffer.
+    first_instance: usize,
+}
+
+impl InstanceGroup {
+    pub fn instance_count(&self) -> usize {
+        self.instances.len() / INSTANCE_STRIDE
+    }
+
+    /// Per-instance data: the rows (a, b, xoff) and (d, e, yoff) of each
+    /// instance transform, relative to the group origin.
+    pub fn instance_data(&self) -> &[f32] {
+        &self.instances
+    }
+}
+
+/// Vertex data for a mesh, optionally drawn several times with per-instance
+/// transforms.
+///
+/// Instanced geometry with no instances draws nothing. Non-instanced geometry
+/// is drawn once with the identity transform.
 #[derive(Component)]
 pub struct Geometry {
     pub positions: Vec<f32>,
     pub indices: Vec<u32>,
+    instanced: bool,
+    groups: Vec<InstanceGroup>,
+    group_keys: HashMap<(i64, i64), usize>,
     vao: Option<glow::VertexArray>,
     positions_vbo: Option<glow::Buffer>,
     indices_vbo: Option<glow::Buffer>,
+    instances_vbo: Option<glow::Buffer>,
     positions_uploaded: bool,
     indices_uploaded: bool,
+    instances_uploaded: bool,
 }
 
 impl Geometry {
@@ -19,26 +71,106 @@ impl Geometry {
         Self {
             positions: Vec::new(),
             indices: Vec::new(),
+            instanced: false,
+            groups: Vec::new(),
+            group_keys: HashMap::new(),
             vao: None,
             positions_vbo: None,
             indices_vbo: None,
+            instances_vbo: None,
             positions_uploaded: false,
             indices_uploaded: false,
+            instances_uploaded: false,
         }
     }
 
+    /// Creates geometry that is drawn once per pushed instance.
+    pub fn new_instanced() -> Self {
+        let mut geometry = Self::new();
+        geometry.instanced = true;
+        geometry
+    }
+
+    pub fn is_instanced(&self) -> bool {
+        self.instanced
+    }
+
+    pub fn instance_count(&self) -> usize {
+        self.groups.iter().map(InstanceGroup::instance_count).sum()
+    }
+
+    pub fn instance_groups(&self) -> &[InstanceGroup] {
+        &self.groups
+    }
+
+    /// Adds an instance to the group with the given key, which starts at the
+    /// instance's translation if the group is new. The placement is in world
+    /// space, and the bounds are those of everything the instance draws.
+    pub fn push_instance(
+        &mut self,
+        key: (i64, i64),
+        placement: &AffineTransform,
+        bounds: &BoundingBox,
+    ) {
+        let index = *self.group_keys.entry(key).or_insert_with(|| {
+            self.groups.push(InstanceGroup {
+                origin: Point2d::new(placement.xoff(), placement.yoff()),
+                bounds: BoundingBox::new(),
+                instances: Vec::new(),
+                first_instance: 0,
+            });
+            self.groups.len() - 1
+        });
+        let group = &mut self.groups[index];
+        group.bounds.encompass(bounds);
+
+        // Subtracting in f64 keeps the offset exact before it is narrowed.
+        let transform = placement.compose(&AffineTransform::translate(
+            -group.origin.x,
+            -group.origin.y,
+        ));
+        group.instances.extend_from_slice(&[
+            transform.a() as f32,
+            transform.b() as f32,
+            transform.xoff() as f32,
+            transform.d() as f32,
+            transform.e() as f32,
+            transform.yoff() as f32,
+        ]);
+        self.instances_uploaded = false;
+    }
+
+    pub fn clear_instances(&mut self) {
+        self.groups.clear();
+        self.group_keys.clear();
+        self.instances_uploaded = false;
+    }
+
     pub fn destroy(&mut self, gl: &glow::Context) {
         unsafe {
             if let Some(vao) = self.vao.take() {
                 gl.delete_vertex_array(vao)
             }
-            [&mut self.positions_vbo, &mut self.indices_vbo]
-                .iter_mut()
-                .filter_map(|vbo| vbo.take())
-                .for_each(|vbo| gl.delete_buffer(vbo));
+            [
+                &mut self.positions_vbo,
+                &mut self.indices_vbo,
+                &mut self.instances_vbo,
+            ]
+            .iter_mut()
+            .filter_map(|vbo| vbo.take())
+            .for_each(|vbo| gl.delete_buffer(vbo));
         }
     }
 
+    /// Empties the vertex data and schedules it for upload. The GPU objects
+    /// are kept so they can be reused when new data is appended.
+    pub fn clear(&mut self) {
+        self.positions.clear();
+        self.indices.clear();
+        self.positions_uploaded = false;
+        self.indices_uploaded = false;
+    }
+
     /// Replaces the geometry of an entity with self.
     pub fn replace(self: Geometry, world: &mut World, gl: &glow::Context, entity: Entity) {
         if let Some(mut geometry) = world.get_mut::<Geometry>(entity) {
@@ -55,8 +187,113 @@ impl Geometry {
         if !self.indices_uploaded {
             self.upload_indices(gl);
         }
+        if !self.instances_uploaded {
+            self.upload_instances(gl);
+        }
         unsafe {
             gl.bind_vertex_array(self.vao);
+
+            // Attributes that are not backed by a buffer take these constant
+            // values, so shaders that expect instances draw non-instanced
+            // geometry with the identity transform.
+            if self.groups.is_empty() {
+                gl.vertex_attrib_3_f32(INSTANCE_ROW0_LOCATION, 1.0, 0.0, 0.0);
+                gl.vertex_attrib_3_f32(INSTANCE_ROW1_LOCATION, 0.0, 1.0, 0.0);
+            }
+        }
+    }
+
+    /// Issues the draw call for the bound non-instanced geometry.
+    pub fn draw(&self, gl: &glow::Context) {
+        let count = self.indices.len() as i32;
+        unsafe {
+            gl.draw_elements(glow::TRIANGLES, count, glow::UNSIGNED_INT, 0);
+        }
+    }
+
+    /// Issues the draw call for one instance group of the bound geometry.
+    pub fn draw_group(&self, gl: &glow::Context, group: usize) {
+        let group = &self.groups[group];
+        let count = self.indices.len() as i32;
+        unsafe {
+            // WebGL2 has no base instance, so the attributes are pointed at
+            // the group's range of the instance buffer instead.
+            let stride = (INSTANCE_STRIDE * 4) as i32;
+            let offset = group.first_instance as i32 * stride;
+            gl.bind_buffer(glow::ARRAY_BUFFER, self.instances_vbo);
+            gl.vertex_attrib_pointer_f32(
+                INSTANCE_ROW0_LOCATION,
+                3,
+                glow::FLOAT,
+                false,
+                stride,
+                offset,
+            );
+            gl.vertex_attrib_pointer_f32(
+                INSTANCE_ROW1_LOCATION,
+                3,
+                glow::FLOAT,
+                false,
+                stride,
+                offset + 12,
+            );
+            gl.bind_buffer(glow::ARRAY_BUFFER, None);
+            gl.draw_elements_instanced(
+                glow::TRIANGLES,
+                count,
+                glow::UNSIGNED_INT,
+                0,
+                group.instance_count() as i32,
+            );
+        }
+    }
+
+    fn upload_instances(&mut self, gl: &glow::Context) {
+        // Instances are attached to the VAO, which is created along with the
+        // vertex data.
+        if self.vao.is_none() {
+            return;
+        }
+        self.instances_uploaded = true;
+
+        unsafe {
+            gl.bind_vertex_array(self.vao);
+
+            if self.groups.is_empty() {
+                gl.disable_vertex_attrib_array(INSTANCE_ROW0_LOCATION);
+                gl.disable_vertex_attrib_array(INSTANCE_ROW1_LOCATION);
+                gl.bind_vertex_array(None);
+                return;
+            }
+
+            if self.instances_vbo.is_none() {
+                self.instances_vbo =
+                    Some(gl.create_buffer().expect("Failed to create instances VBO"));
+            }
+
+            // The groups are uploaded back to back into one buffer.
+            let mut instances = Vec::with_capacity(self.instance_count() * INSTANCE_STRIDE);
+            for group in &mut self.groups {
+                group.first_instance = instances.len() / INSTANCE_STRIDE;
+                instances.extend_from_slice(&group.instances);
+            }
+
+            let stride = (INSTANCE_STRIDE * 4) as i32;
+            gl.bind_buffer(glow::ARRAY_BUFFER, self.instances_vbo);
+            gl.buffer_data_u8_slice(
+                glow::ARRAY_BUFFER,
+                bytemuck::cast_slice(&instances),
+                glow::DYNAMIC_DRAW,
+            );
+            gl.enable_vertex_attrib_array(INSTANCE_ROW0_LOCATION);
+            gl.vertex_attrib_pointer_f32(INSTANCE_ROW0_LOCATION, 3, glow::FLOAT, false, stride, 0);
+            gl.vertex_attrib_divisor(INSTANCE_ROW0_LOCATION, 1);
+            gl.enable_vertex_attrib_array(INSTANCE_ROW1_LOCATION);
+            gl.vertex_attrib_pointer_f32(INSTANCE_ROW1_LOCATION, 3, glow::FLOAT, false, stride, 12);
+            gl.vertex_attrib_divisor(INSTANCE_ROW1_LOCATION, 1);
+
+            gl.bind_vertex_array(None);
+            gl.bind_buffer(glow::ARRAY_BUFFER, None);
         }
     }
 
@@ -79,8 +316,8 @@ impl Geometry {
                 bytemuck::cast_slice(&self.positions),
                 glow::STATIC_DRAW,
             );
-            gl.enable_vertex_attrib_array(0);
-            gl.vertex_attrib_pointer_f32(0, 3, glow::FLOAT, false, 12, 0);
+            gl.enable_vertex_attrib_array(POSITION_LOCATION);
+            gl.vertex_attrib_pointer_f32(POSITION_LOCATION, 3, glow::FLOAT, false, 12, 0);
         }
         self.positions_uploaded = true;
     }
@@ -118,8 +355,8 @@ impl Geometry {
             // Set up VAO
             gl.bind_vertex_array(self.vao);
             gl.bind_buffer(glow::ARRAY_BUFFER, self.positions_vbo);
-            gl.enable_vertex_attrib_array(0);
-            gl.vertex_attrib_pointer_f32(0, 3, glow::FLOAT, false, 12, 0);
+            gl.enable_vertex_attrib_array(POSITION_LOCATION);
+            gl.vertex_attrib_pointer_f32(POSITION_LOCATION, 3, glow::FLOAT, false, 12, 0);
             gl.bind_buffer(glow::ELEMENT_ARRAY_BUFFER, self.indices_vbo);
 
             // Cleanup
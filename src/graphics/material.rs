@@ -1,5 +1,8 @@
 use super::default_shaders::FRAGMENT_SHADER;
 use super::default_shaders::VERTEX_SHADER;
+use super::geometry::INSTANCE_ROW0_LOCATION;
+use super::geometry::INSTANCE_ROW1_LOCATION;
+use super::geometry::POSITION_LOCATION;
 use bevy_ecs::component::Component;
 use glow::HasContext;
 use indexmap::IndexMap;
@@ -51,9 +54,9 @@ impl Material {
     }
 
     pub(crate) fn create_program(&mut self, gl: &glow::Context) {
-        if self.program.is_some() {
+        if let Some(program) = self.program {
             unsafe {
-                gl.delete_program(self.program.unwrap());
+                gl.delete_program(program);
             }
         }
 
@@ -83,7 +86,11 @@ impl Material {
                 shaders.push(shader);
             }
 
-            // Link program
+            // Link program with fixed attribute locations, so that every
+            // program agrees with the vertex layout of Geometry.
+            gl.bind_attrib_location(program, POSITION_LOCATION, "position");
+            gl.bind_attrib_location(program, INSTANCE_ROW0_LOCATION, "instance_row0");
+            gl.bind_attrib_location(program, INSTANCE_ROW1_LOCATION, "instance_row1");
             gl.link_program(program);
             if !gl.get_program_link_status(program) {
                 let log = gl.get_program_info_log(program);
@@ -1,14 +1,73 @@
 use bevy_ecs::component::Component;
 use bevy_ecs::entity::Entity;
-use glow::HasContext;
 use indexmap::IndexMap;
 use nalgebra::Matrix4;
 use nalgebra::Vector2;
 use nalgebra::Vector3;
 use nalgebra::Vector4;
 
+use crate::graphics::bounds::BoundingBox;
 use crate::graphics::geometry::Geometry;
 use crate::graphics::material::Material;
+use crate::graphics::vectors::*;
+
+/// Features smaller than this many pixels are not worth rasterizing, so
+/// meshes made of them give way to their coarse replacements.
+pub const DETAIL_PIXEL_THRESHOLD: f64 = 1.0;
+
+/// Chooses whether a mesh is drawn at the current zoom level.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LevelOfDetail {
+    /// Drawn at every zoom level.
+    Always,
+
+    /// Drawn when features of the given world space size cover at least
+    /// DETAIL_PIXEL_THRESHOLD pixels.
+    Detail { feature_size: f64 },
+
+    /// Drawn in place of a Detail mesh with the same feature size when
+    /// zoomed out too far to see its features.
+    ///
+    /// Coarse meshes are instanced like the Detail mesh they replace, so a
+    /// zoomed out view still draws one quad per cell instance. Quads of
+    /// neighbouring instances are not merged; the saving is in triangles,
+    /// and instance groups keep the number of draw calls low.
+    Coarse { feature_size: f64 },
+}
+
+impl LevelOfDetail {
+    /// Returns true if the mesh should be drawn when one pixel covers
+    /// `pixel_size` world units.
+    pub fn is_drawn(&self, pixel_size: f64) -> bool {
+        match self {
+            LevelOfDetail::Always => true,
+            LevelOfDetail::Detail { feature_size } => {
+                *feature_size >= pixel_size * DETAIL_PIXEL_THRESHOLD
+            }
+            LevelOfDetail::Coarse { feature_size } => {
+                *feature_size < pixel_size * DETAIL_PIXEL_THRESHOLD
+            }
+        }
+    }
+
+    /// Returns a copy with the given feature size, or self for Always.
+    pub fn with_feature_size(&self, feature_size: f64) -> Self {
+        match self {
+            LevelOfDetail::Always => LevelOfDetail::Always,
+            LevelOfDetail::Detail { .. } => LevelOfDetail::Detail { feature_size },
+            LevelOfDetail::Coarse { .. } => LevelOfDetail::Coarse { feature_size },
+        }
+    }
+
+    pub fn feature_size(&self) -> Option<f64> {
+        match self {
+            LevelOfDetail::Always => None,
+            LevelOfDetail::Detail { feature_size } | LevelOfDetail::Coarse { feature_size } => {
+                Some(*feature_size)
+            }
+        }
+    }
+}
 
 #[derive(Component)]
 pub struct Mesh {
@@ -16,7 +75,18 @@ pub struct Mesh {
     pub material: Entity,
     pub visible: bool,
     pub matrix: Matrix4<f32>,
+
+    /// World space position that the vertex data is relative to. Keeping
+    /// vertices close to the origin avoids f32 precision loss at large
+    /// coordinates; see model_matrix. Instanced geometry uses the origin of
+    /// each instance group instead.
+    pub origin: Point2d,
     pub render_order: i32,
+
+    /// World space bounds of everything the mesh draws. Meshes whose bounds
+    /// are outside the camera view are skipped. None means always draw.
+    pub bounds: Option<BoundingBox>,
+    pub level_of_detail: LevelOfDetail,
     float_uniforms: IndexMap<String, f32>,
     vec2_uniforms: IndexMap<String, Vector2<f32>>,
     vec3_uniforms: IndexMap<String, Vector3<f32>>,
@@ -34,7 +104,10 @@ impl Mesh {
             material,
             visible: true,
             matrix: Matrix4::identity(),
+            origin: Point2d::origin(),
             render_order: 0,
+            bounds: None,
+            level_of_detail: LevelOfDetail::Always,
             float_uniforms: IndexMap::new(),
             vec2_uniforms: IndexMap::new(),
             vec3_uniforms: IndexMap::new(),
@@ -45,6 +118,32 @@ impl Mesh {
         }
     }
 
+    /// Returns true if the mesh is visible, overlaps the view, and suits the
+    /// zoom level, where one pixel covers `pixel_size` world units.
+    pub fn is_drawn(&self, view_bounds: &BoundingBox, pixel_size: f64) -> bool {
+        let in_view = match &self.bounds {
+            Some(bounds) => bounds.intersects(view_bounds),
+            None => true,
+        };
+        self.visible && in_view && self.level_of_detail.is_drawn(pixel_size)
+    }
+
+    /// Returns the model matrix for a view whose eye is at the given world
+    /// position, for use with Camera::get_relative_view_matrix. The offset
+    /// from the eye to the origin is computed in f64, so that only small
+    /// values reach the GPU.
+    pub fn model_matrix(&self, eye: &Point2d) -> Matrix4<f32> {
+        self.model_matrix_at(&self.origin, eye)
+    }
+
+    /// Returns the model matrix for vertex data relative to the given world
+    /// position rather than the mesh origin, such as an instance group.
+    pub fn model_matrix_at(&self, origin: &Point2d, eye: &Point2d) -> Matrix4<f32> {
+        let offset = origin - eye;
+        let translation = Mat4::new_translation(&Vector3d::new(offset.x, offset.y, 0.0));
+        (translation * self.matrix.cast::<f64>()).cast::<f32>()
+    }
+
     pub fn set_float(&mut self, name: &str, value: f32) {
         self.float_uniforms.insert(name.to_string(), value);
     }
@@ -101,11 +200,21 @@ impl Mesh {
         self.bool_uniforms.get(name)
     }
 
-    pub fn draw(&self, gl: &glow::Context, material: &mut Material, geometry: &mut Geometry) {
+    /// Draws the mesh, skipping instance groups outside the view.
+    pub fn draw(
+        &self,
+        gl: &glow::Context,
+        material: &mut Material,
+        geometry: &mut Geometry,
+        eye: &Point2d,
+        view_bounds: &BoundingBox,
+    ) {
         if geometry.indices.is_empty() {
             return;
         }
-        material.set_mat4(gl, "model", &self.matrix);
+        if geometry.is_instanced() && geometry.instance_count() == 0 {
+            return;
+        }
         for (name, value) in &self.float_uniforms {
             material.set_float(gl, name, *value);
         }
@@ -128,13 +237,16 @@ impl Mesh {
             material.set_bool(gl, name, *value);
         }
         geometry.bind(gl);
-        unsafe {
-            gl.draw_elements(
-                glow::TRIANGLES,
-                geometry.indices.len() as i32,
-                glow::UNSIGNED_INT,
-                0,
-            );
+        if !geometry.is_instanced() {
+            material.set_mat4(gl, "model", &self.model_matrix(eye));
+            geometry.draw(gl);
+            return;
+        }
+        for (index, group) in geometry.instance_groups().iter().enumerate() {
+            if group.bounds.intersects(view_bounds) {
+                material.set_mat4(gl, "model", &self.model_matrix_at(&group.origin, eye));
+                geometry.draw_group(gl, index);
+            }
         }
     }
 }
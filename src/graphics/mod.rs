@@ -5,6 +5,7 @@ pub mod material;
 pub mod mesh;
 pub mod renderer;
 pub mod ribbon;
+pub mod text;
 pub mod vectors;
 pub mod viewport;
 
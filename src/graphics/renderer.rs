@@ -9,6 +9,7 @@ use bevy_ecs::query::QueryState;
 use bevy_ecs::system::lifetimeless::Read;
 use bevy_ecs::world::World;
 use glow::*;
+use std::ops::RangeInclusive;
 
 pub struct Renderer {
     gl: glow::Context,
@@ -72,9 +73,8 @@ impl Renderer {
 
     /// Sets the screen space rectangle in which to draw.
     /// This is the region that the camera's projection quad fits to.
-    ///
-    /// NOTE: For now we do not bother scissoring to the viewport, which we will
-    /// need for features like splitting the screen into multiple viewports.
+    /// Drawing is scissored to it, so that it can share the screen with
+    /// other viewports.
     pub fn set_viewport(&mut self, viewport: Viewport) {
         self.viewport = viewport;
     }
@@ -83,28 +83,89 @@ impl Renderer {
         self.clear_color = (r, g, b, a);
     }
 
+    /// Clears the viewport and draws every mesh.
     pub fn render(&mut self, world: &mut World, camera: &Camera) {
+        let viewport = self.viewport;
+        self.draw(world, camera, &viewport, i32::MIN..=i32::MAX);
+    }
+
+    /// Fills the whole viewport with a color, which shows wherever nothing
+    /// else is drawn, such as between the panes of a split view.
+    pub fn fill(&mut self, r: f32, g: f32, b: f32, a: f32) {
+        let vp = self.viewport;
         unsafe {
             let gl = &self.gl;
-            let vp = &self.viewport;
-
             gl.viewport(
                 vp.left as i32,
                 vp.top as i32,
                 vp.width as i32,
                 vp.height as i32,
             );
+            gl.clear_color(r, g, b, a);
+            gl.clear(glow::COLOR_BUFFER_BIT);
+        }
+    }
+
+    /// Clears a rectangle within the viewport and draws the meshes whose
+    /// render order is in the given range into it, for insets such as a
+    /// minimap, or for the panes of a split view, which each have their own
+    /// camera. The rectangle is in pixels from the top left corner of the
+    /// viewport.
+    pub fn render_inset(
+        &mut self,
+        world: &mut World,
+        camera: &Camera,
+        inset: &Viewport,
+        render_orders: RangeInclusive<i32>,
+    ) {
+        // OpenGL puts the origin at the bottom left.
+        let viewport = Viewport {
+            left: self.viewport.left + inset.left,
+            top: self.viewport.top + self.viewport.height - inset.top - inset.height,
+            width: inset.width,
+            height: inset.height,
+        };
+        self.draw(world, camera, &viewport, render_orders);
+    }
+
+    /// Clears the given rectangle, which is in OpenGL window coordinates,
+    /// and draws into it.
+    fn draw(
+        &mut self,
+        world: &mut World,
+        camera: &Camera,
+        vp: &Viewport,
+        render_orders: RangeInclusive<i32>,
+    ) {
+        unsafe {
+            let gl = &self.gl;
+
+            let (left, top, width, height) = (
+                vp.left as i32,
+                vp.top as i32,
+                vp.width as i32,
+                vp.height as i32,
+            );
+            gl.viewport(left, top, width, height);
+            gl.enable(glow::SCISSOR_TEST);
+            gl.scissor(left, top, width, height);
             let (r, g, b, a) = self.clear_color;
             gl.clear_color(r, g, b, a);
             gl.clear(glow::COLOR_BUFFER_BIT);
 
             let projection = camera.get_projection_matrix().cast::<f32>();
-            let view_matrix = camera.get_view_matrix().cast::<f32>();
+            let view_matrix = camera.get_relative_view_matrix().cast::<f32>();
+            let eye = camera.eye();
+
+            let view_bounds = camera.world_bounds();
+            let pixel_size = camera.width / vp.width;
 
             let mesh_query = self.mesh_query.get_or_insert_with(|| world.query());
 
             let meshes = mesh_query.iter(world).filter_map(|(entity, mesh)| {
-                if mesh.visible {
+                if render_orders.contains(&mesh.render_order)
+                    && mesh.is_drawn(&view_bounds, pixel_size)
+                {
                     Some((entity, mesh.geometry, mesh.material, mesh.render_order))
                 } else {
                     None
@@ -123,8 +184,10 @@ impl Renderer {
                 mat.bind(gl);
                 mat.set_mat4(gl, "view", &view_matrix);
                 mat.set_mat4(gl, "projection", &projection);
-                mesh.draw(gl, &mut mat, &mut geo);
+                mesh.draw(gl, &mut mat, &mut geo, &eye, &view_bounds);
             }
+
+            gl.disable(glow::SCISSOR_TEST);
         }
     }
 }
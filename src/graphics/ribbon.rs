@@ -49,6 +49,11 @@ impl Ribbon {
         mesh.visible = true;
     }
 
+    pub fn set_color(&self, world: &mut World, color: Vector4f) {
+        let mesh = world.get_mut::<Mesh>(self.mesh).unwrap().into_inner();
+        mesh.set_vec4("color", color);
+    }
+
     pub fn set_render_order(&self, world: &mut World, render_order: i32) {
         let mesh = world.get_mut::<Mesh>(self.mesh).unwrap().into_inner();
         mesh.render_order = render_order;
@@ -64,11 +69,17 @@ impl Ribbon {
 
         self.show(world);
 
+        // Vertices are relative to the first point of the spine.
+        let origin = points[0];
+        let mesh = world.get_mut::<Mesh>(self.mesh).unwrap().into_inner();
+        mesh.origin = origin;
+
         let mut positions = Vec::new();
         let mut indices = Vec::new();
 
         // Helper function to add a 3D point to positions
         let add_point = |positions: &mut Vec<f32>, p: Point2d| {
+            let p = p - origin;
             positions.extend_from_slice(&[p.x as f32, p.y as f32, 0.0]);
         };
 
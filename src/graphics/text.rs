@@ -0,0 +1,276 @@
+use bevy_ecs::entity::Entity;
+use bevy_ecs::world::World;
+
+use crate::graphics::geometry::Geometry;
+use crate::graphics::material::BlendMode;
+use crate::graphics::material::Material;
+use crate::graphics::mesh::Mesh;
+use crate::graphics::vectors::*;
+
+/// Glyphs are designed on a grid that is 4 units wide and 6 units tall.
+const GLYPH_HEIGHT: f64 = 6.0;
+const GLYPH_ADVANCE: f64 = 6.0;
+const STROKE_WIDTH: f64 = 0.8;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum HorizontalAlign {
+    #[default]
+    Left,
+    Center,
+    Right,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum VerticalAlign {
+    #[default]
+    Top,
+    Middle,
+    Bottom,
+}
+
+#[derive(Clone, Debug)]
+pub struct Label {
+    pub text: String,
+    pub position: Point2d,
+    pub horizontal: HorizontalAlign,
+    pub vertical: VerticalAlign,
+}
+
+/// Draws a batch of single-line labels using a simple stroke font.
+///
+/// Labels are laid out along the `right` and `up` directions, which allows
+/// callers to keep text upright regardless of the camera orientation.
+pub struct TextBatch {
+    mesh: Entity,
+    geometry: Entity,
+    pub labels: Vec<Label>,
+
+    /// Height of capital letters in world units.
+    pub height: f64,
+
+    pub right: Vector2d,
+    pub up: Vector2d,
+}
+
+impl TextBatch {
+    pub fn new(world: &mut World) -> Self {
+        let geometry = world.spawn(Geometry::new()).id();
+
+        let mut material_component = Material::default();
+        material_component.set_blending(BlendMode::SourceOver);
+        let material = world.spawn(material_component).id();
+
+        let mut mesh_component = Mesh::new(geometry, material);
+        mesh_component.visible = false;
+        mesh_component.set_vec4("color", Vector4f::new(1.0, 1.0, 1.0, 1.0));
+        let mesh = world.spawn(mesh_component).id();
+
+        Self {
+            mesh,
+            geometry,
+            labels: Vec::new(),
+            height: 1.0,
+            right: Vector2d::new(1.0, 0.0),
+            up: Vector2d::new(0.0, 1.0),
+        }
+    }
+
+    pub fn hide(&self, world: &mut World) {
+        let mesh = world.get_mut::<Mesh>(self.mesh).unwrap().into_inner();
+        mesh.visible = false;
+    }
+
+    pub fn show(&self, world: &mut World) {
+        let mesh = world.get_mut::<Mesh>(self.mesh).unwrap().into_inner();
+        mesh.visible = true;
+    }
+
+    pub fn set_render_order(&self, world: &mut World, render_order: i32) {
+        let mesh = world.get_mut::<Mesh>(self.mesh).unwrap().into_inner();
+        mesh.render_order = render_order;
+    }
+
+    pub fn set_color(&self, world: &mut World, color: Vector4f) {
+        let mesh = world.get_mut::<Mesh>(self.mesh).unwrap().into_inner();
+        mesh.set_vec4("color", color);
+    }
+
+    pub fn update(&mut self, world: &mut World, gl: &glow::Context) {
+        // Vertices are relative to the first label.
+        let origin = self
+            .labels
+            .first()
+            .map_or_else(Point2d::origin, |label| label.position);
+
+        let mut geometry = Geometry::new();
+        for label in &self.labels {
+            self.append_label(label, &origin, &mut geometry);
+        }
+
+        if geometry.indices.is_empty() {
+            self.hide(world);
+            return;
+        }
+
+        self.show(world);
+        let mesh = world.get_mut::<Mesh>(self.mesh).unwrap().into_inner();
+        mesh.origin = origin;
+        geometry.replace(world, gl, self.geometry);
+    }
+
+    fn append_label(&self, label: &Label, origin: &Point2d, geometry: &mut Geometry) {
+        let scale = self.height / GLYPH_HEIGHT;
+        let width = text_width(&label.text, self.height);
+
+        let dx = match label.horizontal {
+            HorizontalAlign::Left => 0.0,
+            HorizontalAlign::Center => -width / 2.0,
+            HorizontalAlign::Right => -width,
+        };
+        let dy = match label.vertical {
+            VerticalAlign::Top => -self.height,
+            VerticalAlign::Middle => -self.height / 2.0,
+            VerticalAlign::Bottom => 0.0,
+        };
+
+        let start = label.position - origin + self.right * dx + self.up * dy;
+        let start = Point2d::origin() + start;
+        let half_width = 0.5 * STROKE_WIDTH * scale;
+
+        for (index, c) in label.text.chars().enumerate() {
+            let glyph_origin = start + self.right * (index as f64 * GLYPH_ADVANCE * scale);
+            let to_world = |(x, y): (f64, f64)| -> Point2d {
+                glyph_origin + self.right * (x * scale) + self.up * (y * scale)
+            };
+            for_each_stroke(c, |p0, p1| {
+                append_stroke(geometry, to_world(p0), to_world(p1), half_width);
+            });
+        }
+    }
+}
+
+/// Returns the width of a single line of text in world units.
+pub fn text_width(text: &str, height: f64) -> f64 {
+    let count = text.chars().count();
+    if count == 0 {
+        return 0.0;
+    }
+    let scale = height / GLYPH_HEIGHT;
+    ((count - 1) as f64 * GLYPH_ADVANCE + 4.0) * scale
+}
+
+/// Appends a line segment as a quad that is extended by half its width at
+/// both ends, which makes adjoining strokes meet without gaps.
+fn append_stroke(geometry: &mut Geometry, p0: Point2d, p1: Point2d, half_width: f64) {
+    let dir = p1 - p0;
+    let length = dir.norm();
+    let dir = if length > 0.0 {
+        dir / length
+    } else {
+        Vector2d::new(1.0, 0.0)
+    };
+    let normal = Vector2d::new(-dir.y, dir.x) * half_width;
+    let dir = dir * half_width;
+
+    let base = (geometry.positions.len() / 3) as u32;
+    for p in [
+        p0 - dir + normal,
+        p0 - dir - normal,
+        p1 + dir + normal,
+        p1 + dir - normal,
+    ] {
+        geometry
+            .positions
+            .extend_from_slice(&[p.x as f32, p.y as f32, 0.0]);
+    }
+    geometry
+        .indices
+        .extend_from_slice(&[base, base + 1, base + 2, base + 2, base + 1, base + 3]);
+}
+
+/// Invokes the callback for each line segment of the given character, in
+/// glyph grid units.
+fn for_each_stroke(c: char, mut callback: impl FnMut((f64, f64), (f64, f64))) {
+    for polyline in glyph(c).split(';').filter(|s| !s.is_empty()) {
+        let mut previous = None;
+        for point in polyline.split(' ') {
+            let Some((x, y)) = point.split_once(',') else {
+                continue;
+            };
+            let point = (x.parse().unwrap_or(0.0), y.parse().unwrap_or(0.0));
+            if let Some(previous) = previous {
+                callback(previous, point);
+            }
+            previous = Some(point);
+        }
+    }
+}
+
+/// Each glyph is a list of polylines separated by semicolons. Lowercase
+/// letters are drawn as uppercase.
+fn glyph(c: char) -> &'static str {
+    match c.to_ascii_uppercase() {
+        ' ' => "",
+        '0' => "0,0 4,0 4,6 0,6 0,0;0,0 4,6",
+        '1' => "1,5 2,6 2,0;1,0 3,0",
+        '2' => "0,6 4,6 4,3 0,3 0,0 4,0",
+        '3' => "0,6 4,6 4,0 0,0;1,3 4,3",
+        '4' => "0,6 0,3 4,3;4,6 4,0",
+        '5' => "4,6 0,6 0,3 4,3 4,0 0,0",
+        '6' => "4,6 0,6 0,0 4,0 4,3 0,3",
+        '7' => "0,6 4,6 4,0",
+        '8' => "0,0 4,0 4,6 0,6 0,0;0,3 4,3",
+        '9' => "4,3 0,3 0,6 4,6 4,0 0,0",
+        'A' => "0,0 0,4 2,6 4,4 4,0;0,3 4,3",
+        'B' => "0,0 0,6 3,6 4,5 4,4 3,3 0,3;3,3 4,2 4,1 3,0 0,0",
+        'C' => "4,6 0,6 0,0 4,0",
+        'D' => "0,0 0,6 2,6 4,4 4,2 2,0 0,0",
+        'E' => "4,6 0,6 0,0 4,0;0,3 3,3",
+        'F' => "4,6 0,6 0,0;0,3 3,3",
+        'G' => "4,5 4,6 0,6 0,0 4,0 4,3 2,3",
+        'H' => "0,0 0,6;4,0 4,6;0,3 4,3",
+        'I' => "1,6 3,6;2,6 2,0;1,0 3,0",
+        'J' => "4,6 4,0 0,0 0,2",
+        'K' => "0,0 0,6;4,6 0,3 4,0",
+        'L' => "0,6 0,0 4,0",
+        'M' => "0,0 0,6 2,3 4,6 4,0",
+        'N' => "0,0 0,6 4,0 4,6",
+        'O' => "0,0 4,0 4,6 0,6 0,0",
+        'P' => "0,0 0,6 4,6 4,3 0,3",
+        'Q' => "0,0 4,0 4,6 0,6 0,0;2,2 4,0",
+        'R' => "0,0 0,6 4,6 4,3 0,3 4,0",
+        'S' => "4,6 1,6 0,5 0,4 1,3 3,3 4,2 4,1 3,0 0,0",
+        'T' => "0,6 4,6;2,6 2,0",
+        'U' => "0,6 0,0 4,0 4,6",
+        'V' => "0,6 2,0 4,6",
+        'W' => "0,6 1,0 2,3 3,0 4,6",
+        'X' => "0,0 4,6;0,6 4,0",
+        'Y' => "0,6 2,3 4,6;2,3 2,0",
+        'Z' => "0,6 4,6 0,0 4,0",
+        'µ' => "0,-2 0,4;0,1 1,0 3,0 4,1;4,4 4,0",
+        '.' => "2,0 2,1",
+        ',' => "2,1 1,-1",
+        ':' => "2,1 2,2;2,4 2,5",
+        ';' => "2,4 2,5;2,1 1,-1",
+        '-' => "1,3 3,3",
+        '+' => "1,3 3,3;2,2 2,4",
+        '=' => "1,2 3,2;1,4 3,4",
+        '*' => "2,1 2,5;1,2 3,4;1,4 3,2",
+        '/' => "0,0 4,6",
+        '\\' => "0,6 4,0",
+        '_' => "0,0 4,0",
+        '(' => "3,6 2,5 2,1 3,0",
+        ')' => "1,6 2,5 2,1 1,0",
+        '[' => "3,6 2,6 2,0 3,0",
+        ']' => "1,6 2,6 2,0 1,0",
+        '<' => "3,5 1,3 3,1",
+        '>' => "1,5 3,3 1,1",
+        '#' => "1,0 1,6;3,0 3,6;0,2 4,2;0,4 4,4",
+        '%' => "0,0 4,6;0,6 0,5;4,1 4,0",
+        '!' => "2,6 2,2;2,1 2,0",
+        '?' => "0,5 1,6 3,6 4,5 4,4 2,3 2,2;2,1 2,0",
+        '\'' => "2,6 2,5",
+        '"' => "1,6 1,5;3,6 3,5",
+        _ => "0,0 4,0 4,6 0,6 0,0",
+    }
+}
@@ -0,0 +1,94 @@
+use web_sys::HtmlInputElement;
+use yew::prelude::*;
+
+#[derive(Properties, PartialEq)]
+pub struct BookmarkListProps {
+    pub names: Vec<String>,
+
+    /// Bookmarks the current view under the given name.
+    pub add: Callback<String>,
+    pub go_to: Callback<String>,
+    pub remove: Callback<String>,
+}
+
+pub enum BookmarkListMsg {
+    SetName(String),
+    Add,
+}
+
+/// Named views, with a field for bookmarking the current one.
+pub struct BookmarkList {
+    name: String,
+}
+
+impl Component for BookmarkList {
+    type Message = BookmarkListMsg;
+    type Properties = BookmarkListProps;
+
+    fn create(_ctx: &Context<Self>) -> Self {
+        Self {
+            name: String::new(),
+        }
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let set_name = ctx.link().callback(|e: InputEvent| {
+            let input: HtmlInputElement = e.target_unchecked_into();
+            BookmarkListMsg::SetName(input.value())
+        });
+        let onkeydown = ctx.link().batch_callback(|e: KeyboardEvent| {
+            (e.key() == "Enter").then_some(BookmarkListMsg::Add)
+        });
+        let add = ctx.link().callback(|_| BookmarkListMsg::Add);
+
+        html! {
+            <div class="bookmark-list">
+                <div class="bookmark-add">
+                    <input
+                        type="text"
+                        placeholder="Bookmark this view"
+                        value={self.name.clone()}
+                        oninput={set_name}
+                        onkeydown={onkeydown}
+                    />
+                    <button onclick={add} disabled={self.name.trim().is_empty()} title="Add bookmark">
+                        <i class="fas fa-bookmark"></i>
+                    </button>
+                </div>
+                {for ctx.props().names.iter().map(|name| {
+                    let go_to = ctx.props().go_to.clone();
+                    let remove = ctx.props().remove.clone();
+                    let go_to_name = name.clone();
+                    let remove_name = name.clone();
+                    let onclick = Callback::from(move |_: MouseEvent| go_to.emit(go_to_name.clone()));
+                    let onremove = Callback::from(move |e: MouseEvent| {
+                        e.stop_propagation();
+                        remove.emit(remove_name.clone());
+                    });
+                    html! {
+                        <div class="bookmark-item" key={name.clone()} onclick={onclick} title="Go to this view">
+                            <span class="bookmark-name">{name}</span>
+                            <i class="fas fa-fw fa-trash bookmark-remove" onclick={onremove} title="Remove bookmark"></i>
+                        </div>
+                    }
+                })}
+            </div>
+        }
+    }
+
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            BookmarkListMsg::SetName(name) => {
+                self.name = name;
+                true
+            }
+            BookmarkListMsg::Add => {
+                if self.name.trim().is_empty() {
+                    return false;
+                }
+                ctx.props().add.emit(std::mem::take(&mut self.name));
+                true
+            }
+        }
+    }
+}
@@ -0,0 +1,265 @@
+use std::collections::HashSet;
+
+use bevy_ecs::entity::Entity;
+use web_sys::HtmlInputElement;
+use yew::prelude::*;
+
+use crate::core::cell_display::HierarchyLevels;
+use crate::core::cell_proxy::CellProxy;
+use crate::core::cell_proxy::CellProxyList;
+use crate::core::components::CellDisplay;
+
+/// Search results beyond this count are not listed.
+const MAX_SEARCH_RESULTS: usize = 500;
+
+#[derive(Properties, PartialEq)]
+pub struct CellTreeProps {
+    /// Cell instances in depth-first order, as returned by CellProxy::gather.
+    pub cells: CellProxyList,
+    pub zoom_to_cell: Callback<CellProxy>,
+    pub select_root: Callback<CellProxy>,
+
+    /// Sets the display mode of a cell definition.
+    pub set_display: Callback<(Entity, CellDisplay)>,
+
+    /// Range of hierarchy levels whose shapes are drawn.
+    pub levels: HierarchyLevels,
+    pub set_levels: Callback<HierarchyLevels>,
+}
+
+pub enum CellTreeMsg {
+    Toggle(Entity),
+    Search(String),
+}
+
+/// Collapsible tree of cell instances with search by name.
+pub struct CellTree {
+    /// Instances whose expansion differs from the default, which is to
+    /// expand only the root.
+    toggled: HashSet<Entity>,
+    search: String,
+}
+
+impl CellTree {
+    fn is_expanded(&self, cell: &CellProxy) -> bool {
+        (cell.depth == 0) != self.toggled.contains(&cell.instance)
+    }
+
+    /// Two number inputs that pick the shallowest and deepest drawn level.
+    fn view_levels(&self, ctx: &Context<Self>) -> Html {
+        let deepest = ctx
+            .props()
+            .cells
+            .iter()
+            .map(|cell| cell.depth)
+            .max()
+            .unwrap_or(0);
+        let levels = ctx.props().levels;
+        let (min, max) = (levels.min.min(deepest), levels.max.min(deepest));
+
+        // Keeps the range ordered, and draws every level once the maximum
+        // reaches the deepest one.
+        let level_input = |is_min: bool| {
+            let set_levels = ctx.props().set_levels.clone();
+            Callback::from(move |e: Event| {
+                let input: HtmlInputElement = e.target_unchecked_into();
+                let Ok(level) = input.value().parse::<usize>() else {
+                    return;
+                };
+                let level = level.min(deepest);
+                let (min, max) = if is_min {
+                    (level, max.max(level))
+                } else {
+                    (min.min(level), level)
+                };
+                let max = if max == deepest { usize::MAX } else { max };
+                set_levels.emit(HierarchyLevels::new(min, max));
+            })
+        };
+
+        html! {
+            <div class="cell-levels" title="Deeper instances are drawn as boxes">
+                <span>{"Levels"}</span>
+                <input
+                    type="number"
+                    min="0"
+                    max={deepest.to_string()}
+                    value={min.to_string()}
+                    onchange={level_input(true)}
+                />
+                <span>{"to"}</span>
+                <input
+                    type="number"
+                    min="0"
+                    max={deepest.to_string()}
+                    value={max.to_string()}
+                    onchange={level_input(false)}
+                />
+            </div>
+        }
+    }
+
+    fn view_cell(&self, ctx: &Context<Self>, cell: &CellProxy, indent: usize) -> Html {
+        let instance = cell.instance;
+        let zoom_cell = cell.clone();
+        let root_cell = cell.clone();
+        let zoom_to_cell = ctx.props().zoom_to_cell.clone();
+        let select_root = ctx.props().select_root.clone();
+        let context_root = select_root.clone();
+        let context_cell = cell.clone();
+
+        let onclick = Callback::from(move |_: MouseEvent| zoom_to_cell.emit(zoom_cell.clone()));
+        let oncontextmenu = Callback::from(move |e: MouseEvent| {
+            e.prevent_default();
+            context_root.emit(context_cell.clone());
+        });
+        let toggle = ctx.link().callback(move |e: MouseEvent| {
+            e.stop_propagation();
+            CellTreeMsg::Toggle(instance)
+        });
+        let reroot = Callback::from(move |e: MouseEvent| {
+            e.stop_propagation();
+            select_root.emit(root_cell.clone());
+        });
+
+        // Each toggle switches between its own mode and showing contents.
+        let display_toggle = |display: CellDisplay| {
+            let set_display = ctx.props().set_display.clone();
+            let cell_definition = cell.cell_definition;
+            let next = if cell.display == display {
+                CellDisplay::Contents
+            } else {
+                display
+            };
+            Callback::from(move |e: MouseEvent| {
+                e.stop_propagation();
+                set_display.emit((cell_definition, next));
+            })
+        };
+        let toggle_hidden = display_toggle(CellDisplay::Hidden);
+        let toggle_abstract = display_toggle(CellDisplay::Abstract);
+        let active = |display: CellDisplay| {
+            if cell.display == display {
+                "cell-action active"
+            } else {
+                "cell-action"
+            }
+        };
+
+        let caret = if cell.descendant_count == 0 {
+            "fas fa-fw"
+        } else if self.is_expanded(cell) {
+            "fas fa-fw fa-caret-down"
+        } else {
+            "fas fa-fw fa-caret-right"
+        };
+
+        html! {
+            <div
+                class="cell-item"
+                key={instance.to_string()}
+                style={format!("padding-left: {}rem", 0.25 + 0.75 * indent as f32)}
+                onclick={onclick}
+                oncontextmenu={oncontextmenu}
+                title="Click to zoom, right click to view this cell on its own"
+            >
+                <i class={caret} onclick={toggle}></i>
+                <span class="cell-name">{&*cell.name}</span>
+                if cell.instance_count > 1 {
+                    <span class="cell-count">{format!("×{}", cell.instance_count)}</span>
+                }
+                <span class="cell-actions">
+                    <i
+                        class={classes!("fas", "fa-fw", "fa-eye-slash", active(CellDisplay::Hidden))}
+                        onclick={toggle_hidden}
+                        title="Hide every instance of this cell"
+                    ></i>
+                    <i
+                        class={classes!("fas", "fa-fw", "fa-vector-square", active(CellDisplay::Abstract))}
+                        onclick={toggle_abstract}
+                        title="Draw every instance of this cell as a box"
+                    ></i>
+                    <i class="fas fa-fw fa-sitemap cell-action" onclick={reroot} title="View this cell on its own"></i>
+                </span>
+            </div>
+        }
+    }
+}
+
+impl Component for CellTree {
+    type Message = CellTreeMsg;
+    type Properties = CellTreeProps;
+
+    fn create(_ctx: &Context<Self>) -> Self {
+        Self {
+            toggled: HashSet::new(),
+            search: String::new(),
+        }
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let cells = &ctx.props().cells;
+        let search = ctx.link().callback(|e: InputEvent| {
+            let input: HtmlInputElement = e.target_unchecked_into();
+            CellTreeMsg::Search(input.value())
+        });
+
+        let mut items = Vec::new();
+        if self.search.is_empty() {
+            let mut index = 0;
+            while index < cells.len() {
+                let cell = &cells[index];
+                items.push(self.view_cell(ctx, cell, cell.depth));
+                index += 1;
+                if !self.is_expanded(cell) {
+                    index += cell.descendant_count;
+                }
+            }
+        } else {
+            let needle = self.search.to_lowercase();
+            let mut matches = cells
+                .iter()
+                .filter(|cell| cell.name.to_lowercase().contains(&needle));
+            for cell in matches.by_ref().take(MAX_SEARCH_RESULTS) {
+                items.push(self.view_cell(ctx, cell, 0));
+            }
+            let remaining = matches.count();
+            if remaining > 0 {
+                items.push(html! {
+                    <div class="cell-more" key="more">{format!("{} more", remaining)}</div>
+                });
+            }
+        }
+
+        html! {
+            <div class="cell-tree">
+                <input
+                    type="search"
+                    class="cell-search"
+                    placeholder="Search cells"
+                    value={self.search.clone()}
+                    oninput={search}
+                />
+                {self.view_levels(ctx)}
+                <div class="cell-list">
+                    {for items}
+                </div>
+            </div>
+        }
+    }
+
+    fn update(&mut self, _ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            CellTreeMsg::Toggle(instance) => {
+                if !self.toggled.remove(&instance) {
+                    self.toggled.insert(instance);
+                }
+                true
+            }
+            CellTreeMsg::Search(search) => {
+                self.search = search;
+                true
+            }
+        }
+    }
+}
@@ -0,0 +1,28 @@
+use yew::prelude::*;
+
+#[derive(Properties, PartialEq)]
+pub struct InspectorProps {
+    /// Label and value pairs from ShapeReport::rows.
+    pub rows: Vec<(String, String)>,
+}
+
+/// Floating panel that describes the hovered or selected shape.
+#[function_component(Inspector)]
+pub fn inspector(props: &InspectorProps) -> Html {
+    if props.rows.is_empty() {
+        return html! {};
+    }
+
+    html! {
+        <div class="inspector">
+            <table>
+                {for props.rows.iter().map(|(label, value)| html! {
+                    <tr>
+                        <th>{label}</th>
+                        <td>{value}</td>
+                    </tr>
+                })}
+            </table>
+        </div>
+    }
+}
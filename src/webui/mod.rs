@@ -1,6 +1,9 @@
 pub mod app;
 
+mod bookmarks;
+mod cell_tree;
 mod home_page;
+mod inspector;
 mod sidebar;
 mod toast;
 mod viewer_page;
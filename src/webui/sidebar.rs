@@ -2,7 +2,13 @@ use bevy_ecs::entity::Entity;
 use web_sys::HtmlInputElement;
 use yew::prelude::*;
 
+use crate::core::cell_display::HierarchyLevels;
+use crate::core::cell_proxy::CellProxy;
+use crate::core::cell_proxy::CellProxyList;
+use crate::core::components::CellDisplay;
 use crate::core::layer_proxy::LayerProxy;
+use crate::webui::bookmarks::BookmarkList;
+use crate::webui::cell_tree::CellTree;
 
 // Disabled the color picker after introducing special blending modes.
 const ENABLE_COLOR_PICKER: bool = false;
@@ -11,6 +17,16 @@ const ENABLE_COLOR_PICKER: bool = false;
 pub struct SidebarProps {
     pub layers: Vec<LayerProxy>,
     pub update_layer: Callback<LayerProxy>,
+    pub cells: CellProxyList,
+    pub zoom_to_cell: Callback<CellProxy>,
+    pub select_root: Callback<CellProxy>,
+    pub set_cell_display: Callback<(Entity, CellDisplay)>,
+    pub hierarchy_levels: HierarchyLevels,
+    pub set_hierarchy_levels: Callback<HierarchyLevels>,
+    pub bookmarks: Vec<String>,
+    pub add_bookmark: Callback<String>,
+    pub go_to_bookmark: Callback<String>,
+    pub remove_bookmark: Callback<String>,
 }
 
 pub enum SidebarMsg {
@@ -41,6 +57,12 @@ impl Component for Sidebar {
                     <button onclick={hide_all}>{"Hide All"}</button>
                     <button onclick={show_all}>{"Show All"}</button>
                 </div>
+                <BookmarkList
+                    names={ctx.props().bookmarks.clone()}
+                    add={ctx.props().add_bookmark.clone()}
+                    go_to={ctx.props().go_to_bookmark.clone()}
+                    remove={ctx.props().remove_bookmark.clone()}
+                />
                 <div class="layer-list">
                     {ctx.props().layers.iter().filter_map(|layer| {
                         if layer.is_empty {
@@ -85,7 +107,7 @@ impl Component for Sidebar {
                                         html! {}
                                     }
                                 }
-                                <span class="layer-index">{format!("Layer {}", layer.index)}</span>
+                                <span class="layer-index">{format!("Layer {}", layer.key)}</span>
                                 <input
                                     type="range"
                                     min="0"
@@ -99,6 +121,14 @@ impl Component for Sidebar {
                         })
                     }).collect::<Html>()}
                 </div>
+                <CellTree
+                    cells={ctx.props().cells.clone()}
+                    zoom_to_cell={ctx.props().zoom_to_cell.clone()}
+                    select_root={ctx.props().select_root.clone()}
+                    set_display={ctx.props().set_cell_display.clone()}
+                    levels={ctx.props().hierarchy_levels}
+                    set_levels={ctx.props().set_hierarchy_levels.clone()}
+                />
             </div>
         }
     }
@@ -1,3 +1,4 @@
+use bevy_ecs::entity::Entity;
 use bevy_ecs::world::World;
 use gloo_timers::future::TimeoutFuture;
 use serde::Serialize;
@@ -6,7 +7,10 @@ use wasm_bindgen::JsCast;
 use wasm_bindgen_futures::spawn_local;
 use wasm_bindgen_futures::JsFuture;
 use web_sys::window;
+use web_sys::Element;
 use web_sys::HtmlCanvasElement;
+use web_sys::HtmlSelectElement;
+use web_sys::KeyboardEvent;
 use web_sys::PointerEvent;
 use web_sys::Request;
 use web_sys::RequestInit;
@@ -21,16 +25,26 @@ use yew_router::prelude::*;
 
 use crate::core::app_controller::AppController;
 use crate::core::app_controller::Theme;
+use crate::core::bookmarks::Bookmarks;
+use crate::core::cell_display::HierarchyLevels;
+use crate::core::cell_proxy::CellProxy;
+use crate::core::cell_proxy::CellProxyList;
+use crate::core::components::CellDefinition;
+use crate::core::components::CellDisplay;
+use crate::core::instancer::InstanceLimits;
 use crate::core::instancer::Instancer;
 use crate::core::layer_proxy::LayerProxy;
+use crate::core::loader::LoadError;
 use crate::core::loader::Loader;
 use crate::core::root_finder::RootFinder;
+use crate::core::view_state::ViewState;
 use crate::graphics::renderer::Renderer;
 use crate::graphics::vectors::Vector2u;
 use crate::rsutils::resize_observer::ResizeObserver;
 use crate::webui::app::Route;
 use crate::webui::home_page::has_dropped_file;
 use crate::webui::home_page::take_dropped_file;
+use crate::webui::inspector::Inspector;
 use crate::webui::sidebar::Sidebar;
 use crate::webui::toast::ToastContainer;
 use crate::webui::toast::ToastManager;
@@ -45,13 +59,34 @@ pub enum ViewerMsg {
     SpawnLoader(Vec<u8>),
     SpawnInstancer(Box<World>),
     StashWorld(Box<World>),
+    NoRootCell(Box<World>),
+    LoadFailed(LoadError),
+    ShowToast(String),
+    SetTopCells(Vec<String>, String),
+    SelectTopCell(String),
     SetStatus(String),
     Render,
     Resize,
     Tick,
     RemoveToast(usize),
     UpdateLayer(LayerProxy),
+    ZoomToCell(CellProxy),
+    SelectRoot(CellProxy),
+    SetCellDisplay((Entity, CellDisplay)),
+    SetHierarchyLevels(HierarchyLevels),
     ToggleTheme,
+    ToggleGrid,
+    ToggleMinimap,
+    ToggleSplitView,
+    ToggleLinkedCameras,
+    ToggleRulerMode,
+    ToggleRulerSnapping,
+    ClearRulers,
+    AddBookmark(String),
+    GoToBookmark(String),
+    RemoveBookmark(String),
+    GoBack,
+    GoForward,
     PointerDown(PointerEvent),
     PointerMove(PointerEvent),
     PointerUp,
@@ -66,12 +101,60 @@ pub enum ViewerMsg {
 
 pub struct ViewerPage {
     canvas_ref: NodeRef,
+
+    /// Shows the world position under the pointer. Its text is written
+    /// directly rather than through a re-render, since it changes on every
+    /// pointer move.
+    readout_ref: NodeRef,
     controller: Option<AppController>,
     toast_manager: ToastManager,
     layer_proxies: Vec<LayerProxy>,
+    cell_proxies: CellProxyList,
+    hierarchy_levels: HierarchyLevels,
     theme: Theme,
     status: String,
 
+    /// Names of all top-level cells, and the one being displayed.
+    top_cells: Vec<String>,
+    top_cell: Option<String>,
+
+    /// The selected shapes as CSV, or None if nothing is selected.
+    selection_csv: Option<String>,
+
+    /// Mirrors the ruler settings of the controller, and the number of
+    /// finished rulers.
+    ruler_mode: bool,
+    ruler_snapping: bool,
+    ruler_count: usize,
+    grid_visible: bool,
+    minimap_visible: bool,
+
+    /// Mirrors the split view of the controller. The layer list shows the
+    /// layers of the active pane, so it is refreshed when that changes.
+    split_view: bool,
+    cameras_linked: bool,
+    active_pane: usize,
+
+    /// Names of the bookmarks of this file, which are kept in local storage.
+    bookmarks: Vec<String>,
+
+    /// Depth and instance limits, which can be set with the `max_depth` and
+    /// `max_instances` URL query parameters.
+    instance_limits: InstanceLimits,
+
+    /// Handles the keyboard shortcuts for the camera history. It listens on
+    /// the window, and is removed when the page is destroyed.
+    keydown_listener: Option<Closure<dyn FnMut(KeyboardEvent)>>,
+
+    /// The shape shown in the inspector, and its description.
+    inspected_shape: Option<Entity>,
+    inspector_rows: Vec<(String, String)>,
+
+    /// The view state last written to the URL, and when it was written, in
+    /// milliseconds since the epoch.
+    url_query: Vec<(String, String)>,
+    url_updated_at: f64,
+
     /// The UI is read-only until the GDS file is fully loaded.
     enabled: bool,
 }
@@ -110,9 +193,12 @@ impl Component for ViewerPage {
 
         Self {
             canvas_ref,
+            readout_ref: NodeRef::default(),
             controller,
             toast_manager,
             layer_proxies,
+            cell_proxies: CellProxyList::default(),
+            hierarchy_levels: HierarchyLevels::default(),
             theme: if is_dark_theme {
                 Theme::Dark
             } else {
@@ -120,10 +206,37 @@ impl Component for ViewerPage {
             },
             enabled: false,
             status: "Fetching GDS".to_string(),
+            top_cells: Vec::new(),
+            top_cell: None,
+            selection_csv: None,
+            ruler_mode: false,
+            ruler_snapping: true,
+            ruler_count: 0,
+            grid_visible: true,
+            minimap_visible: true,
+            split_view: false,
+            cameras_linked: false,
+            active_pane: 0,
+            bookmarks: Vec::new(),
+            instance_limits: InstanceLimits::from_query(
+                &ctx.link()
+                    .location()
+                    .and_then(|location| location.query::<Vec<(String, String)>>().ok())
+                    .unwrap_or_default(),
+            ),
+            keydown_listener: None,
+            url_query: Vec::new(),
+            url_updated_at: 0.0,
+            inspected_shape: None,
+            inspector_rows: Vec::new(),
         }
     }
 
     fn destroy(&mut self, _ctx: &Context<Self>) {
+        if let (Some(window), Some(listener)) = (window(), self.keydown_listener.take()) {
+            let _ = window
+                .remove_event_listener_with_callback("keydown", listener.as_ref().unchecked_ref());
+        }
         self.controller = None;
     }
 
@@ -179,7 +292,27 @@ impl Component for ViewerPage {
 
         let on_remove_toast = ctx.link().callback(ViewerMsg::RemoveToast);
         let update_layer = ctx.link().callback(ViewerMsg::UpdateLayer);
+        let zoom_to_cell = ctx.link().callback(ViewerMsg::ZoomToCell);
+        let select_root = ctx.link().callback(ViewerMsg::SelectRoot);
+        let set_cell_display = ctx.link().callback(ViewerMsg::SetCellDisplay);
+        let set_hierarchy_levels = ctx.link().callback(ViewerMsg::SetHierarchyLevels);
         let toggle_theme = ctx.link().callback(|_| ViewerMsg::ToggleTheme);
+        let toggle_grid = ctx.link().callback(|_| ViewerMsg::ToggleGrid);
+        let toggle_minimap = ctx.link().callback(|_| ViewerMsg::ToggleMinimap);
+        let toggle_split_view = ctx.link().callback(|_| ViewerMsg::ToggleSplitView);
+        let toggle_linked_cameras = ctx.link().callback(|_| ViewerMsg::ToggleLinkedCameras);
+        let toggle_ruler_mode = ctx.link().callback(|_| ViewerMsg::ToggleRulerMode);
+        let toggle_ruler_snapping = ctx.link().callback(|_| ViewerMsg::ToggleRulerSnapping);
+        let clear_rulers = ctx.link().callback(|_| ViewerMsg::ClearRulers);
+        let go_back = ctx.link().callback(|_| ViewerMsg::GoBack);
+        let go_forward = ctx.link().callback(|_| ViewerMsg::GoForward);
+        let add_bookmark = ctx.link().callback(ViewerMsg::AddBookmark);
+        let go_to_bookmark = ctx.link().callback(ViewerMsg::GoToBookmark);
+        let remove_bookmark = ctx.link().callback(ViewerMsg::RemoveBookmark);
+        let select_top_cell = ctx.link().callback(|e: Event| {
+            let select: HtmlSelectElement = e.target_unchecked_into();
+            ViewerMsg::SelectTopCell(select.value())
+        });
         let is_dark_theme = self.theme.is_dark();
 
         html! {
@@ -204,12 +337,109 @@ impl Component for ViewerPage {
                         <button class="floating-button" onclick={toggle_theme} disabled={!self.enabled}>
                             <i class={format!("fas fa-{} fa-lg", if is_dark_theme { "sun" } else { "moon" })}></i>
                         </button>
+                        <button class="floating-button" onclick={go_back} disabled={!self.enabled} title="Back (Alt+Left)">
+                            <i class="fas fa-chevron-left fa-lg"></i>
+                        </button>
+                        <button class="floating-button" onclick={go_forward} disabled={!self.enabled} title="Forward (Alt+Right)">
+                            <i class="fas fa-chevron-right fa-lg"></i>
+                        </button>
+                        <button
+                            class={classes!("floating-button", self.grid_visible.then_some("active"))}
+                            onclick={toggle_grid}
+                            disabled={!self.enabled}
+                            title="Show grid"
+                        >
+                            <i class="fas fa-border-all fa-lg"></i>
+                        </button>
+                        <button
+                            class={classes!("floating-button", self.minimap_visible.then_some("active"))}
+                            onclick={toggle_minimap}
+                            disabled={!self.enabled}
+                            title="Show overview: click or drag in it to move the view"
+                        >
+                            <i class="fas fa-map fa-lg"></i>
+                        </button>
+                        <button
+                            class={classes!("floating-button", self.split_view.then_some("active"))}
+                            onclick={toggle_split_view}
+                            disabled={!self.enabled}
+                            title="Split view: the layer list applies to the view that was clicked last"
+                        >
+                            <i class="fas fa-columns fa-lg"></i>
+                        </button>
+                        if self.split_view {
+                            <button
+                                class={classes!("floating-button", self.cameras_linked.then_some("active"))}
+                                onclick={toggle_linked_cameras}
+                                title="Link views: pan and zoom them together"
+                            >
+                                <i class="fas fa-link fa-lg"></i>
+                            </button>
+                        }
+                        <button
+                            class={classes!("floating-button", self.ruler_mode.then_some("active"))}
+                            onclick={toggle_ruler_mode}
+                            disabled={!self.enabled}
+                            title="Measure: click two points to place a ruler"
+                        >
+                            <i class="fas fa-ruler fa-lg"></i>
+                        </button>
+                        if self.ruler_mode {
+                            <button
+                                class={classes!("floating-button", self.ruler_snapping.then_some("active"))}
+                                onclick={toggle_ruler_snapping}
+                                title="Snap rulers to vertices and edges"
+                            >
+                                <i class="fas fa-magnet fa-lg"></i>
+                            </button>
+                        }
+                        if self.ruler_count > 0 {
+                            <button class="floating-button" onclick={clear_rulers} title="Clear rulers">
+                                <i class="fas fa-eraser fa-lg"></i>
+                            </button>
+                        }
+                        if self.top_cells.len() > 1 {
+                            <select class="top-cell-picker" onchange={select_top_cell} disabled={!self.enabled}>
+                                {for self.top_cells.iter().map(|name| {
+                                    let selected = self.top_cell.as_ref() == Some(name);
+                                    html! { <option value={name.clone()} selected={selected}>{name}</option> }
+                                })}
+                                if let Some(name) = self.top_cell.as_ref().filter(|name| !self.top_cells.contains(name)) {
+                                    <option value={name.clone()} selected=true>{name}</option>
+                                }
+                            </select>
+                        }
+                        if let Some(csv) = &self.selection_csv {
+                            <a
+                                class="floating-button"
+                                href={format!("data:text/csv;charset=utf-8,{}", String::from(js_sys::encode_uri_component(csv)))}
+                                download="selection.csv"
+                                title="Export selection"
+                            >
+                                <i class="fas fa-file-csv fa-lg"></i>
+                            </a>
+                        }
                         <span class="status-text">{self.status.clone()}</span>
                     </div>
                 </div>
                 <div class={classes!(if is_dark_theme { "dark-theme" } else { "light-theme" })}>
-                    <Sidebar layers={self.layer_proxies.clone()} update_layer={update_layer} />
+                    <Sidebar
+                        layers={self.layer_proxies.clone()}
+                        update_layer={update_layer}
+                        cells={self.cell_proxies.clone()}
+                        zoom_to_cell={zoom_to_cell}
+                        select_root={select_root}
+                        set_cell_display={set_cell_display}
+                        hierarchy_levels={self.hierarchy_levels}
+                        set_hierarchy_levels={set_hierarchy_levels}
+                        bookmarks={self.bookmarks.clone()}
+                        add_bookmark={add_bookmark}
+                        go_to_bookmark={go_to_bookmark}
+                        remove_bookmark={remove_bookmark}
+                    />
                 </div>
+                <Inspector rows={self.inspector_rows.clone()} />
+                <div class="coordinate-readout" ref={self.readout_ref.clone()}></div>
                 <ToastContainer toasts={self.toast_manager.toasts().to_vec()} on_remove={on_remove_toast} />
             </>
         }
@@ -273,6 +503,29 @@ impl Component for ViewerPage {
         });
         resize_observer.observe(&canvas_clone);
 
+        // Alt+Left and Alt+Right step through the camera history, as they
+        // would through the browser history.
+        let link = ctx.link().clone();
+        let keydown_listener = Closure::wrap(Box::new(move |e: KeyboardEvent| {
+            if !e.alt_key() {
+                return;
+            }
+            let msg = match e.key().as_str() {
+                "ArrowLeft" => ViewerMsg::GoBack,
+                "ArrowRight" => ViewerMsg::GoForward,
+                _ => return,
+            };
+            e.prevent_default();
+            link.send_message(msg);
+        }) as Box<dyn FnMut(KeyboardEvent)>);
+        if let Some(window) = window() {
+            let _ = window.add_event_listener_with_callback(
+                "keydown",
+                keydown_listener.as_ref().unchecked_ref(),
+            );
+        }
+        self.keydown_listener = Some(keydown_listener);
+
         ctx.link().send_message(ViewerMsg::Tick);
         ctx.link().send_message(ViewerMsg::Render);
     }
@@ -301,7 +554,10 @@ impl Component for ViewerPage {
                 false
             }
             ViewerMsg::Tick => {
-                controller.tick();
+                if controller.tick() {
+                    self.update_readout();
+                }
+                self.update_url(context);
                 let closure = Closure::wrap(Box::new(move || {
                     link.send_message(ViewerMsg::Tick);
                 }) as Box<dyn FnMut()>);
@@ -319,7 +575,14 @@ impl Component for ViewerPage {
                 spawn_local(async move {
                     let loader = Loader::new(&content);
                     let mut world = None;
-                    for mut progress in loader {
+                    for progress in loader {
+                        let mut progress = match progress {
+                            Ok(progress) => progress,
+                            Err(error) => {
+                                link.send_message(ViewerMsg::LoadFailed(error));
+                                return;
+                            }
+                        };
                         print_and_yield(&link, &progress.status_message()).await;
                         world = progress.take_world();
                     }
@@ -329,6 +592,7 @@ impl Component for ViewerPage {
                 true
             }
             ViewerMsg::SpawnInstancer(world) => {
+                let instance_limits = self.instance_limits;
                 spawn_local(async move {
                     let mut boxed_world = world;
                     let world = boxed_world.as_mut();
@@ -338,8 +602,31 @@ impl Component for ViewerPage {
                     let message = format!("Found {} roots. Instancing...", roots.len());
                     print_and_yield(&link, &message).await;
 
+                    let Some(root) = root_finder.find_default_root(world) else {
+                        link.send_message(ViewerMsg::NoRootCell(boxed_world));
+                        return;
+                    };
+
+                    let names = roots
+                        .iter()
+                        .map(|root| CellDefinition::name_of(world, *root))
+                        .collect();
+                    let name = CellDefinition::name_of(world, root);
+                    link.send_message(ViewerMsg::SetTopCells(names, name));
+
                     let mut instancer = Instancer::new(world);
-                    instancer.select_root(world, roots[0]);
+                    instancer.set_limits(instance_limits);
+                    instancer.select_root(world, root);
+
+                    let skipped = instancer.skipped_references().len();
+                    if skipped > 0 {
+                        let message = format!(
+                            "Skipped {} cell references that form cycles or exceed the depth or instance limit",
+                            skipped
+                        );
+                        link.send_message(ViewerMsg::ShowToast(message));
+                    }
+
                     link.send_message(ViewerMsg::StashWorld(boxed_world));
                 });
                 true
@@ -356,15 +643,89 @@ impl Component for ViewerPage {
                 };
 
                 controller.set_world(*world);
+                controller.set_instance_limits(self.instance_limits);
                 self.enabled = true;
+                self.ruler_count = controller.rulers().len();
 
                 self.toast_manager.show("Zoom and pan like a map");
 
                 controller.apply_theme(&self.theme);
 
                 self.layer_proxies = controller.create_layer_proxies();
+                self.cell_proxies = controller.create_cell_proxies();
+                self.hierarchy_levels = controller.hierarchy_levels();
+                controller.set_bookmarks(load_bookmarks(&context.props().id));
+                self.bookmarks = controller.bookmarks().names();
+
+                // Restore the view that a shared link points at.
+                let query = context
+                    .link()
+                    .location()
+                    .and_then(|location| location.query::<Vec<(String, String)>>().ok())
+                    .unwrap_or_default();
+                self.restore_view_state(&ViewState::from_query(&query));
+                true
+            }
+            ViewerMsg::NoRootCell(world) => {
+                // The world still replaces the previous one, so that nothing
+                // keeps working on a stale file.
+                controller.set_world(*world);
+                self.top_cells.clear();
+                self.top_cell = None;
+                self.layer_proxies = controller.create_layer_proxies();
+                self.cell_proxies = controller.create_cell_proxies();
+                self.hierarchy_levels = controller.hierarchy_levels();
+                self.fail_load("No top-level cell found");
+                true
+            }
+            ViewerMsg::LoadFailed(error) => {
+                self.fail_load(&error.to_string());
+                true
+            }
+            ViewerMsg::ShowToast(message) => {
+                self.toast_manager.show(&message);
+                true
+            }
+            ViewerMsg::SetTopCells(names, selected) => {
+                self.top_cells = names;
+                self.top_cell = Some(selected);
                 true
             }
+            ViewerMsg::SelectTopCell(name) => {
+                if self.top_cell.as_ref() == Some(&name) {
+                    return false;
+                }
+                let Some(cell_definition) = controller.find_cell(&name) else {
+                    return false;
+                };
+                self.select_root(cell_definition, name);
+                true
+            }
+            ViewerMsg::SelectRoot(cell) => {
+                if self.top_cell.as_deref() == Some(&*cell.name) {
+                    return false;
+                }
+                self.select_root(cell.cell_definition, cell.name.to_string());
+                true
+            }
+            ViewerMsg::SetCellDisplay((cell_definition, display)) => {
+                controller.set_cell_display(cell_definition, display);
+                self.cell_proxies = controller.create_cell_proxies();
+                self.update_selection_csv();
+                self.update_inspector();
+                true
+            }
+            ViewerMsg::SetHierarchyLevels(levels) => {
+                controller.set_hierarchy_levels(levels);
+                self.hierarchy_levels = levels;
+                self.update_selection_csv();
+                self.update_inspector();
+                true
+            }
+            ViewerMsg::ZoomToCell(cell) => {
+                controller.zoom_to_cell(cell.instance);
+                false
+            }
             ViewerMsg::SetStatus(status) => {
                 self.status = status;
                 true
@@ -399,14 +760,92 @@ impl Component for ViewerPage {
                 }
                 true
             }
+            ViewerMsg::ToggleGrid => {
+                self.grid_visible = !self.grid_visible;
+                controller.set_grid_visible(self.grid_visible);
+                true
+            }
+            ViewerMsg::ToggleMinimap => {
+                self.minimap_visible = !self.minimap_visible;
+                controller.set_minimap_visible(self.minimap_visible);
+                true
+            }
+            ViewerMsg::ToggleSplitView => {
+                self.split_view = !self.split_view;
+                controller.set_pane_count(if self.split_view { 2 } else { 1 });
+                self.update_active_pane();
+                true
+            }
+            ViewerMsg::ToggleLinkedCameras => {
+                self.cameras_linked = !self.cameras_linked;
+                controller.set_cameras_linked(self.cameras_linked);
+                true
+            }
+            ViewerMsg::ToggleRulerMode => {
+                self.ruler_mode = !self.ruler_mode;
+                controller.set_ruler_mode(self.ruler_mode);
+                true
+            }
+            ViewerMsg::ToggleRulerSnapping => {
+                self.ruler_snapping = !self.ruler_snapping;
+                controller.set_ruler_snapping(self.ruler_snapping);
+                true
+            }
+            ViewerMsg::ClearRulers => {
+                controller.clear_rulers();
+                self.ruler_count = 0;
+                self.status.clear();
+                true
+            }
+            ViewerMsg::AddBookmark(name) => {
+                if !controller.add_bookmark(&name) {
+                    return false;
+                }
+                save_bookmarks(&context.props().id, controller.bookmarks());
+                self.bookmarks = controller.bookmarks().names();
+                true
+            }
+            ViewerMsg::RemoveBookmark(name) => {
+                if !controller.remove_bookmark(&name) {
+                    return false;
+                }
+                save_bookmarks(&context.props().id, controller.bookmarks());
+                self.bookmarks = controller.bookmarks().names();
+                true
+            }
+            ViewerMsg::GoToBookmark(name) => {
+                if !controller.go_to_bookmark(&name) {
+                    return false;
+                }
+                // The bookmark may have switched to another root cell.
+                let top_cell = controller.root_cell_name();
+                if top_cell != self.top_cell {
+                    self.top_cell = top_cell;
+                    self.inspected_shape = None;
+                    self.inspector_rows.clear();
+                    self.cell_proxies = controller.create_cell_proxies();
+                }
+                self.layer_proxies = controller.create_layer_proxies();
+                self.update_selection_csv();
+                self.update_inspector();
+                true
+            }
+            ViewerMsg::GoBack => {
+                controller.go_back();
+                false
+            }
+            ViewerMsg::GoForward => {
+                controller.go_forward();
+                false
+            }
             ViewerMsg::PointerDown(pointer) => {
                 let x = pointer.client_x() as u32;
                 let y = pointer.client_y() as u32;
                 let scale = window().unwrap().device_pixel_ratio();
                 let x = (x as f64) * scale;
                 let y = (y as f64) * scale;
-                controller.handle_mouse_press(x as u32, y as u32);
-                false
+                controller.handle_mouse_press(x as u32, y as u32, pointer.shift_key());
+                self.update_active_pane()
             }
             ViewerMsg::PointerMove(pointer) => {
                 let x = pointer.client_x() as u32;
@@ -415,15 +854,24 @@ impl Component for ViewerPage {
                 let x = (x as f64) * scale;
                 let y = (y as f64) * scale;
                 controller.handle_mouse_move(x as u32, y as u32);
-                false
+                self.update_readout();
+                self.update_inspector()
             }
             ViewerMsg::PointerUp => {
                 controller.handle_mouse_release();
-                false
+                let rulers = controller.rulers();
+                let mut changed = rulers.len() != self.ruler_count;
+                if let Some(ruler) = rulers.last().filter(|_| changed) {
+                    self.status = ruler.label(&controller.units());
+                }
+                self.ruler_count = rulers.len();
+                changed |= self.update_selection_csv();
+                self.update_inspector() || changed
             }
             ViewerMsg::PointerLeave => {
                 controller.handle_mouse_leave();
-                false
+                self.update_readout();
+                self.update_inspector()
             }
             ViewerMsg::Wheel(wheel) => {
                 let x = wheel.offset_x() as u32;
@@ -432,7 +880,7 @@ impl Component for ViewerPage {
                 let x = (x as f64) * scale;
                 let y = (y as f64) * scale;
                 controller.handle_mouse_wheel(x as u32, y as u32, -wheel.delta_y());
-                false
+                self.update_active_pane()
             }
             ViewerMsg::SingleTouchStart(touch) => {
                 let x = touch.client_x() as u32;
@@ -440,14 +888,14 @@ impl Component for ViewerPage {
                 let scale = window().unwrap().device_pixel_ratio();
                 let x = (x as f64) * scale;
                 let y = (y as f64) * scale;
-                controller.handle_mouse_press(x as u32, y as u32);
-                false
+                controller.handle_mouse_press(x as u32, y as u32, false);
+                self.update_active_pane()
             }
             ViewerMsg::DoubleTouchStart(touch1, touch2) => {
                 let distance = compute_pinch_distance(&touch1, &touch2);
                 let center = compute_pinch_center(&touch1, &touch2);
                 controller.handle_pinch_start(distance, center);
-                false
+                self.update_active_pane()
             }
             ViewerMsg::SingleTouchMove(touch) => {
                 let x = touch.client_x() as u32;
@@ -472,6 +920,180 @@ impl Component for ViewerPage {
     }
 }
 
+impl ViewerPage {
+    /// Leaves the page with its controls disabled and tells the user why the
+    /// file could not be loaded.
+    fn fail_load(&mut self, message: &str) {
+        log::error!("{}", message);
+        self.enabled = false;
+        self.status = "Unable to load file".to_string();
+        self.toast_manager.show(message);
+    }
+
+    /// Replaces the instance tree with one rooted at the given cell
+    /// definition, and refreshes everything that refers to the old tree.
+    fn select_root(&mut self, cell_definition: Entity, name: String) {
+        let Some(controller) = &mut self.controller else {
+            return;
+        };
+        let skipped = controller.select_root(cell_definition);
+        if !skipped.is_empty() {
+            let message = format!(
+                "Skipped {} cell references that form cycles or exceed the depth or instance limit",
+                skipped.len()
+            );
+            self.toast_manager.show(&message);
+        }
+        self.top_cell = Some(name);
+        self.selection_csv = None;
+        self.inspected_shape = None;
+        self.inspector_rows.clear();
+        self.layer_proxies = controller.create_layer_proxies();
+        self.cell_proxies = controller.create_cell_proxies();
+    }
+
+    /// Selects the root cell of a ViewState, then restores the rest of it.
+    fn restore_view_state(&mut self, state: &ViewState) {
+        let Some(controller) = &mut self.controller else {
+            return;
+        };
+        if let Some(name) = &state.cell {
+            if self.top_cell.as_ref() != Some(name) {
+                if let Some(cell_definition) = controller.find_cell(name) {
+                    self.select_root(cell_definition, name.clone());
+                }
+            }
+        }
+        let Some(controller) = &mut self.controller else {
+            return;
+        };
+        controller.apply_view_state(state);
+        self.layer_proxies = controller.create_layer_proxies();
+        self.update_selection_csv();
+        self.update_inspector();
+    }
+
+    /// Writes the current view into the URL so that it can be shared. This
+    /// is throttled, since it runs on every animation frame.
+    fn update_url(&mut self, ctx: &Context<Self>) {
+        const URL_UPDATE_INTERVAL_MS: f64 = 500.0;
+
+        let now = js_sys::Date::now();
+        if !self.enabled || now - self.url_updated_at < URL_UPDATE_INTERVAL_MS {
+            return;
+        }
+        self.url_updated_at = now;
+
+        let Some(controller) = &mut self.controller else {
+            return;
+        };
+        // Parameters that are not part of the view, such as the instance
+        // limits, are kept so that reloads and shared links still use them.
+        let current = ctx
+            .link()
+            .location()
+            .and_then(|location| location.query::<Vec<(String, String)>>().ok())
+            .unwrap_or_default();
+        let query = controller.view_state().update_query(&current);
+        if query == self.url_query {
+            return;
+        }
+        if let Some(navigator) = ctx.link().navigator() {
+            let route = Route::Viewer {
+                id: ctx.props().id.clone(),
+            };
+            if let Err(error) = navigator.replace_with_query(&route, &query) {
+                log::warn!("Unable to update the URL: {}", error);
+            }
+        }
+        self.url_query = query;
+    }
+
+    /// Refreshes the layer list if another pane of the split view became
+    /// active. Returns true if the page needs to be redrawn.
+    fn update_active_pane(&mut self) -> bool {
+        let Some(controller) = &mut self.controller else {
+            return false;
+        };
+        let active_pane = controller.active_pane();
+        if active_pane == self.active_pane {
+            return false;
+        }
+        self.active_pane = active_pane;
+        self.layer_proxies = controller.create_layer_proxies();
+        true
+    }
+
+    fn update_readout(&self) {
+        let Some(controller) = &self.controller else {
+            return;
+        };
+        if let Some(readout) = self.readout_ref.cast::<Element>() {
+            readout.set_text_content(controller.cursor_readout().as_deref());
+        }
+    }
+
+    /// Exports the selected shapes for the download link. Returns true if the
+    /// export changed.
+    fn update_selection_csv(&mut self) -> bool {
+        let Some(controller) = &mut self.controller else {
+            return false;
+        };
+        let selection_csv = if controller.selected_shapes().is_empty() {
+            None
+        } else {
+            Some(controller.export_selection())
+        };
+        let changed = selection_csv != self.selection_csv;
+        self.selection_csv = selection_csv;
+        changed
+    }
+
+    /// Shows the hovered shape in the inspector, or the selected shape if
+    /// exactly one is selected. Returns true if the inspector changed.
+    fn update_inspector(&mut self) -> bool {
+        let Some(controller) = &mut self.controller else {
+            return false;
+        };
+        let shape = controller.hovered_shape().or_else(|| {
+            let selected = controller.selected_shapes();
+            if selected.len() == 1 {
+                Some(selected[0])
+            } else {
+                None
+            }
+        });
+        if shape == self.inspected_shape {
+            return false;
+        }
+        self.inspected_shape = shape;
+        self.inspector_rows = shape
+            .and_then(|shape| controller.inspect(shape))
+            .map(|report| report.rows(&controller.units()))
+            .unwrap_or_default();
+        true
+    }
+}
+
+/// Bookmarks are kept in local storage, under a key for each file.
+fn load_bookmarks(id: &str) -> Bookmarks {
+    window()
+        .and_then(|window| window.local_storage().ok().flatten())
+        .and_then(|storage| {
+            storage
+                .get_item(&format!("bookmarks/{}", id))
+                .ok()
+                .flatten()
+        })
+        .map_or_else(Bookmarks::default, |text| Bookmarks::from_text(&text))
+}
+
+fn save_bookmarks(id: &str, bookmarks: &Bookmarks) {
+    if let Some(storage) = window().and_then(|window| window.local_storage().ok().flatten()) {
+        let _ = storage.set_item(&format!("bookmarks/{}", id), &bookmarks.to_text());
+    }
+}
+
 // Helper function to fetch GDS file
 async fn fetch_gds_file(filename: &str) -> Result<Vec<u8>, wasm_bindgen::JsValue> {
     let opts = RequestInit::new();
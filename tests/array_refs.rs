@@ -0,0 +1,167 @@
+//! Verifies that GDS array references expand into one instance per grid
+//! position, with the STRANS applied to each instance.
+
+mod common;
+
+use gds21::GdsArrayRef;
+use gds21::GdsBoundary;
+use gds21::GdsElement;
+use gds21::GdsLibrary;
+use gds21::GdsPoint;
+use gds21::GdsStrans;
+use gds21::GdsStruct;
+use geo::BoundingRect;
+use layout_viewer::core::components::CellDefinition;
+use layout_viewer::core::components::CellInstance;
+use layout_viewer::core::components::ShapeInstance;
+use layout_viewer::core::instancer::Instancer;
+use layout_viewer::core::instancer::SkippedReference;
+use layout_viewer::core::root_finder::RootFinder;
+
+/// Places a 10 x 5 rectangle in an array and returns the world-space
+/// bounding box of every instance as (min_x, min_y, max_x, max_y), sorted.
+fn array_bounds(
+    cols: i16,
+    rows: i16,
+    xy: [(i32, i32); 3],
+    strans: Option<GdsStrans>,
+) -> Vec<(f64, f64, f64, f64)> {
+    let mut library = GdsLibrary::new("arrays");
+
+    let mut unit = GdsStruct::new("unit");
+    unit.elems.push(GdsElement::GdsBoundary(GdsBoundary {
+        layer: 1,
+        datatype: 0,
+        xy: GdsPoint::vec(&[(0, 0), (10, 0), (10, 5), (0, 5), (0, 0)]),
+        ..Default::default()
+    }));
+    library.structs.push(unit);
+
+    let mut top = GdsStruct::new("top");
+    top.elems.push(GdsElement::GdsArrayRef(GdsArrayRef {
+        name: "unit".to_string(),
+        xy: xy.map(|(x, y)| GdsPoint::new(x, y)),
+        cols,
+        rows,
+        strans,
+        ..Default::default()
+    }));
+    library.structs.push(top);
+
+    let mut world = common::load_and_instantiate(&common::to_bytes(&library));
+    let mut bounds: Vec<_> = world
+        .query::<&ShapeInstance>()
+        .iter(&world)
+        .map(|shape| {
+            let rect = shape.world_polygon(&world).bounding_rect().unwrap();
+            (rect.min().x, rect.min().y, rect.max().x, rect.max().y)
+        })
+        .collect();
+    bounds.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    bounds
+}
+
+#[test]
+fn rows_and_columns_are_spaced_by_the_pitch() {
+    // 3 columns with a pitch of 100 along x, 2 rows with a pitch of 50
+    // along y.
+    let bounds = array_bounds(3, 2, [(0, 0), (300, 0), (0, 100)], None);
+    let mut expected = Vec::new();
+    for col in 0..3 {
+        for row in 0..2 {
+            let (x, y) = (col as f64 * 100.0, row as f64 * 50.0);
+            expected.push((x, y, x + 10.0, y + 5.0));
+        }
+    }
+    assert_eq!(bounds, expected);
+}
+
+#[test]
+fn rotated_arrays_rotate_each_instance() {
+    // The grid itself is given in parent coordinates, here with columns
+    // along y. Each instance is rotated by 90 degrees in place.
+    let strans = GdsStrans {
+        angle: Some(90.0),
+        ..Default::default()
+    };
+    let bounds = array_bounds(2, 1, [(0, 0), (0, 200), (-100, 0)], Some(strans));
+    assert_eq!(bounds.len(), 2);
+    for (actual, expected) in bounds
+        .iter()
+        .zip([(-5.0, 0.0, 0.0, 10.0), (-5.0, 100.0, 0.0, 110.0)])
+    {
+        assert!((actual.0 - expected.0).abs() < 1e-9, "{:?}", bounds);
+        assert!((actual.1 - expected.1).abs() < 1e-9, "{:?}", bounds);
+        assert!((actual.2 - expected.2).abs() < 1e-9, "{:?}", bounds);
+        assert!((actual.3 - expected.3).abs() < 1e-9, "{:?}", bounds);
+    }
+}
+
+#[test]
+fn mirrored_arrays_reflect_each_instance() {
+    let strans = GdsStrans {
+        reflected: true,
+        ..Default::default()
+    };
+    let bounds = array_bounds(1, 3, [(20, 30), (40, 30), (20, 90)], Some(strans));
+    assert_eq!(
+        bounds,
+        vec![
+            (20.0, 25.0, 30.0, 30.0),
+            (20.0, 45.0, 30.0, 50.0),
+            (20.0, 65.0, 30.0, 70.0),
+        ]
+    );
+}
+
+#[test]
+fn large_arrays_are_expanded_within_the_instance_limit() {
+    let mut library = GdsLibrary::new("arrays");
+    library.structs.push(GdsStruct::new("unit"));
+    let mut top = GdsStruct::new("top");
+    top.elems.push(GdsElement::GdsArrayRef(GdsArrayRef {
+        name: "unit".to_string(),
+        xy: [
+            GdsPoint::new(0, 0),
+            GdsPoint::new(i16::MAX as i32 * 10, 0),
+            GdsPoint::new(0, i16::MAX as i32 * 20),
+        ],
+        cols: i16::MAX,
+        rows: i16::MAX,
+        ..Default::default()
+    }));
+    library.structs.push(top);
+
+    // The array is loaded as one reference rather than a billion.
+    let mut world = common::load(&common::to_bytes(&library));
+    let root = RootFinder::new(&mut world)
+        .find_cell(&world, "top")
+        .unwrap();
+    let cell_refs = &world.get::<CellDefinition>(root).unwrap().cell_refs;
+    assert_eq!(cell_refs.len(), 1);
+    assert_eq!(cell_refs[0].element_count(), 32767 * 32767);
+
+    let mut instancer = Instancer::new(&mut world);
+    instancer.set_max_instances(32769);
+    instancer.select_root(&mut world, root);
+    assert!(matches!(
+        instancer.skipped_references(),
+        [SkippedReference::InstanceLimit { .. }]
+    ));
+
+    // Elements are placed along the first row, then the second.
+    let mut offsets: Vec<_> = world
+        .query::<&CellInstance>()
+        .iter(&world)
+        .filter(|instance| instance.parent.is_some())
+        .map(|instance| {
+            let transform = instance.world_transform;
+            (transform.yoff(), transform.xoff())
+        })
+        .collect();
+    offsets.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    assert_eq!(offsets.len(), 32768);
+    assert_eq!(offsets[1], (0.0, 10.0));
+    assert_eq!(offsets[32766], (0.0, 327660.0));
+    assert_eq!(offsets[32767], (20.0, 0.0));
+}
@@ -0,0 +1,115 @@
+//! Verifies back and forward navigation through the camera history, and
+//! that bookmarks survive a round trip through their text format.
+
+use layout_viewer::core::bookmarks::Bookmarks;
+use layout_viewer::core::camera_history::CameraHistory;
+use layout_viewer::core::camera_history::CameraView;
+use layout_viewer::core::camera_history::MAX_HISTORY;
+use layout_viewer::core::components::LayerKey;
+use layout_viewer::core::view_state::ViewState;
+use layout_viewer::graphics::vectors::Point2d;
+
+fn view(x: f64, width: f64) -> CameraView {
+    CameraView {
+        center: Point2d::new(x, 0.0),
+        width,
+    }
+}
+
+#[test]
+fn history_steps_back_and_forward() {
+    let mut history = CameraHistory::default();
+    assert!(!history.can_go_back());
+    assert_eq!(history.back(view(0.0, 10.0)), None);
+
+    // Pan from 0 to 1, then zoom at 1. Repeated views are recorded once.
+    history.push(view(0.0, 10.0));
+    history.push(view(1.0, 10.0));
+    history.push(view(1.0, 10.0));
+    let current = view(1.0, 5.0);
+
+    assert_eq!(history.back(current), Some(view(1.0, 10.0)));
+    assert_eq!(history.back(view(1.0, 10.0)), Some(view(0.0, 10.0)));
+    assert!(!history.can_go_back());
+    assert_eq!(history.forward(view(0.0, 10.0)), Some(view(1.0, 10.0)));
+    assert_eq!(history.forward(view(1.0, 10.0)), Some(current));
+    assert!(!history.can_go_forward());
+
+    // Navigating after going back drops the forward views.
+    history.back(current);
+    assert!(history.can_go_forward());
+    history.push(view(1.0, 10.0));
+    assert!(!history.can_go_forward());
+
+    // Both stacks keep only the most recent views.
+    history.clear();
+    for x in 0..MAX_HISTORY + 50 {
+        history.push(view(x as f64, 10.0));
+    }
+    let mut current = view(-1.0, 10.0);
+    let mut steps_back = 0;
+    while let Some(previous) = history.back(current) {
+        current = previous;
+        steps_back += 1;
+    }
+    assert_eq!(steps_back, MAX_HISTORY);
+    assert_eq!(current, view(50.0, 10.0));
+    let mut steps_forward = 0;
+    while let Some(next) = history.forward(current) {
+        current = next;
+        steps_forward += 1;
+    }
+    assert_eq!(steps_forward, MAX_HISTORY);
+    assert_eq!(current, view(-1.0, 10.0));
+}
+
+#[test]
+fn bookmarks_are_replaced_by_name() {
+    let mut bookmarks = Bookmarks::default();
+    let alu = ViewState {
+        center: Some((1.0, 2.0)),
+        ..Default::default()
+    };
+    let pads = ViewState {
+        width: Some(500.0),
+        ..Default::default()
+    };
+    assert!(bookmarks.set("  the \t ALU ", alu.clone()));
+    assert!(bookmarks.set("pad ring", pads.clone()));
+    assert!(!bookmarks.set(" \n", pads.clone()));
+    assert_eq!(bookmarks.names(), vec!["the ALU", "pad ring"]);
+    assert_eq!(bookmarks.get("the ALU").unwrap().view, alu);
+
+    assert!(bookmarks.set("the ALU", pads.clone()));
+    assert_eq!(bookmarks.names(), vec!["the ALU", "pad ring"]);
+    assert_eq!(bookmarks.get("the ALU").unwrap().view, pads);
+
+    assert!(bookmarks.remove("the ALU"));
+    assert!(!bookmarks.remove("the ALU"));
+    assert_eq!(bookmarks.names(), vec!["pad ring"]);
+}
+
+#[test]
+fn bookmarks_round_trip_through_text() {
+    let mut bookmarks = Bookmarks::default();
+    bookmarks.set(
+        "register file",
+        ViewState {
+            center: Some((-12.5, 40.0)),
+            width: Some(8.0),
+            cell: Some("A&B=100%".to_string()),
+            hidden_layers: vec![LayerKey::new(1, 0)],
+            layer_colors: vec![(LayerKey::new(2, 0), "ff0000".to_string())],
+            selected: None,
+        },
+    );
+    bookmarks.set("everything", ViewState::default());
+
+    let text = bookmarks.to_text();
+    assert_eq!(text.lines().count(), 2);
+    assert_eq!(Bookmarks::from_text(&text), bookmarks);
+
+    // Comments and lines without a tab are skipped.
+    let edited = format!("# saved views\nnot a bookmark\n\n{}", text);
+    assert_eq!(Bookmarks::from_text(&edited), bookmarks);
+}
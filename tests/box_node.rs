@@ -0,0 +1,97 @@
+//! Verifies how GDS BOX and NODE elements are loaded.
+
+mod common;
+
+use bevy_ecs::world::World;
+use gds21::GdsBox;
+use gds21::GdsElement;
+use gds21::GdsLibrary;
+use gds21::GdsNode;
+use gds21::GdsPoint;
+use gds21::GdsStruct;
+use layout_viewer::core::components::Layer;
+use layout_viewer::core::components::LayerKey;
+use layout_viewer::core::components::ShapeDefinition;
+use layout_viewer::core::components::ShapeType;
+use layout_viewer::graphics::bounds::BoundingBox;
+
+/// Layer 1 holds a box, layer 2 a large square node, and layer 3 a short
+/// two point node.
+fn load_box_and_nodes() -> World {
+    let mut library = GdsLibrary::new("box_node");
+    let mut top = GdsStruct::new("top");
+    top.elems.push(GdsElement::GdsBox(GdsBox {
+        layer: 1,
+        boxtype: 0,
+        xy: [(0, 0), (100, 0), (100, 50), (0, 50), (0, 0)].map(|(x, y)| GdsPoint::new(x, y)),
+        ..Default::default()
+    }));
+    top.elems.push(GdsElement::GdsNode(GdsNode {
+        layer: 2,
+        nodetype: 0,
+        xy: GdsPoint::vec(&[(0, 0), (1000, 0), (1000, 1000), (0, 1000)]),
+        ..Default::default()
+    }));
+    top.elems.push(GdsElement::GdsNode(GdsNode {
+        layer: 3,
+        nodetype: 0,
+        xy: GdsPoint::vec(&[(0, 0), (10, 0)]),
+        ..Default::default()
+    }));
+    library.structs.push(top);
+    common::load(&common::to_bytes(&library))
+}
+
+fn shape_on_layer(world: &mut World, layer: i16) -> &ShapeDefinition {
+    let mut query = world.query::<&ShapeDefinition>();
+    query
+        .iter(world)
+        .find(|shape| world.get::<Layer>(shape.layer).unwrap().key == LayerKey::new(layer, 0))
+        .unwrap()
+}
+
+fn assert_bounds(bounds: BoundingBox, expected: (f64, f64, f64, f64)) {
+    let actual = (bounds.min_x, bounds.min_y, bounds.max_x, bounds.max_y);
+    let close = (actual.0 - expected.0).abs() < 1e-9
+        && (actual.1 - expected.1).abs() < 1e-9
+        && (actual.2 - expected.2).abs() < 1e-9
+        && (actual.3 - expected.3).abs() < 1e-9;
+    assert!(close, "expected {:?}, got {:?}", expected, actual);
+}
+
+#[test]
+fn boxes_are_filled() {
+    let mut world = load_box_and_nodes();
+    let shape = shape_on_layer(&mut world, 1);
+    assert!(matches!(&shape.shape_type, ShapeType::Box(points) if points.len() == 5));
+    assert_bounds(shape.local_triangles.bounds(), (0.0, 0.0, 100.0, 50.0));
+    assert!((shape.local_triangles.area() - 5000.0).abs() < 1e-9);
+}
+
+#[test]
+fn closed_nodes_are_outlined_at_a_fraction_of_their_extent() {
+    let mut world = load_box_and_nodes();
+    let shape = shape_on_layer(&mut world, 2);
+    assert!(matches!(&shape.shape_type, ShapeType::Node(points) if points.len() == 4));
+
+    // A 2% band of 20 dbu centered on the node points, with the interior
+    // left open.
+    assert_bounds(
+        shape.local_triangles.bounds(),
+        (-10.0, -10.0, 1010.0, 1010.0),
+    );
+    let band_area = 1020.0 * 1020.0 - 980.0 * 980.0;
+    assert!((shape.local_triangles.area() - band_area).abs() < 1e-6);
+}
+
+#[test]
+fn short_nodes_are_outlined_at_least_one_unit_wide() {
+    let mut world = load_box_and_nodes();
+    let shape = shape_on_layer(&mut world, 3);
+    assert!(matches!(&shape.shape_type, ShapeType::Node(points) if points.len() == 2));
+
+    // 2% of 10 dbu is raised to 1 dbu, traced as an open segment with
+    // square caps.
+    assert_bounds(shape.local_triangles.bounds(), (-0.5, -0.5, 10.5, 0.5));
+    assert!((shape.local_triangles.area() - 11.0).abs() < 1e-9);
+}
@@ -0,0 +1,274 @@
+//! Verifies that hidden and abstract cells are left out of drawing, picking
+//! and SVG export, that restoring a cell brings its contents back, and that
+//! the hierarchy level range limits drawing and outlines deeper instances.
+
+mod common;
+
+use bevy_ecs::entity::Entity;
+use bevy_ecs::world::World;
+use common::load_cells;
+use common::place;
+use common::rect;
+use common::total_mesh_instances;
+use layout_viewer::cli::generate_svg::generate_svg;
+use layout_viewer::core::cell_display::abstract_outlines;
+use layout_viewer::core::cell_display::deepest_level;
+use layout_viewer::core::cell_display::is_concealed;
+use layout_viewer::core::cell_display::set_cell_display;
+use layout_viewer::core::cell_display::set_hierarchy_levels;
+use layout_viewer::core::cell_display::HierarchyLevels;
+use layout_viewer::core::components::CellDisplay;
+use layout_viewer::core::components::CellInstance;
+use layout_viewer::core::root_finder::RootFinder;
+use layout_viewer::core::rtree::build_rtree;
+use layout_viewer::core::selection::shapes_in_box;
+use layout_viewer::graphics::bounds::BoundingBox;
+
+/// "block" holds two instances of "leaf" and a 40 x 40 square of its own.
+/// "top" places "block" at the origin and another "leaf" at (100, 0).
+///
+/// Each cell has one tile, drawn by a detailed mesh and a density quad, so
+/// every drawn cell instance with shapes adds two mesh instances.
+fn load_block_and_leaves() -> World {
+    load_cells(vec![
+        ("leaf", vec![rect(1, 0, 0, 10, 10)]),
+        (
+            "block",
+            vec![
+                place("leaf", 0, 0),
+                place("leaf", 20, 0),
+                rect(2, 0, 0, 40, 40),
+            ],
+        ),
+        ("top", vec![place("block", 0, 0), place("leaf", 100, 0)]),
+    ])
+}
+
+fn find_cell(world: &mut World, name: &str) -> Entity {
+    RootFinder::new(world).find_cell(world, name).unwrap()
+}
+
+/// Returns the instances of a cell at the given depth below the root.
+fn instances_of(world: &mut World, name: &str, depth: usize) -> Vec<Entity> {
+    let cell_definition = find_cell(world, name);
+    world
+        .query::<(Entity, &CellInstance)>()
+        .iter(world)
+        .filter(|(_, instance)| instance.cell_definition == cell_definition)
+        .filter(|(_, instance)| instance.depth == depth)
+        .map(|(entity, _)| entity)
+        .collect()
+}
+
+/// Returns the name and bounds of each outline, in depth-first order.
+fn outlines(world: &mut World) -> Vec<(String, BoundingBox)> {
+    abstract_outlines(world)
+        .into_iter()
+        .map(|outline| (outline.name, outline.world_bounds))
+        .collect()
+}
+
+fn bounds(min_x: f64, min_y: f64, max_x: f64, max_y: f64) -> BoundingBox {
+    BoundingBox {
+        min_x,
+        min_y,
+        max_x,
+        max_y,
+    }
+}
+
+fn pickable_shapes(world: &mut World) -> usize {
+    let rtree = build_rtree(world);
+    let everything = BoundingBox {
+        min_x: -1.0,
+        min_y: -1.0,
+        max_x: 200.0,
+        max_y: 200.0,
+    };
+    shapes_in_box(world, &rtree, &everything).len()
+}
+
+#[test]
+fn hidden_cells_are_not_drawn_picked_or_exported() {
+    let mut world = load_block_and_leaves();
+    assert_eq!(total_mesh_instances(&mut world), 8);
+    assert_eq!(pickable_shapes(&mut world), 4);
+    assert_eq!(generate_svg(&mut world, false).matches("<path").count(), 4);
+
+    let block = find_cell(&mut world, "block");
+    set_cell_display(&mut world, block, CellDisplay::Hidden);
+    assert_eq!(total_mesh_instances(&mut world), 2);
+    assert_eq!(pickable_shapes(&mut world), 1);
+    let svg = generate_svg(&mut world, false);
+    assert_eq!(svg.matches("<path").count(), 1);
+    assert!(!svg.contains("<rect"));
+    assert!(abstract_outlines(&mut world).is_empty());
+
+    set_cell_display(&mut world, block, CellDisplay::Contents);
+    assert_eq!(total_mesh_instances(&mut world), 8);
+    assert_eq!(pickable_shapes(&mut world), 4);
+}
+
+#[test]
+fn cells_stay_hidden_until_they_and_their_parents_are_shown() {
+    let mut world = load_block_and_leaves();
+    let block = find_cell(&mut world, "block");
+    let leaf = find_cell(&mut world, "leaf");
+    let nested_leaves = instances_of(&mut world, "leaf", 2);
+    let direct_leaf = instances_of(&mut world, "leaf", 1)[0];
+    assert_eq!(nested_leaves.len(), 2);
+
+    // A hidden parent conceals cells that are themselves shown.
+    set_cell_display(&mut world, block, CellDisplay::Hidden);
+    assert!(nested_leaves
+        .iter()
+        .all(|instance| is_concealed(&world, *instance)));
+    assert!(!is_concealed(&world, direct_leaf));
+
+    // Showing the parent again leaves a separately hidden cell hidden.
+    set_cell_display(&mut world, leaf, CellDisplay::Hidden);
+    set_cell_display(&mut world, block, CellDisplay::Contents);
+    assert!(nested_leaves
+        .iter()
+        .all(|instance| is_concealed(&world, *instance)));
+    assert!(is_concealed(&world, direct_leaf));
+    assert_eq!(total_mesh_instances(&mut world), 2);
+    assert_eq!(pickable_shapes(&mut world), 1);
+}
+
+#[test]
+fn abstract_cells_are_outlined_once() {
+    let mut world = load_block_and_leaves();
+    let block = find_cell(&mut world, "block");
+    let leaf = find_cell(&mut world, "leaf");
+
+    // Abstract cells inside an abstract cell are covered by its outline.
+    set_cell_display(&mut world, block, CellDisplay::Abstract);
+    set_cell_display(&mut world, leaf, CellDisplay::Abstract);
+    let outlines = abstract_outlines(&mut world);
+    let outlines: Vec<(&str, BoundingBox)> = outlines
+        .iter()
+        .map(|outline| (outline.name.as_str(), outline.world_bounds))
+        .collect();
+    assert_eq!(
+        outlines,
+        vec![
+            (
+                "block",
+                BoundingBox {
+                    min_x: 0.0,
+                    min_y: 0.0,
+                    max_x: 40.0,
+                    max_y: 40.0,
+                }
+            ),
+            (
+                "leaf",
+                BoundingBox {
+                    min_x: 100.0,
+                    min_y: 0.0,
+                    max_x: 110.0,
+                    max_y: 10.0,
+                }
+            ),
+        ]
+    );
+    assert_eq!(total_mesh_instances(&mut world), 0);
+    assert_eq!(pickable_shapes(&mut world), 0);
+
+    let svg = generate_svg(&mut world, false);
+    assert_eq!(svg.matches("<rect").count(), 2);
+    assert!(svg.contains("block"));
+
+    // Hiding the root leaves nothing to outline.
+    let top = find_cell(&mut world, "top");
+    set_cell_display(&mut world, top, CellDisplay::Hidden);
+    assert!(abstract_outlines(&mut world).is_empty());
+}
+
+#[test]
+fn instances_record_their_depth() {
+    let mut world = load_block_and_leaves();
+    assert_eq!(instances_of(&mut world, "top", 0).len(), 1);
+    assert_eq!(instances_of(&mut world, "block", 1).len(), 1);
+    assert_eq!(instances_of(&mut world, "leaf", 1).len(), 1);
+    assert_eq!(instances_of(&mut world, "leaf", 2).len(), 2);
+    assert_eq!(deepest_level(&mut world), 2);
+
+    let levels = HierarchyLevels::new(1, 2);
+    assert!(!levels.contains(0));
+    assert!(levels.contains(1));
+    assert!(levels.contains(2));
+    assert!(!levels.contains(3));
+}
+
+#[test]
+fn only_the_first_level_below_the_range_is_outlined() {
+    let mut world = load_block_and_leaves();
+
+    set_hierarchy_levels(&mut world, HierarchyLevels::new(0, 0));
+    assert_eq!(total_mesh_instances(&mut world), 0);
+    assert_eq!(pickable_shapes(&mut world), 0);
+    assert_eq!(
+        outlines(&mut world),
+        vec![
+            ("block".to_string(), bounds(0.0, 0.0, 40.0, 40.0)),
+            ("leaf".to_string(), bounds(100.0, 0.0, 110.0, 10.0)),
+        ]
+    );
+    assert_eq!(generate_svg(&mut world, false).matches("<rect").count(), 2);
+
+    set_hierarchy_levels(&mut world, HierarchyLevels::new(0, 1));
+    assert_eq!(total_mesh_instances(&mut world), 4);
+    assert_eq!(
+        outlines(&mut world),
+        vec![
+            ("leaf".to_string(), bounds(0.0, 0.0, 10.0, 10.0)),
+            ("leaf".to_string(), bounds(20.0, 0.0, 30.0, 10.0)),
+        ]
+    );
+
+    set_hierarchy_levels(&mut world, HierarchyLevels::default());
+    assert_eq!(total_mesh_instances(&mut world), 8);
+    assert!(outlines(&mut world).is_empty());
+}
+
+#[test]
+fn instances_above_the_range_are_not_drawn() {
+    let mut world = load_block_and_leaves();
+    set_hierarchy_levels(&mut world, HierarchyLevels::new(2, usize::MAX));
+
+    // Only the leaves inside the block are drawn.
+    let block = instances_of(&mut world, "block", 1)[0];
+    let direct_leaf = instances_of(&mut world, "leaf", 1)[0];
+    assert_eq!(total_mesh_instances(&mut world), 4);
+    assert_eq!(pickable_shapes(&mut world), 2);
+    assert!(is_concealed(&world, block));
+    assert!(is_concealed(&world, direct_leaf));
+    assert!(outlines(&mut world).is_empty());
+
+    // A range below the deepest level draws nothing and outlines nothing.
+    set_hierarchy_levels(&mut world, HierarchyLevels::new(5, 9));
+    assert_eq!(total_mesh_instances(&mut world), 0);
+    assert!(outlines(&mut world).is_empty());
+}
+
+#[test]
+fn hidden_cells_below_the_range_are_not_outlined() {
+    let mut world = load_block_and_leaves();
+    let block = find_cell(&mut world, "block");
+    set_cell_display(&mut world, block, CellDisplay::Hidden);
+    set_hierarchy_levels(&mut world, HierarchyLevels::new(0, 0));
+    assert_eq!(
+        outlines(&mut world),
+        vec![("leaf".to_string(), bounds(100.0, 0.0, 110.0, 10.0))]
+    );
+
+    // Abstract cells above the range keep their own outline.
+    set_cell_display(&mut world, block, CellDisplay::Abstract);
+    set_hierarchy_levels(&mut world, HierarchyLevels::new(0, 1));
+    assert_eq!(
+        outlines(&mut world),
+        vec![("block".to_string(), bounds(0.0, 0.0, 40.0, 40.0))]
+    );
+}
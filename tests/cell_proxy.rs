@@ -0,0 +1,124 @@
+//! Verifies the cell instance list that backs the hierarchy browser.
+
+mod common;
+
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use bevy_ecs::entity::Entity;
+use bevy_ecs::world::World;
+use common::load_cells;
+use common::place;
+use common::rect;
+use layout_viewer::core::cell_display::set_cell_display;
+use layout_viewer::core::cell_proxy::CellProxy;
+use layout_viewer::core::cell_proxy::CellProxyList;
+use layout_viewer::core::components::CellDisplay;
+use layout_viewer::core::instancer::Instancer;
+use layout_viewer::core::root_finder::RootFinder;
+
+/// "top" places "a", then "b", then "a" again. Each "a" holds a "leaf".
+fn load_shared_cells() -> World {
+    load_cells(vec![
+        ("leaf", vec![rect(1, 0, 0, 10, 10)]),
+        ("a", vec![place("leaf", 0, 0)]),
+        ("b", vec![rect(1, 0, 0, 50, 50)]),
+        (
+            "top",
+            vec![place("a", 0, 0), place("b", 100, 0), place("a", 200, 0)],
+        ),
+    ])
+}
+
+fn find_cell(world: &mut World, name: &str) -> Entity {
+    RootFinder::new(world).find_cell(world, name).unwrap()
+}
+
+/// Returns (name, depth, descendant count, instance count) of each proxy.
+fn summary(cells: &[CellProxy]) -> Vec<(&str, usize, usize, usize)> {
+    cells
+        .iter()
+        .map(|cell| {
+            (
+                &*cell.name,
+                cell.depth,
+                cell.descendant_count,
+                cell.instance_count,
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn cells_are_listed_depth_first_in_reference_order() {
+    let mut world = load_shared_cells();
+    let cells = CellProxy::gather(&mut world);
+    assert_eq!(
+        summary(&cells),
+        vec![
+            ("top", 0, 5, 1),
+            ("a", 1, 1, 2),
+            ("leaf", 2, 0, 2),
+            ("b", 1, 0, 1),
+            ("a", 1, 1, 2),
+            ("leaf", 2, 0, 2),
+        ]
+    );
+    assert_ne!(cells[1].instance, cells[4].instance);
+    assert_eq!(cells[1].cell_definition, cells[4].cell_definition);
+}
+
+#[test]
+fn hidden_cells_keep_their_place_in_the_tree() {
+    let mut world = load_shared_cells();
+    let before = CellProxy::gather(&mut world);
+    let a = find_cell(&mut world, "a");
+    set_cell_display(&mut world, a, CellDisplay::Hidden);
+
+    // Hidden cells and their contents stay listed, so that they can be
+    // found and shown again.
+    let after = CellProxy::gather(&mut world);
+    assert_eq!(summary(&after), summary(&before));
+    let displays: Vec<CellDisplay> = after.iter().map(|cell| cell.display).collect();
+    assert_eq!(
+        displays,
+        vec![
+            CellDisplay::Contents,
+            CellDisplay::Hidden,
+            CellDisplay::Contents,
+            CellDisplay::Contents,
+            CellDisplay::Hidden,
+            CellDisplay::Contents,
+        ]
+    );
+}
+
+#[test]
+fn rerooting_lists_only_the_new_tree() {
+    let mut world = load_shared_cells();
+    let a = find_cell(&mut world, "a");
+    Instancer::new(&mut world).select_root(&mut world, a);
+    let cells = CellProxy::gather(&mut world);
+    assert_eq!(summary(&cells), vec![("a", 0, 1, 1), ("leaf", 1, 0, 1)]);
+
+    assert!(CellProxy::gather(&mut World::new()).is_empty());
+}
+
+#[test]
+fn names_and_lists_are_shared() {
+    let mut world = load_shared_cells();
+    let cells = CellProxy::gather(&mut world);
+
+    let mut names: HashMap<_, &Rc<str>> = HashMap::new();
+    for cell in &cells {
+        let name = names.entry(cell.cell_definition).or_insert(&cell.name);
+        assert!(Rc::ptr_eq(name, &cell.name));
+    }
+    assert_eq!(names.len(), 4);
+
+    // Lists compare by identity rather than by content.
+    let list = CellProxyList::new(cells.clone());
+    assert_eq!(list.len(), cells.len());
+    assert!(list == list.clone());
+    assert!(list != CellProxyList::new(cells));
+}
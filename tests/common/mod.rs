@@ -0,0 +1,112 @@
+//! Fixtures shared by the integration tests. Each test crate uses a different
+//! subset of them.
+#![allow(dead_code)]
+
+use bevy_ecs::entity::Entity;
+use bevy_ecs::world::World;
+use gds21::GdsBoundary;
+use gds21::GdsElement;
+use gds21::GdsLibrary;
+use gds21::GdsPoint;
+use gds21::GdsStruct;
+use gds21::GdsStructRef;
+use layout_viewer::core::components::Layer;
+use layout_viewer::core::instancer::Instancer;
+use layout_viewer::core::loader::Loader;
+use layout_viewer::core::root_finder::RootFinder;
+use layout_viewer::graphics::geometry::Geometry;
+use layout_viewer::graphics::mesh::Mesh;
+
+/// Loads a GDS file without instantiating any cell.
+pub fn load(gds_content: &[u8]) -> World {
+    let mut world = None;
+    for progress in Loader::new(gds_content) {
+        world = progress.unwrap().take_world();
+    }
+    world.expect("World was not yielded")
+}
+
+/// Loads a GDS file and instantiates its default root cell.
+pub fn load_and_instantiate(gds_content: &[u8]) -> World {
+    let mut world = load(gds_content);
+    let mut root_finder = RootFinder::new(&mut world);
+    let root = root_finder.find_default_root(&world).unwrap();
+    Instancer::new(&mut world).select_root(&mut world, root);
+    world
+}
+
+/// Loads the ring oscillator example and instantiates its default root.
+pub fn load_ringo() -> World {
+    load_and_instantiate(include_bytes!("../../assets/gds/ringo.gds"))
+}
+
+/// Builds a library from (cell name, elements) pairs, then loads it and
+/// instantiates its default root cell.
+pub fn load_cells(cells: Vec<(&str, Vec<GdsElement>)>) -> World {
+    let mut library = GdsLibrary::new("cells");
+    for (name, elems) in cells {
+        let mut gds_struct = GdsStruct::new(name);
+        gds_struct.elems = elems;
+        library.structs.push(gds_struct);
+    }
+    load_and_instantiate(&to_bytes(&library))
+}
+
+/// A polygon on the given layer through the given points, which is closed
+/// by repeating the first point.
+pub fn polygon(layer: i16, points: &[(i32, i32)]) -> GdsElement {
+    let mut xy = points.to_vec();
+    xy.push(points[0]);
+    GdsElement::GdsBoundary(GdsBoundary {
+        layer,
+        datatype: 0,
+        xy: GdsPoint::vec(&xy),
+        ..Default::default()
+    })
+}
+
+/// A rectangle on the given layer with its lower left corner at (x, y).
+pub fn rect(layer: i16, x: i32, y: i32, width: i32, height: i32) -> GdsElement {
+    polygon(
+        layer,
+        &[
+            (x, y),
+            (x + width, y),
+            (x + width, y + height),
+            (x, y + height),
+        ],
+    )
+}
+
+/// A reference to the named cell with its origin at (x, y).
+pub fn place(name: &str, x: i32, y: i32) -> GdsElement {
+    GdsElement::GdsStructRef(GdsStructRef {
+        name: name.to_string(),
+        xy: GdsPoint::new(x, y),
+        ..Default::default()
+    })
+}
+
+/// Returns the number of mesh instances across all layers, which is what
+/// the GPU would draw with nothing culled.
+pub fn total_mesh_instances(world: &mut World) -> usize {
+    let meshes: Vec<Entity> = world
+        .query::<&Layer>()
+        .iter(world)
+        .flat_map(|layer| layer.meshes.clone())
+        .collect();
+    meshes
+        .into_iter()
+        .map(|mesh| {
+            let geometry = world.get::<Mesh>(mesh).unwrap().geometry;
+            world.get::<Geometry>(geometry).unwrap().instance_count()
+        })
+        .sum()
+}
+
+/// Serializes a library the way it would be stored in a GDS file.
+pub fn to_bytes(library: &GdsLibrary) -> Vec<u8> {
+    let mut gds_content = Vec::new();
+    library.write(&mut gds_content).unwrap();
+    gds_content
+}
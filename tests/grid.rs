@@ -0,0 +1,51 @@
+//! Verifies the rounding of grid spacings and scale bar lengths, and the
+//! formatting of the coordinate readout.
+
+use layout_viewer::core::grid::format_microns;
+use layout_viewer::core::grid::nice_length_at_least;
+use layout_viewer::core::grid::nice_length_at_most;
+use layout_viewer::core::units::LibraryUnits;
+
+fn assert_close(actual: f64, expected: f64) {
+    assert!(
+        (actual - expected).abs() <= expected.abs() * 1e-9,
+        "expected {}, got {}",
+        expected,
+        actual
+    );
+}
+
+#[test]
+fn grid_spacing_rounds_up_to_nice_lengths() {
+    assert_close(nice_length_at_least(0.7), 1.0);
+    assert_close(nice_length_at_least(1.0), 1.0);
+    assert_close(nice_length_at_least(1.3), 2.0);
+    assert_close(nice_length_at_least(3.0), 5.0);
+    assert_close(nice_length_at_least(6.0), 10.0);
+    assert_close(nice_length_at_least(0.0031), 0.005);
+    assert_close(nice_length_at_least(420.0), 500.0);
+}
+
+#[test]
+fn scale_bar_rounds_down_to_nice_lengths() {
+    assert_close(nice_length_at_most(0.7), 0.5);
+    assert_close(nice_length_at_most(1.0), 1.0);
+    assert_close(nice_length_at_most(1.3), 1.0);
+    assert_close(nice_length_at_most(3.0), 2.0);
+    assert_close(nice_length_at_most(9.9), 5.0);
+    assert_close(nice_length_at_most(0.0031), 0.002);
+    assert_close(nice_length_at_most(1200.0), 1000.0);
+}
+
+#[test]
+fn readouts_show_microns_and_database_units() {
+    assert_eq!(format_microns(0.05), "0.05 µm");
+    assert_eq!(format_microns(200.0), "200 µm");
+    assert_eq!(format_microns(2000.0), "2 mm");
+
+    let units = LibraryUnits::default();
+    assert_eq!(
+        units.describe_point((1500.4, -20.0)),
+        "x 1.5004 µm, y -0.02 µm (1500, -20 dbu)"
+    );
+}
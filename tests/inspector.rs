@@ -0,0 +1,161 @@
+//! Verifies the shape reports shown by the inspector.
+
+mod common;
+
+use bevy_ecs::entity::Entity;
+use bevy_ecs::world::World;
+use common::load_cells;
+use common::place;
+use common::rect;
+use gds21::GdsElement;
+use gds21::GdsPath;
+use gds21::GdsPoint;
+use gds21::GdsStrans;
+use layout_viewer::core::components::ShapeInstance;
+use layout_viewer::core::inspector::ShapeKind;
+use layout_viewer::core::inspector::ShapeReport;
+use layout_viewer::core::units::LibraryUnits;
+use layout_viewer::graphics::bounds::BoundingBox;
+
+/// A leaf cell with a 10 x 20 rectangle on layer 1 and a bent path on
+/// layer 2. The top cell places it once through a rotated cell that
+/// magnifies it, and once directly with a reflection.
+fn load_transformed_leaves() -> World {
+    let with_strans = |element: GdsElement, strans: GdsStrans| match element {
+        GdsElement::GdsStructRef(mut cell_ref) => {
+            cell_ref.strans = Some(strans);
+            GdsElement::GdsStructRef(cell_ref)
+        }
+        _ => unreachable!(),
+    };
+    let path = GdsElement::GdsPath(GdsPath {
+        layer: 2,
+        datatype: 0,
+        xy: GdsPoint::vec(&[(0, 0), (100, 0), (100, 50)]),
+        width: Some(10),
+        ..Default::default()
+    });
+    load_cells(vec![
+        ("leaf", vec![rect(1, 0, 0, 10, 20), path]),
+        (
+            "mid",
+            vec![with_strans(
+                place("leaf", 0, 500),
+                GdsStrans {
+                    mag: Some(2.0),
+                    ..Default::default()
+                },
+            )],
+        ),
+        (
+            "top",
+            vec![
+                with_strans(
+                    place("mid", 1000, 0),
+                    GdsStrans {
+                        angle: Some(90.0),
+                        ..Default::default()
+                    },
+                ),
+                with_strans(
+                    place("leaf", -100, -100),
+                    GdsStrans {
+                        reflected: true,
+                        ..Default::default()
+                    },
+                ),
+            ],
+        ),
+    ])
+}
+
+/// Returns the reports of every shape on the given layer, ordered by the
+/// length of their instance path.
+fn reports_on_layer(world: &mut World, layer: i16) -> Vec<ShapeReport> {
+    let shapes: Vec<Entity> = world
+        .query::<(Entity, &ShapeInstance)>()
+        .iter(world)
+        .filter(|(_, shape)| shape.layer_key.layer == layer)
+        .map(|(entity, _)| entity)
+        .collect();
+    let mut reports: Vec<ShapeReport> = shapes
+        .into_iter()
+        .map(|shape| ShapeReport::new(world, shape).unwrap())
+        .collect();
+    reports.sort_by_key(|report| report.instance_path.len());
+    reports
+}
+
+fn assert_bounds(bounds: BoundingBox, expected: (f64, f64, f64, f64)) {
+    let actual = (bounds.min_x, bounds.min_y, bounds.max_x, bounds.max_y);
+    for (a, e) in [
+        (actual.0, expected.0),
+        (actual.1, expected.1),
+        (actual.2, expected.2),
+        (actual.3, expected.3),
+    ] {
+        assert!((a - e).abs() < 1e-6, "{:?} != {:?}", actual, expected);
+    }
+}
+
+fn row(report: &ShapeReport, label: &str) -> String {
+    report
+        .rows(&LibraryUnits::new(1e-9, 1e-3))
+        .into_iter()
+        .find(|(name, _)| name == label)
+        .map(|(_, value)| value)
+        .unwrap()
+}
+
+#[test]
+fn nested_transforms_compose_along_the_instance_path() {
+    let mut world = load_transformed_leaves();
+    let reports = reports_on_layer(&mut world, 1);
+    assert_eq!(reports.len(), 2);
+    let report = &reports[1];
+
+    assert_eq!(report.instance_path, vec!["top", "mid", "leaf"]);
+    assert_eq!(report.cell_name, "leaf");
+    assert_eq!(report.kind, ShapeKind::Polygon);
+    assert_eq!(report.vertex_count, 5);
+
+    // Magnified to 20 x 40 at (0, 500), rotated a quarter turn, then moved
+    // to (1000, 0).
+    assert_bounds(report.world_bounds, (460.0, 0.0, 500.0, 20.0));
+    assert!((report.area - 800.0).abs() < 1e-6);
+    assert_eq!(row(report, "Path"), "top / mid / leaf");
+
+    // Rotation leaves rounding noise far below a database unit, which is not
+    // shown.
+    assert_eq!(row(report, "Bounds"), "(0.46, 0) to (0.5, 0.02) µm");
+    assert_eq!(
+        row(report, "Transform"),
+        "translate (0.5, 0) µm, rotate 90°, magnify 2"
+    );
+}
+
+#[test]
+fn reflected_instances_are_reported_as_mirrored() {
+    let mut world = load_transformed_leaves();
+    let report = &reports_on_layer(&mut world, 1)[0];
+
+    assert_eq!(report.instance_path, vec!["top", "leaf"]);
+    assert_bounds(report.world_bounds, (-100.0, -120.0, -90.0, -100.0));
+    assert_eq!(report.area, 200.0);
+    assert_eq!(
+        row(report, "Transform"),
+        "translate (-0.1, -0.1) µm, mirrored"
+    );
+}
+
+#[test]
+fn paths_report_their_width_and_spine() {
+    let mut world = load_transformed_leaves();
+    let report = &reports_on_layer(&mut world, 2)[0];
+
+    assert_eq!(report.kind, ShapeKind::Path { width: 10.0 });
+    assert_eq!(report.vertex_count, 3);
+    assert_eq!(row(report, "Type"), "Path, 0.01 µm wide");
+    assert_eq!(row(report, "Vertices"), "3");
+    assert_eq!(row(report, "Layer"), "2/0");
+}
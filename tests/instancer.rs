@@ -0,0 +1,280 @@
+//! Verifies root selection, that the Instancer survives reference cycles,
+//! deep nesting and large trees, and that cell meshes receive one transform
+//! per instance.
+
+mod common;
+
+use bevy_ecs::entity::Entity;
+use bevy_ecs::world::World;
+use gds21::GdsElement;
+use gds21::GdsLibrary;
+use gds21::GdsPoint;
+use gds21::GdsStruct;
+use gds21::GdsStructRef;
+use layout_viewer::core::components::CellDefinition;
+use layout_viewer::core::components::CellInstance;
+use layout_viewer::core::components::Layer;
+use layout_viewer::core::components::RootCellInstance;
+use layout_viewer::core::instancer::InstanceLimits;
+use layout_viewer::core::instancer::Instancer;
+use layout_viewer::core::instancer::SkippedReference;
+use layout_viewer::core::root_finder::RootFinder;
+use layout_viewer::graphics::geometry::Geometry;
+use layout_viewer::graphics::mesh::Mesh;
+
+/// Builds a library from (cell name, referenced cell names) pairs.
+fn load(cells: &[(&str, &[&str])]) -> World {
+    let mut library = GdsLibrary::new("hierarchy");
+    for (name, refs) in cells {
+        let mut gds_struct = GdsStruct::new(*name);
+        for child in *refs {
+            gds_struct
+                .elems
+                .push(GdsElement::GdsStructRef(GdsStructRef {
+                    name: child.to_string(),
+                    xy: GdsPoint::new(0, 0),
+                    ..Default::default()
+                }));
+        }
+        library.structs.push(gds_struct);
+    }
+    common::load(&common::to_bytes(&library))
+}
+
+fn instantiate(world: &mut World, root: &str, max_depth: Option<usize>) -> Instancer {
+    let root = world
+        .query::<(Entity, &CellDefinition)>()
+        .iter(world)
+        .find(|(_, cell_def)| cell_def.name == root)
+        .unwrap()
+        .0;
+    let mut instancer = Instancer::new(world);
+    if let Some(max_depth) = max_depth {
+        instancer.set_max_depth(max_depth);
+    }
+    instancer.select_root(world, root);
+    instancer
+}
+
+fn instance_count(world: &mut World) -> usize {
+    world.query::<&CellInstance>().iter(world).count()
+}
+
+#[test]
+fn reference_cycle() {
+    let mut world = load(&[("top", &["a"]), ("a", &["b"]), ("b", &["a"])]);
+    let instancer = instantiate(&mut world, "top", None);
+
+    let cycle = ["a", "b", "a"].map(String::from).to_vec();
+    assert_eq!(
+        instancer.skipped_references(),
+        &[SkippedReference::Cycle(cycle)]
+    );
+    assert_eq!(instance_count(&mut world), 3);
+}
+
+#[test]
+fn self_reference() {
+    let mut world = load(&[("top", &["top", "leaf"]), ("leaf", &[])]);
+    let instancer = instantiate(&mut world, "top", None);
+
+    let cycle = ["top", "top"].map(String::from).to_vec();
+    assert_eq!(
+        instancer.skipped_references(),
+        &[SkippedReference::Cycle(cycle)]
+    );
+    assert_eq!(instance_count(&mut world), 2);
+}
+
+#[test]
+fn depth_limit() {
+    let mut world = load(&[
+        ("c0", &["c1"]),
+        ("c1", &["c2"]),
+        ("c2", &["c3"]),
+        ("c3", &["c4"]),
+        ("c4", &[]),
+    ]);
+    let instancer = instantiate(&mut world, "c0", Some(2));
+
+    let expected = SkippedReference::DepthLimit {
+        parent: "c2".to_string(),
+        name: "c3".to_string(),
+    };
+    assert_eq!(instancer.skipped_references(), &[expected]);
+    assert_eq!(instance_count(&mut world), 3);
+}
+
+#[test]
+fn instance_limit() {
+    let mut world = load(&[
+        ("top", &["a", "b"]),
+        ("a", &["leaf"]),
+        ("b", &["leaf"]),
+        ("leaf", &[]),
+    ]);
+    let root = RootFinder::new(&mut world)
+        .find_cell(&world, "top")
+        .unwrap();
+    let mut instancer = Instancer::new(&mut world);
+    instancer.set_max_instances(3);
+    instancer.select_root(&mut world, root);
+
+    // The traversal stops at the first reference beyond the limit.
+    let expected = SkippedReference::InstanceLimit {
+        parent: "top".to_string(),
+        name: "b".to_string(),
+    };
+    assert_eq!(instancer.skipped_references(), &[expected]);
+    assert_eq!(instance_count(&mut world), 3);
+
+    // The root is always instantiated.
+    instancer.set_max_instances(0);
+    instancer.select_root(&mut world, root);
+    assert_eq!(instance_count(&mut world), 1);
+}
+
+#[test]
+fn instance_limits_from_query() {
+    let query = |pairs: &[(&str, &str)]| -> Vec<(String, String)> {
+        pairs
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect()
+    };
+    let limits = InstanceLimits::from_query(&query(&[
+        ("max_depth", "8"),
+        ("max_instances", "1000"),
+        ("x", "5"),
+    ]));
+    assert_eq!(
+        limits,
+        InstanceLimits {
+            max_depth: 8,
+            max_instances: 1000,
+        }
+    );
+
+    let limits = InstanceLimits::from_query(&query(&[("max_depth", "-1"), ("max_instances", "")]));
+    assert_eq!(limits, InstanceLimits::default());
+}
+
+#[test]
+fn shared_cells_are_not_cycles() {
+    let mut world = load(&[
+        ("top", &["a", "b"]),
+        ("a", &["leaf"]),
+        ("b", &["leaf"]),
+        ("leaf", &[]),
+    ]);
+    let instancer = instantiate(&mut world, "top", None);
+
+    assert!(instancer.skipped_references().is_empty());
+    assert_eq!(instance_count(&mut world), 5);
+}
+
+#[test]
+fn default_root_has_most_descendants() {
+    let mut world = load(&[
+        ("empty", &[]),
+        ("small", &["leaf"]),
+        ("large", &["mid"]),
+        ("mid", &["leaf"]),
+        ("leaf", &[]),
+    ]);
+    let mut root_finder = RootFinder::new(&mut world);
+
+    let names = |world: &World, roots: Vec<Entity>| -> Vec<String> {
+        roots
+            .iter()
+            .map(|root| world.get::<CellDefinition>(*root).unwrap().name.clone())
+            .collect()
+    };
+    let roots = root_finder.find_roots(&world);
+    assert_eq!(names(&world, roots), ["empty", "large", "small"]);
+
+    let root = root_finder.find_default_root(&world).unwrap();
+    assert_eq!(names(&world, vec![root]), ["large"]);
+}
+
+fn layer_bounds(world: &mut World) -> Vec<(usize, f64, f64, f64, f64)> {
+    let mut bounds: Vec<_> = world
+        .query::<&Layer>()
+        .iter(world)
+        .map(|layer| {
+            let b = layer.world_bounds;
+            (
+                layer.shape_instances.len(),
+                b.min_x,
+                b.min_y,
+                b.max_x,
+                b.max_y,
+            )
+        })
+        .collect();
+    bounds.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    bounds
+}
+
+#[test]
+fn select_root_replaces_existing_tree() {
+    let mut world = common::load(include_bytes!("../assets/gds/ringo.gds"));
+    instantiate(&mut world, "RINGO", None);
+    instantiate(&mut world, "INVX1", None);
+
+    let mut expected_world = common::load(include_bytes!("../assets/gds/ringo.gds"));
+    instantiate(&mut expected_world, "INVX1", None);
+
+    let roots = world.query::<&RootCellInstance>().iter(&world).count();
+    assert_eq!(roots, 1);
+    assert_eq!(
+        instance_count(&mut world),
+        instance_count(&mut expected_world)
+    );
+    assert_eq!(layer_bounds(&mut world), layer_bounds(&mut expected_world));
+}
+
+#[test]
+fn cell_meshes_have_one_instance_per_cell_instance() {
+    let mut world = common::load(include_bytes!("../assets/gds/ringo.gds"));
+    instantiate(&mut world, "RINGO", None);
+
+    let cells: Vec<(Entity, Vec<Entity>)> = world
+        .query::<(Entity, &CellDefinition)>()
+        .iter(&world)
+        .map(|(entity, cell_def)| (entity, cell_def.meshes.clone()))
+        .collect();
+
+    let mut instanced_cells = 0;
+    for (cell_def, meshes) in cells {
+        let instances = world
+            .query::<&CellInstance>()
+            .iter(&world)
+            .filter(|instance| instance.cell_definition == cell_def)
+            .count();
+        if instances > 0 && !meshes.is_empty() {
+            instanced_cells += 1;
+        }
+        for mesh in meshes {
+            let geometry = world.get::<Mesh>(mesh).unwrap().geometry;
+            let geometry = world.get::<Geometry>(geometry).unwrap();
+            assert!(geometry.is_instanced());
+            assert!(!geometry.indices.is_empty());
+            assert_eq!(geometry.instance_count(), instances);
+        }
+    }
+    assert!(instanced_cells > 1);
+
+    // Every layer mesh belongs to exactly one cell definition.
+    let layer_meshes: usize = world
+        .query::<&Layer>()
+        .iter(&world)
+        .map(|layer| layer.meshes.len())
+        .sum();
+    let cell_meshes: usize = world
+        .query::<&CellDefinition>()
+        .iter(&world)
+        .map(|cell_def| cell_def.meshes.len())
+        .sum();
+    assert_eq!(layer_meshes, cell_meshes);
+}
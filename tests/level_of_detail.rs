@@ -0,0 +1,143 @@
+//! Verifies that every tile has a detailed mesh and a density quad, that
+//! exactly one of them is drawn at any zoom level, and what a zoomed out
+//! view of many small instances costs.
+
+mod common;
+
+use common::load_ringo;
+use gds21::GdsArrayRef;
+use gds21::GdsBoundary;
+use gds21::GdsElement;
+use gds21::GdsLibrary;
+use gds21::GdsPoint;
+use gds21::GdsStruct;
+use layout_viewer::core::components::CellDefinition;
+use layout_viewer::core::components::LayerMesh;
+use layout_viewer::graphics::geometry::Geometry;
+use layout_viewer::graphics::mesh::LevelOfDetail;
+use layout_viewer::graphics::mesh::Mesh;
+
+#[test]
+fn detail_and_coarse_are_exclusive() {
+    let detail = LevelOfDetail::Detail { feature_size: 10.0 };
+    let coarse = LevelOfDetail::Coarse { feature_size: 10.0 };
+    for pixel_size in [0.1, 1.0, 9.9, 10.0, 10.1, 1000.0] {
+        assert_ne!(detail.is_drawn(pixel_size), coarse.is_drawn(pixel_size));
+    }
+    assert!(detail.is_drawn(1.0));
+    assert!(coarse.is_drawn(100.0));
+    assert!(LevelOfDetail::Always.is_drawn(1e9));
+}
+
+#[test]
+fn tiles_have_density_quads() {
+    let mut world = load_ringo();
+
+    let cells: Vec<Vec<_>> = world
+        .query::<&CellDefinition>()
+        .iter(&world)
+        .map(|cell_def| cell_def.meshes.clone())
+        .collect();
+
+    for meshes in cells {
+        // Meshes come in pairs of detail and density quad.
+        assert_eq!(meshes.len() % 2, 0);
+        for pair in meshes.chunks(2) {
+            let detail = world.get::<Mesh>(pair[0]).unwrap();
+            let coarse = world.get::<Mesh>(pair[1]).unwrap();
+            assert!(matches!(
+                detail.level_of_detail,
+                LevelOfDetail::Detail { .. }
+            ));
+            assert!(matches!(
+                coarse.level_of_detail,
+                LevelOfDetail::Coarse { .. }
+            ));
+            assert_eq!(
+                detail.level_of_detail.feature_size(),
+                coarse.level_of_detail.feature_size()
+            );
+
+            let opacity = world.get::<LayerMesh>(pair[1]).unwrap().opacity;
+            assert!(opacity > 0.0 && opacity <= 1.0);
+        }
+    }
+}
+
+#[test]
+fn instancing_sets_feature_size() {
+    let mut world = load_ringo();
+    let mut query = world.query::<(&Mesh, &LayerMesh)>();
+    for (mesh, layer_mesh) in query.iter(&world) {
+        let feature_size = mesh.level_of_detail.feature_size().unwrap();
+        if mesh.bounds.unwrap().is_empty() {
+            assert_eq!(feature_size, 0.0);
+        } else {
+            // Ringo has no magnified references.
+            assert_eq!(feature_size, layer_mesh.feature_size);
+        }
+    }
+}
+
+#[test]
+fn many_small_instances_draw_one_quad_each() {
+    // A cell of four 10 x 5 rectangles, arrayed 100 x 100 with a pitch of 50.
+    let mut library = GdsLibrary::new("lod");
+    let mut unit = GdsStruct::new("unit");
+    for (x, y) in [(0, 0), (20, 0), (0, 20), (20, 20)] {
+        unit.elems.push(GdsElement::GdsBoundary(GdsBoundary {
+            layer: 1,
+            datatype: 0,
+            xy: GdsPoint::vec(&[(x, y), (x + 10, y), (x + 10, y + 5), (x, y + 5), (x, y)]),
+            ..Default::default()
+        }));
+    }
+    library.structs.push(unit);
+    let mut top = GdsStruct::new("top");
+    top.elems.push(GdsElement::GdsArrayRef(GdsArrayRef {
+        name: "unit".to_string(),
+        xy: [
+            GdsPoint::new(0, 0),
+            GdsPoint::new(5000, 0),
+            GdsPoint::new(0, 5000),
+        ],
+        cols: 100,
+        rows: 100,
+        ..Default::default()
+    }));
+    library.structs.push(top);
+    let mut world = common::load_and_instantiate(&common::to_bytes(&library));
+
+    let mut query = world.query::<(&Mesh, &LayerMesh)>();
+    let meshes: Vec<_> = query
+        .iter(&world)
+        .map(|(mesh, _)| {
+            (
+                mesh.level_of_detail,
+                world.get::<Geometry>(mesh.geometry).unwrap(),
+            )
+        })
+        .filter(|(_, geometry)| geometry.instance_count() > 0)
+        .collect();
+    assert_eq!(meshes.len(), 2);
+
+    // With one pixel covering 100 units, the 10 unit rectangles give way to
+    // the density quad. Quads are not merged across instances, so the cost
+    // is one quad per instance, all in a single instance group.
+    for (level_of_detail, geometry) in meshes {
+        assert_eq!(geometry.instance_count(), 10_000);
+        assert_eq!(geometry.instance_groups().len(), 1);
+        match level_of_detail {
+            LevelOfDetail::Detail { feature_size } => {
+                assert_eq!(feature_size, 10.0);
+                assert!(!level_of_detail.is_drawn(100.0));
+                assert_eq!(geometry.indices.len(), 4 * 6);
+            }
+            LevelOfDetail::Coarse { .. } => {
+                assert!(level_of_detail.is_drawn(100.0));
+                assert_eq!(geometry.indices.len(), 6);
+            }
+            LevelOfDetail::Always => panic!("tile meshes have a level of detail"),
+        }
+    }
+}
@@ -0,0 +1,87 @@
+//! Verifies that malformed GDS files are reported as errors rather than panics.
+
+mod common;
+
+use common::to_bytes;
+use gds21::GdsBoundary;
+use gds21::GdsElement;
+use gds21::GdsLibrary;
+use gds21::GdsPoint;
+use gds21::GdsStruct;
+use gds21::GdsStructRef;
+use layout_viewer::core::loader::LoadError;
+use layout_viewer::core::loader::Loader;
+
+fn load(gds_content: &[u8]) -> Result<(), LoadError> {
+    let mut world = None;
+    for progress in Loader::new(gds_content) {
+        world = progress?.take_world();
+    }
+    assert!(world.is_some(), "World was not yielded");
+    Ok(())
+}
+
+#[test]
+fn parse_error() {
+    let result = load(b"this is not a gds file");
+    assert!(matches!(result, Err(LoadError::Parse(_))), "{:?}", result);
+}
+
+#[test]
+fn empty_library() {
+    let library = GdsLibrary::new("empty");
+    assert_eq!(load(&to_bytes(&library)), Err(LoadError::EmptyLibrary));
+}
+
+#[test]
+fn unknown_cell() {
+    let mut top = GdsStruct::new("top");
+    top.elems.push(GdsElement::GdsStructRef(GdsStructRef {
+        name: "missing".to_string(),
+        xy: GdsPoint::new(0, 0),
+        ..Default::default()
+    }));
+    let mut library = GdsLibrary::new("dangling");
+    library.structs.push(top);
+
+    let expected = LoadError::UnknownCell {
+        parent: "top".to_string(),
+        name: "missing".to_string(),
+    };
+    assert_eq!(load(&to_bytes(&library)), Err(expected));
+}
+
+#[test]
+fn valid_library() {
+    let mut library = GdsLibrary::new("valid");
+    library.structs.push(GdsStruct::new("top"));
+    assert_eq!(load(&to_bytes(&library)), Ok(()));
+}
+
+#[test]
+fn empty_structures_between_others() {
+    let boundary = || {
+        GdsElement::GdsBoundary(GdsBoundary {
+            layer: 1,
+            datatype: 0,
+            xy: vec![
+                GdsPoint::new(0, 0),
+                GdsPoint::new(10, 0),
+                GdsPoint::new(10, 10),
+                GdsPoint::new(0, 0),
+            ],
+            ..Default::default()
+        })
+    };
+    let mut library = GdsLibrary::new("gaps");
+    library.structs.push(GdsStruct::new("empty_first"));
+    let mut a = GdsStruct::new("a");
+    a.elems.push(boundary());
+    library.structs.push(a);
+    library.structs.push(GdsStruct::new("empty"));
+    library.structs.push(GdsStruct::new("also_empty"));
+    let mut c = GdsStruct::new("c");
+    c.elems.extend([boundary(), boundary(), boundary()]);
+    library.structs.push(c);
+    assert_eq!(load(&to_bytes(&library)), Ok(()));
+}
@@ -0,0 +1,90 @@
+//! Verifies where the minimap is placed, how its pixels map to the world,
+//! and that its render orders sit between the layers and the overlays.
+
+use layout_viewer::core::components::LayerKey;
+use layout_viewer::core::components::BACKGROUND_RENDER_ORDER;
+use layout_viewer::core::components::MINIMAP_RENDER_ORDER;
+use layout_viewer::core::components::OVERLAY_RENDER_ORDER;
+use layout_viewer::core::minimap::MinimapView;
+use layout_viewer::graphics::bounds::BoundingBox;
+use layout_viewer::graphics::camera::Camera;
+use layout_viewer::graphics::vectors::Point2d;
+use layout_viewer::graphics::vectors::Point3d;
+
+const WINDOW_SIZE: (u32, u32) = (800, 600);
+
+/// Oriented like the AppController's camera.
+fn main_camera() -> Camera {
+    Camera::new(Point3d::new(0.0, 0.0, 0.0), 128.0, 96.0, -1.0, 1.0)
+}
+
+fn bounds(min_x: f64, min_y: f64, max_x: f64, max_y: f64) -> BoundingBox {
+    BoundingBox {
+        min_x,
+        min_y,
+        max_x,
+        max_y,
+    }
+}
+
+#[test]
+fn minimap_is_shaped_like_the_layout_in_the_top_right_corner() {
+    // Screen x follows world y, so this layout is twice as wide as it is
+    // tall on screen.
+    let view = MinimapView::new(
+        &main_camera(),
+        WINDOW_SIZE,
+        &bounds(0.0, 0.0, 500.0, 1000.0),
+    )
+    .unwrap();
+    let inset = view.inset;
+    assert_eq!((inset.width, inset.height), (200.0, 100.0));
+    assert_eq!(inset.left + inset.width, WINDOW_SIZE.0 as f64 - 16.0);
+    assert_eq!(inset.top, 16.0);
+
+    // Tall layouts are limited by the height of the window.
+    let view = MinimapView::new(
+        &main_camera(),
+        WINDOW_SIZE,
+        &bounds(0.0, 0.0, 1000.0, 100.0),
+    )
+    .unwrap();
+    assert_eq!((view.inset.width, view.inset.height), (15.0, 150.0));
+
+    assert!(MinimapView::new(&main_camera(), WINDOW_SIZE, &BoundingBox::new()).is_none());
+    assert!(MinimapView::new(&main_camera(), (1, 1), &bounds(0.0, 0.0, 1.0, 1.0)).is_none());
+}
+
+#[test]
+fn minimap_pixels_map_to_the_layout() {
+    let layout = bounds(0.0, 0.0, 500.0, 1000.0);
+    let view = MinimapView::new(&main_camera(), WINDOW_SIZE, &layout).unwrap();
+    let inset = view.inset;
+    let (left, top) = (inset.left as u32, inset.top as u32);
+    let (right, bottom) = (left + inset.width as u32, top + inset.height as u32);
+
+    let center = view.screen_to_world((left + right) / 2, (top + bottom) / 2);
+    assert!((center - Point2d::new(250.0, 500.0)).norm() < 1e-9);
+
+    // The layout fits inside the minimap with some room to spare. Screen up
+    // follows world -x.
+    let top_left = view.screen_to_world(left, top);
+    let bottom_right = view.screen_to_world(right, bottom);
+    assert!(top_left.x < layout.min_x && top_left.y < layout.min_y);
+    assert!(bottom_right.x > layout.max_x && bottom_right.y > layout.max_y);
+
+    assert!(view.contains(left, top));
+    assert!(view.contains(right - 1, bottom - 1));
+    assert!(!view.contains(right, top));
+    assert!(!view.contains(left, bottom));
+}
+
+#[test]
+fn minimap_render_orders_are_between_layers_and_overlays() {
+    // GDS datatypes are at most 255 in practice.
+    let highest_layer = LayerKey::new(i16::MAX, 255).render_order();
+    let lowest_layer = LayerKey::new(0, 0).render_order();
+    assert!(highest_layer < MINIMAP_RENDER_ORDER);
+    const { assert!(MINIMAP_RENDER_ORDER + 1 < OVERLAY_RENDER_ORDER) };
+    assert!(BACKGROUND_RENDER_ORDER < lowest_layer);
+}
@@ -0,0 +1,135 @@
+//! Verifies that vertex and instance data stay small at large coordinates,
+//! so that rendering does not lose precision far from the origin.
+
+mod common;
+
+use bevy_ecs::world::World;
+use gds21::GdsBoundary;
+use gds21::GdsElement;
+use gds21::GdsLibrary;
+use gds21::GdsPoint;
+use gds21::GdsStruct;
+use gds21::GdsStructRef;
+use layout_viewer::core::components::LayerMesh;
+use layout_viewer::graphics::geometry::Geometry;
+use layout_viewer::graphics::mesh::Mesh;
+use layout_viewer::graphics::vectors::Point2d;
+use nalgebra::Vector4;
+
+/// Corner of a 10 mm die in nanometre database units.
+const FAR: i32 = 10_000_000;
+
+/// Places the 3 x 1 leaf at each of the given positions.
+fn load_leaves(positions: &[(i32, i32)]) -> World {
+    let mut library = GdsLibrary::new("far");
+
+    let mut leaf = GdsStruct::new("leaf");
+    leaf.elems.push(GdsElement::GdsBoundary(GdsBoundary {
+        layer: 1,
+        datatype: 0,
+        xy: GdsPoint::vec(&[(0, 0), (3, 0), (3, 1), (0, 1), (0, 0)]),
+        ..Default::default()
+    }));
+    library.structs.push(leaf);
+
+    let mut top = GdsStruct::new("top");
+    for &(x, y) in positions {
+        top.elems.push(GdsElement::GdsStructRef(GdsStructRef {
+            name: "leaf".to_string(),
+            xy: GdsPoint::new(x, y),
+            ..Default::default()
+        }));
+    }
+    library.structs.push(top);
+
+    common::load_and_instantiate(&common::to_bytes(&library))
+}
+
+fn load_far_library() -> World {
+    load_leaves(&[(FAR - 100, FAR - 100), (FAR - 93, FAR - 100)])
+}
+
+#[test]
+fn vertex_and_instance_data_are_small() {
+    let mut world = load_far_library();
+    let mut query = world.query::<(&Mesh, &LayerMesh)>();
+    let mut checked = 0;
+    for (mesh, _) in query.iter(&world) {
+        let geometry = world.get::<Geometry>(mesh.geometry).unwrap();
+        assert!(geometry.positions.iter().all(|v| v.abs() <= 2.0));
+        let groups = geometry.instance_groups();
+        assert_eq!(groups.len(), 1);
+        assert!(groups[0].instance_data().iter().all(|v| v.abs() <= 8.0));
+        assert_eq!(geometry.instance_count(), 2);
+        checked += 1;
+    }
+    assert!(checked > 0);
+}
+
+#[test]
+fn vertices_are_placed_exactly() {
+    let mut world = load_far_library();
+    let eye = Point2d::new(FAR as f64 - 99.0, FAR as f64 - 99.5);
+
+    let mut query = world.query::<(&Mesh, &LayerMesh)>();
+    for (mesh, _) in query.iter(&world) {
+        let geometry = world.get::<Geometry>(mesh.geometry).unwrap();
+        let group = &geometry.instance_groups()[0];
+        let model = mesh.model_matrix_at(&group.origin, &eye);
+        for (instance, offset) in group.instance_data().chunks(6).zip([0.0, 7.0]) {
+            for vertex in geometry.positions.chunks(3) {
+                // Apply the instance transform as the shader does.
+                let x = instance[0] * vertex[0] + instance[1] * vertex[1] + instance[2];
+                let y = instance[3] * vertex[0] + instance[4] * vertex[1] + instance[5];
+                let relative = model * Vector4::new(x, y, vertex[2], 1.0);
+
+                let world_x = relative.x as f64 + eye.x;
+                let world_y = relative.y as f64 + eye.y;
+                let local_x = world_x - (FAR as f64 - 100.0 + offset);
+                let local_y = world_y - (FAR as f64 - 100.0);
+                assert!((local_x - local_x.round()).abs() < 1e-4, "{}", local_x);
+                assert!((local_y - local_y.round()).abs() < 1e-4, "{}", local_y);
+                assert!((0.0..=3.0).contains(&local_x.round()));
+                assert!((0.0..=1.0).contains(&local_y.round()));
+            }
+        }
+    }
+}
+
+#[test]
+fn instances_at_opposite_corners_are_placed_exactly() {
+    let corners = [(0.0, 0.0), (FAR as f64, FAR as f64)];
+    let mut world = load_leaves(&[(0, 0), (FAR, FAR)]);
+
+    let mut query = world.query::<(&Mesh, &LayerMesh)>();
+    let mut checked = 0;
+    for (mesh, _) in query.iter(&world) {
+        let geometry = world.get::<Geometry>(mesh.geometry).unwrap();
+        let groups = geometry.instance_groups();
+        assert_eq!(groups.len(), 2);
+
+        for (group, corner) in groups.iter().zip(corners) {
+            assert!(group.instance_data().iter().all(|v| v.abs() <= 2.0));
+
+            // Looking at each corner from close by, its vertices land on
+            // whole database units.
+            let eye = Point2d::new(corner.0 + 0.5, corner.1 + 0.5);
+            let model = mesh.model_matrix_at(&group.origin, &eye);
+            let instance = group.instance_data();
+            for vertex in geometry.positions.chunks(3) {
+                let x = instance[0] * vertex[0] + instance[1] * vertex[1] + instance[2];
+                let y = instance[3] * vertex[0] + instance[4] * vertex[1] + instance[5];
+                let relative = model * Vector4::new(x, y, vertex[2], 1.0);
+
+                let local_x = relative.x as f64 + eye.x - corner.0;
+                let local_y = relative.y as f64 + eye.y - corner.1;
+                assert!((local_x - local_x.round()).abs() < 1e-4, "{}", local_x);
+                assert!((local_y - local_y.round()).abs() < 1e-4, "{}", local_y);
+                assert!((0.0..=3.0).contains(&local_x.round()));
+                assert!((0.0..=1.0).contains(&local_y.round()));
+            }
+        }
+        checked += 1;
+    }
+    assert!(checked > 0);
+}
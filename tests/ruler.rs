@@ -0,0 +1,105 @@
+//! Verifies ruler measurements, snapping to shapes through the R-tree, and
+//! that rulers are written to SVG.
+
+mod common;
+
+use bevy_ecs::world::World;
+use common::load_cells;
+use common::place;
+use common::rect;
+use layout_viewer::cli::generate_svg::generate_svg;
+use layout_viewer::core::cell_display::set_cell_display;
+use layout_viewer::core::components::CellDisplay;
+use layout_viewer::core::components::Layer;
+use layout_viewer::core::root_finder::RootFinder;
+use layout_viewer::core::rtree::build_rtree;
+use layout_viewer::core::ruler::snap_point;
+use layout_viewer::core::ruler::Ruler;
+use layout_viewer::core::ruler::Rulers;
+use layout_viewer::core::units::LibraryUnits;
+use layout_viewer::graphics::vectors::Point2d;
+
+/// A 100 x 100 square on layer 1 at the origin, and a 10 x 10 "pad" cell on
+/// layer 2 placed at (300, 0).
+fn load_square_and_pad() -> World {
+    load_cells(vec![
+        ("pad", vec![rect(2, 0, 0, 10, 10)]),
+        ("top", vec![rect(1, 0, 0, 100, 100), place("pad", 300, 0)]),
+    ])
+}
+
+fn snap(world: &mut World, x: f64, y: f64) -> Point2d {
+    let rtree = build_rtree(world);
+    snap_point(world, &rtree, Point2d::new(x, y), 5.0)
+}
+
+#[test]
+fn ruler_reports_length_and_extents() {
+    let ruler = Ruler::new(Point2d::new(1000.0, 0.0), Point2d::new(4000.0, -4000.0));
+    assert_eq!(ruler.dx(), 3000.0);
+    assert_eq!(ruler.dy(), -4000.0);
+    assert_eq!(ruler.length(), 5000.0);
+    assert_eq!(ruler.midpoint(), Point2d::new(2500.0, -2000.0));
+    assert_eq!(
+        ruler.label(&LibraryUnits::default()),
+        "5 µm (5000 dbu), dx 3 µm, dy -4 µm"
+    );
+
+    let point = Ruler::new(Point2d::new(7.0, 7.0), Point2d::new(7.0, 7.0));
+    assert_eq!(point.length(), 0.0);
+    assert_eq!(
+        point.label(&LibraryUnits::default()),
+        "0 µm (0 dbu), dx 0 µm, dy 0 µm"
+    );
+}
+
+#[test]
+fn vertices_win_over_closer_edges() {
+    let mut world = load_square_and_pad();
+
+    // The corner is 3.6 away, the edges only 2.
+    assert_eq!(snap(&mut world, 3.0, 2.0), Point2d::new(0.0, 0.0));
+
+    // Without a vertex in reach, the point moves onto the nearest edge. The
+    // tolerance is inclusive.
+    assert_eq!(snap(&mut world, 50.0, 2.0), Point2d::new(50.0, 0.0));
+    assert_eq!(snap(&mut world, 50.0, -5.0), Point2d::new(50.0, 0.0));
+    assert_eq!(snap(&mut world, 50.0, -5.5), Point2d::new(50.0, -5.5));
+    assert_eq!(snap(&mut world, 1e9, 1e9), Point2d::new(1e9, 1e9));
+}
+
+#[test]
+fn hidden_shapes_are_not_snapped_to() {
+    let mut world = load_square_and_pad();
+    assert_eq!(snap(&mut world, 301.0, 1.0), Point2d::new(300.0, 0.0));
+
+    let pad = RootFinder::new(&mut world)
+        .find_cell(&world, "pad")
+        .unwrap();
+    set_cell_display(&mut world, pad, CellDisplay::Hidden);
+    assert_eq!(snap(&mut world, 301.0, 1.0), Point2d::new(301.0, 1.0));
+
+    let mut query = world.query::<&mut Layer>();
+    for mut layer in query.iter_mut(&mut world) {
+        layer.visible = false;
+    }
+    assert_eq!(snap(&mut world, 3.0, 2.0), Point2d::new(3.0, 2.0));
+}
+
+#[test]
+fn every_ruler_is_written_to_svg() {
+    let mut world = load_square_and_pad();
+    assert!(!generate_svg(&mut world, false).contains("<line"));
+
+    world.insert_resource(Rulers(vec![
+        Ruler::new(Point2d::new(0.0, 0.0), Point2d::new(3000.0, 4000.0)),
+        Ruler::new(Point2d::new(0.0, 0.0), Point2d::new(0.0, 2000.0)),
+    ]));
+    let svg = generate_svg(&mut world, false);
+    assert_eq!(svg.matches("<line").count(), 2);
+    assert!(svg.contains("5 µm (5000 dbu)"));
+    assert!(svg.contains("2 µm (2000 dbu)"));
+
+    world.insert_resource(Rulers::default());
+    assert!(!generate_svg(&mut world, false).contains("<line"));
+}
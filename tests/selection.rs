@@ -0,0 +1,156 @@
+//! Verifies box queries against the R-tree and the CSV export of selected
+//! shapes.
+
+mod common;
+
+use bevy_ecs::entity::Entity;
+use bevy_ecs::world::World;
+use common::load_cells;
+use common::place;
+use common::polygon;
+use common::rect;
+use layout_viewer::core::cell_display::set_cell_display;
+use layout_viewer::core::components::CellDisplay;
+use layout_viewer::core::components::Layer;
+use layout_viewer::core::components::LayerKey;
+use layout_viewer::core::root_finder::RootFinder;
+use layout_viewer::core::rtree::build_rtree;
+use layout_viewer::core::selection::export_csv;
+use layout_viewer::core::selection::shapes_in_box;
+use layout_viewer::core::selection::SelectedShape;
+use layout_viewer::core::units::LibraryUnits;
+use layout_viewer::graphics::bounds::BoundingBox;
+
+/// Two instances of a 10 x 10 pad on layer 1, and a right triangle on
+/// layer 2 whose bounding box is half empty.
+fn load_pads_and_triangle() -> World {
+    load_cells(vec![
+        ("pad", vec![rect(1, 0, 0, 10, 10)]),
+        (
+            "top",
+            vec![
+                place("pad", 0, 0),
+                place("pad", 100, 0),
+                polygon(2, &[(200, 0), (300, 0), (200, 100)]),
+            ],
+        ),
+    ])
+}
+
+fn bounds(min_x: f64, min_y: f64, max_x: f64, max_y: f64) -> BoundingBox {
+    BoundingBox {
+        min_x,
+        min_y,
+        max_x,
+        max_y,
+    }
+}
+
+/// Returns the cell name and bounds of each shape in the box, from left to
+/// right.
+fn select(world: &mut World, bounds: &BoundingBox) -> Vec<(String, BoundingBox)> {
+    let rtree = build_rtree(world);
+    let mut selected: Vec<_> = shapes_in_box(world, &rtree, bounds)
+        .into_iter()
+        .map(|entity| SelectedShape::new(world, entity).unwrap())
+        .map(|shape| (shape.cell_name, shape.world_bounds))
+        .collect();
+    selected.sort_by(|a, b| a.1.min_x.total_cmp(&b.1.min_x));
+    selected
+}
+
+#[test]
+fn boxes_must_reach_the_polygon_not_just_its_bounds() {
+    let mut world = load_pads_and_triangle();
+    let triangle = ("top".to_string(), bounds(200.0, 0.0, 300.0, 100.0));
+
+    // Inside the triangle's bounding box, but past its hypotenuse.
+    assert!(select(&mut world, &bounds(280.0, 80.0, 290.0, 90.0)).is_empty());
+    assert_eq!(
+        select(&mut world, &bounds(210.0, 10.0, 220.0, 20.0)),
+        vec![triangle]
+    );
+
+    // Touching an edge is enough.
+    assert_eq!(
+        select(&mut world, &bounds(10.0, 0.0, 20.0, 5.0)),
+        vec![("pad".to_string(), bounds(0.0, 0.0, 10.0, 10.0))]
+    );
+    assert!(select(&mut world, &bounds(10.5, 0.0, 20.0, 5.0)).is_empty());
+    assert!(select(&mut world, &BoundingBox::new()).is_empty());
+}
+
+#[test]
+fn each_instance_is_selected_on_its_own() {
+    let mut world = load_pads_and_triangle();
+    let rtree = build_rtree(&mut world);
+    let shapes: Vec<Entity> = shapes_in_box(&world, &rtree, &bounds(-1.0, -1.0, 105.0, 5.0));
+    assert_eq!(shapes.len(), 2);
+    assert_ne!(shapes[0], shapes[1]);
+    assert_eq!(
+        select(&mut world, &bounds(-1.0, -1.0, 105.0, 5.0)),
+        vec![
+            ("pad".to_string(), bounds(0.0, 0.0, 10.0, 10.0)),
+            ("pad".to_string(), bounds(100.0, 0.0, 110.0, 10.0)),
+        ]
+    );
+    assert_eq!(
+        select(&mut world, &bounds(50.0, -1.0, 105.0, 5.0)),
+        vec![("pad".to_string(), bounds(100.0, 0.0, 110.0, 10.0))]
+    );
+}
+
+#[test]
+fn hidden_layers_and_cells_are_skipped() {
+    let mut world = load_pads_and_triangle();
+    let everything = bounds(-1.0, -1.0, 301.0, 101.0);
+    assert_eq!(select(&mut world, &everything).len(), 3);
+
+    let mut query = world.query::<&mut Layer>();
+    for mut layer in query.iter_mut(&mut world) {
+        layer.visible = layer.key != LayerKey::new(2, 0);
+    }
+    let selected = select(&mut world, &everything);
+    assert_eq!(selected.len(), 2);
+    assert!(selected.iter().all(|(name, _)| name == "pad"));
+
+    for mut layer in query.iter_mut(&mut world) {
+        layer.visible = true;
+    }
+    let pad = RootFinder::new(&mut world)
+        .find_cell(&world, "pad")
+        .unwrap();
+    set_cell_display(&mut world, pad, CellDisplay::Hidden);
+    assert_eq!(
+        select(&mut world, &everything),
+        vec![("top".to_string(), bounds(200.0, 0.0, 300.0, 100.0))]
+    );
+}
+
+#[test]
+fn export_escapes_cell_names_and_converts_to_microns() {
+    let shape = |cell_name: &str, world_bounds: BoundingBox| SelectedShape {
+        shape_instance: Entity::PLACEHOLDER,
+        layer_key: LayerKey::new(3, 7),
+        cell_name: cell_name.to_string(),
+        world_bounds,
+    };
+    let shapes = vec![
+        shape("plain", bounds(-500.0, 0.0, 1500.0, 250.0)),
+        shape("a,\"b\"", bounds(0.0, 0.0, 1000.0, 1000.0)),
+        shape("two\nlines", bounds(0.0, 0.0, 1000.0, 1000.0)),
+    ];
+
+    let csv = export_csv(&shapes, &LibraryUnits::new(1e-9, 1e-3));
+    assert_eq!(
+        csv,
+        "layer,datatype,cell,min_x,min_y,max_x,max_y\n\
+         3,7,plain,-0.5,0,1.5,0.25\n\
+         3,7,\"a,\"\"b\"\"\",0,0,1,1\n\
+         3,7,\"two\nlines\",0,0,1,1\n"
+    );
+    assert_eq!(
+        export_csv(&[], &LibraryUnits::default()),
+        "layer,datatype,cell,min_x,min_y,max_x,max_y\n"
+    );
+}
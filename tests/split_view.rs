@@ -0,0 +1,68 @@
+//! Verifies how the split view divides the window into panes, and how
+//! linked views follow the pan and zoom of the active pane.
+
+use layout_viewer::core::camera_history::CameraView;
+use layout_viewer::core::split_view;
+use layout_viewer::core::split_view::DIVIDER_PX;
+use layout_viewer::graphics::camera::Camera;
+use layout_viewer::graphics::vectors::Point2d;
+use layout_viewer::graphics::vectors::Point3d;
+
+#[test]
+fn panes_share_the_window_between_dividers() {
+    let panes = split_view::pane_rects((801, 600), 2);
+    assert_eq!(panes.len(), 2);
+    assert_eq!((panes[0].left, panes[0].width), (0.0, 399.0));
+    assert_eq!(
+        (panes[1].left, panes[1].width),
+        (399.0 + DIVIDER_PX as f64, 400.0)
+    );
+    assert!(panes
+        .iter()
+        .all(|pane| pane.top == 0.0 && pane.height == 600.0));
+
+    let single = split_view::pane_rects((800, 600), 1);
+    assert_eq!((single[0].left, single[0].width), (0.0, 800.0));
+    assert_eq!(split_view::pane_rects((800, 600), 0).len(), 1);
+
+    assert_eq!(split_view::pane_at(&panes, 0, 0), Some(0));
+    assert_eq!(split_view::pane_at(&panes, 398, 599), Some(0));
+    assert_eq!(split_view::pane_at(&panes, 399, 300), None);
+    assert_eq!(split_view::pane_at(&panes, 401, 300), Some(1));
+    assert_eq!(split_view::pane_at(&panes, 801, 300), None);
+}
+
+#[test]
+fn pane_positions_map_through_their_own_camera() {
+    let panes = split_view::pane_rects((802, 600), 2);
+    let camera = Camera::new(Point3d::new(10.0, 20.0, 0.0), 400.0, 600.0, -1.0, 1.0);
+
+    // The center of the right pane shows the camera position.
+    let center = split_view::screen_to_world(&camera, &panes[1], 602, 300);
+    assert!((center - Point2d::new(10.0, 20.0)).norm() < 1e-9);
+
+    // Screen right follows world +y, and screen down follows world +x.
+    let corner = split_view::screen_to_world(&camera, &panes[1], 802, 600);
+    assert!((corner - Point2d::new(310.0, 220.0)).norm() < 1e-9);
+}
+
+#[test]
+fn linked_views_pan_and_zoom_by_the_same_fraction() {
+    let view = |x: f64, width: f64| CameraView {
+        center: Point2d::new(x, 0.0),
+        width,
+    };
+
+    // The same region follows exactly.
+    let before = view(0.0, 100.0);
+    let after = view(30.0, 50.0);
+    assert_eq!(split_view::follow(before, before, after), after);
+
+    // A view at twice the width pans twice as far.
+    let linked = split_view::follow(view(1000.0, 200.0), before, after);
+    assert_eq!(linked, view(1060.0, 100.0));
+
+    // Degenerate views are left alone.
+    let linked = split_view::follow(view(5.0, 10.0), view(0.0, 0.0), after);
+    assert_eq!(linked, view(5.0, 10.0));
+}
@@ -0,0 +1,110 @@
+//! Verifies GDSII STRANS semantics using the fixtures in assets/gds/test.
+
+mod common;
+
+use bevy_ecs::entity::Entity;
+use geo::BoundingRect;
+use geo::InteriorPoint;
+use layout_viewer::core::components::ShapeInstance;
+use layout_viewer::core::instancer::Instancer;
+use layout_viewer::core::root_finder::RootFinder;
+use layout_viewer::core::rtree::build_rtree;
+use layout_viewer::graphics::bounds::BoundingBox;
+
+/// Loads and instantiates a GDS file, returning the world-space bounding box
+/// of every shape instance as (min_x, min_y, max_x, max_y), sorted.
+fn instance_bounds(gds_content: &[u8]) -> Vec<(f64, f64, f64, f64)> {
+    let mut world = common::load(gds_content);
+    let roots = RootFinder::new(&mut world).find_roots(&world);
+    assert_eq!(roots.len(), 1);
+    Instancer::new(&mut world).select_root(&mut world, roots[0]);
+
+    let mut bounds: Vec<_> = world
+        .query::<&ShapeInstance>()
+        .iter(&world)
+        .map(|shape| {
+            let rect = shape.world_polygon(&world).bounding_rect().unwrap();
+            (rect.min().x, rect.min().y, rect.max().x, rect.max().y)
+        })
+        .collect();
+    bounds.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    bounds
+}
+
+fn assert_bounds_eq(actual: &[(f64, f64, f64, f64)], expected: &[(f64, f64, f64, f64)]) {
+    assert_eq!(actual.len(), expected.len(), "{:?}", actual);
+    for (a, e) in actual.iter().zip(expected) {
+        let close = (a.0 - e.0).abs() < 1e-6
+            && (a.1 - e.1).abs() < 1e-6
+            && (a.2 - e.2).abs() < 1e-6
+            && (a.3 - e.3).abs() < 1e-6;
+        assert!(close, "expected {:?}, got {:?}", expected, actual);
+    }
+}
+
+const TOP_BOUNDS: (f64, f64, f64, f64) = (166.0, -932.0, 437.0, -660.0);
+
+#[test]
+fn rotation() {
+    let bounds = instance_bounds(include_bytes!("../assets/gds/test/SimpleRotation.gds"));
+    assert_bounds_eq(&bounds, &[TOP_BOUNDS, (201.0, -792.0, 242.0, -692.0)]);
+}
+
+#[test]
+fn reflection_and_rotation() {
+    let bounds = instance_bounds(include_bytes!("../assets/gds/test/SimpleBoth.gds"));
+    assert_bounds_eq(&bounds, &[(89.0, -1001.0, 130.0, -901.0), TOP_BOUNDS]);
+}
+
+#[test]
+fn magnification() {
+    let bounds = instance_bounds(include_bytes!("../assets/gds/test/SimpleMagnification.gds"));
+    assert_bounds_eq(&bounds, &[TOP_BOUNDS, (559.0, -693.0, 641.0, -493.0)]);
+}
+
+#[test]
+fn absolute_magnification_and_angle() {
+    // BAR is placed inside FOO with an absolute magnification of 0.5 and an
+    // absolute angle of zero, so the 2x and 90 degree transform of FOO only
+    // affects its position.
+    let bounds = instance_bounds(include_bytes!("../assets/gds/test/SimpleAbsolute.gds"));
+    assert_bounds_eq(
+        &bounds,
+        &[
+            (-157.0, 1109.0, -107.0, 1134.0),
+            TOP_BOUNDS,
+            (559.0, -693.0, 641.0, -493.0),
+        ],
+    );
+}
+
+#[test]
+fn shape_instances_are_transformed_on_demand() {
+    let mut world = common::load(include_bytes!("../assets/gds/test/SimpleBoth.gds"));
+    let root = RootFinder::new(&mut world)
+        .find_default_root(&world)
+        .unwrap();
+    Instancer::new(&mut world).select_root(&mut world, root);
+    let rtree = build_rtree(&mut world);
+
+    let mut query = world.query::<(Entity, &ShapeInstance)>();
+    for (entity, shape) in query.iter(&world) {
+        // The bounds of the local bounding box are exact for reflections
+        // and quarter turns, and index the shape in the R-tree.
+        let polygon = shape.world_polygon(&world);
+        let bounds = shape.world_bounds(&world);
+        assert_eq!(bounds, BoundingBox::from(polygon.bounding_rect().unwrap()));
+        let item = rtree
+            .iter()
+            .find(|item| item.shape_instance == entity)
+            .unwrap();
+        assert_eq!(item.aabb.lower().x(), bounds.min_x);
+        assert_eq!(item.aabb.upper().y(), bounds.max_y);
+
+        // Points are mapped into the shape's own coordinate system.
+        let inside = polygon.interior_point().unwrap();
+        assert!(shape.contains(&world, &inside));
+        let outside = geo::Point::new(bounds.max_x + 1.0, bounds.max_y + 1.0);
+        assert!(!shape.contains(&world, &outside));
+    }
+}
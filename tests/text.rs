@@ -0,0 +1,133 @@
+//! Verifies that TEXT elements are loaded with their PRESENTATION flags,
+//! and that they are written to SVG as aligned labels.
+
+mod common;
+
+use bevy_ecs::world::World;
+use gds21::GdsElement;
+use gds21::GdsLibrary;
+use gds21::GdsPoint;
+use gds21::GdsStruct;
+use gds21::GdsTextElem;
+use layout_viewer::cli::generate_svg::generate_svg;
+use layout_viewer::core::components::TextDefinition;
+use layout_viewer::graphics::text::HorizontalAlign;
+use layout_viewer::graphics::text::VerticalAlign;
+
+const TEXTTYPE_RECORD: u8 = 0x16;
+
+/// Inserts a PRESENTATION record after the TEXTTYPE record of each TEXT
+/// element that has bits given, since gds21 cannot construct one.
+fn with_presentations(gds_content: &[u8], presentations: &[Option<u8>]) -> Vec<u8> {
+    let mut patched = Vec::new();
+    let mut presentations = presentations.iter();
+    let mut offset = 0;
+    while offset + 4 <= gds_content.len() {
+        let length = u16::from_be_bytes([gds_content[offset], gds_content[offset + 1]]) as usize;
+        if length < 4 {
+            break;
+        }
+        patched.extend_from_slice(&gds_content[offset..offset + length]);
+        if gds_content[offset + 2] == TEXTTYPE_RECORD {
+            if let Some(Some(bits)) = presentations.next() {
+                patched.extend_from_slice(&[0x00, 0x06, 0x17, 0x01, 0x00, *bits]);
+            }
+        }
+        offset += length;
+    }
+    patched
+}
+
+/// Loads a cell with one label per entry, each with the given PRESENTATION
+/// bits, if any.
+fn load_labels(labels: &[(&str, Option<u8>)]) -> World {
+    let mut library = GdsLibrary::new("labels");
+    let mut top = GdsStruct::new("top");
+    for (index, (string, _)) in labels.iter().enumerate() {
+        top.elems.push(GdsElement::GdsTextElem(GdsTextElem {
+            string: string.to_string(),
+            layer: 1,
+            texttype: 0,
+            xy: GdsPoint::new(index as i32 * 100, 0),
+            ..Default::default()
+        }));
+    }
+    library.structs.push(top);
+    let presentations: Vec<_> = labels.iter().map(|(_, bits)| *bits).collect();
+    let gds_content = with_presentations(&common::to_bytes(&library), &presentations);
+    common::load_and_instantiate(&gds_content)
+}
+
+fn presentation_of(world: &mut World, string: &str) -> (u8, HorizontalAlign, VerticalAlign) {
+    let mut query = world.query::<&TextDefinition>();
+    let text_def = query
+        .iter(world)
+        .find(|text_def| text_def.string == string)
+        .unwrap();
+    let presentation = text_def.presentation;
+    (
+        presentation.font,
+        presentation.horizontal,
+        presentation.vertical,
+    )
+}
+
+#[test]
+fn presentation_bits_set_font_and_alignment() {
+    let mut world = load_labels(&[
+        ("plain", None),
+        ("centered", Some(0b00_00_01)),
+        ("corner", Some(0b10_10_10)),
+        ("middle", Some(0b01_01_00)),
+    ]);
+    assert_eq!(
+        presentation_of(&mut world, "plain"),
+        (0, HorizontalAlign::Left, VerticalAlign::Top)
+    );
+    assert_eq!(
+        presentation_of(&mut world, "centered"),
+        (0, HorizontalAlign::Center, VerticalAlign::Top)
+    );
+    assert_eq!(
+        presentation_of(&mut world, "corner"),
+        (2, HorizontalAlign::Right, VerticalAlign::Bottom)
+    );
+    assert_eq!(
+        presentation_of(&mut world, "middle"),
+        (1, HorizontalAlign::Left, VerticalAlign::Middle)
+    );
+}
+
+#[test]
+fn ringo_labels_are_centered() {
+    let mut world = common::load_ringo();
+    let mut query = world.query::<&TextDefinition>();
+    let text_defs: Vec<_> = query.iter(&world).collect();
+    assert_eq!(text_defs.len(), 16);
+    for text_def in text_defs {
+        assert!(!text_def.string.is_empty());
+        assert_eq!(text_def.presentation.horizontal, HorizontalAlign::Center);
+        assert_eq!(text_def.presentation.vertical, VerticalAlign::Middle);
+    }
+}
+
+#[test]
+fn svg_labels_follow_the_presentation() {
+    let mut world = load_labels(&[("a<b", None), ("right", Some(0b00_10_10))]);
+    let svg = generate_svg(&mut world, false);
+    let labels: Vec<_> = svg.split("<text").skip(1).collect();
+    assert_eq!(labels.len(), 2);
+
+    let escaped = labels
+        .iter()
+        .find(|label| label.contains("a&lt;b"))
+        .unwrap();
+    assert!(escaped.contains(r#"text-anchor="start""#));
+    assert!(escaped.contains(r#"dominant-baseline="hanging""#));
+
+    // Label anchors are at world y = 0, which is SVG x.
+    let right = labels.iter().find(|label| label.contains("right")).unwrap();
+    assert!(right.contains(r#"text-anchor="end""#));
+    assert!(right.contains(r#"dominant-baseline="auto""#));
+    assert!(right.contains(r#"x="0" y="100""#));
+}
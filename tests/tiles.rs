@@ -0,0 +1,227 @@
+//! Verifies that layer geometry is split into tiles that fit the vertex
+//! budget, and that tile bounds follow the instances of their cell.
+
+mod common;
+
+use gds21::GdsArrayRef;
+use gds21::GdsBoundary;
+use gds21::GdsElement;
+use gds21::GdsLibrary;
+use gds21::GdsPoint;
+use gds21::GdsStruct;
+use gds21::GdsStructRef;
+use layout_viewer::core::components::LayerMesh;
+use layout_viewer::core::tiles::partition;
+use layout_viewer::core::tiles::TileItem;
+use layout_viewer::graphics::bounds::BoundingBox;
+use layout_viewer::graphics::geometry::Geometry;
+use layout_viewer::graphics::mesh::Mesh;
+
+fn square(x: f64, y: f64) -> TileItem {
+    TileItem {
+        bounds: BoundingBox {
+            min_x: x,
+            min_y: y,
+            max_x: x + 1.0,
+            max_y: y + 1.0,
+        },
+        vertex_count: 4,
+    }
+}
+
+#[test]
+fn grid_is_split_within_budget() {
+    let mut items = Vec::new();
+    for row in 0..32 {
+        for col in 0..32 {
+            items.push(square(col as f64 * 10.0, row as f64 * 10.0));
+        }
+    }
+
+    let tiles = partition(&items, 64);
+    assert!(tiles.len() >= 64);
+
+    let mut seen = vec![false; items.len()];
+    for tile in &tiles {
+        assert!(tile.vertex_count <= 64);
+        for index in &tile.items {
+            assert!(!seen[*index]);
+            seen[*index] = true;
+            let bounds = items[*index].bounds;
+            assert!(tile.bounds.min_x <= bounds.min_x && bounds.max_x <= tile.bounds.max_x);
+            assert!(tile.bounds.min_y <= bounds.min_y && bounds.max_y <= tile.bounds.max_y);
+        }
+    }
+    assert!(seen.iter().all(|seen| *seen));
+}
+
+#[test]
+fn coincident_shapes_are_chunked() {
+    let items = vec![square(0.0, 0.0); 100];
+    let tiles = partition(&items, 40);
+    assert_eq!(tiles.len(), 10);
+    assert!(tiles.iter().all(|tile| tile.vertex_count <= 40));
+}
+
+#[test]
+fn oversized_shape_gets_its_own_tile() {
+    let mut items = vec![square(0.0, 0.0), square(5.0, 5.0)];
+    items[0].vertex_count = 1000;
+    let tiles = partition(&items, 64);
+    assert_eq!(tiles.len(), 2);
+}
+
+#[test]
+fn intersects() {
+    let a = square(0.0, 0.0).bounds;
+    assert!(a.intersects(&square(0.5, 0.5).bounds));
+    assert!(!a.intersects(&square(2.0, 0.0).bounds));
+    assert!(!a.intersects(&BoundingBox::new()));
+}
+
+#[test]
+fn tile_bounds_cover_instances() {
+    let mut world = common::load_ringo();
+
+    let mut query = world.query::<(&Mesh, &LayerMesh)>();
+    for (mesh, layer_mesh) in query.iter(&world) {
+        let geometry = world.get::<Geometry>(mesh.geometry).unwrap();
+        let bounds = mesh.bounds.unwrap();
+        if geometry.instance_count() == 0 {
+            assert!(bounds.is_empty());
+            continue;
+        }
+        for group in geometry.instance_groups() {
+            assert!(contains(&bounds, &group.bounds));
+            for row in group.instance_data().chunks(6) {
+                let instance = geo::AffineTransform::new(
+                    row[0] as f64,
+                    row[1] as f64,
+                    row[2] as f64,
+                    row[3] as f64,
+                    row[4] as f64,
+                    row[5] as f64,
+                );
+
+                // Instances place vertices relative to the tile origin, and
+                // are themselves relative to the group origin.
+                let tile_origin = layer_mesh.origin;
+                let transform = geo::AffineTransform::translate(-tile_origin.x, -tile_origin.y)
+                    .compose(&instance)
+                    .compose(&geo::AffineTransform::translate(
+                        group.origin.x,
+                        group.origin.y,
+                    ));
+                let instance = layer_mesh.local_bounds.transform(&transform);
+                assert!(contains(&group.bounds, &instance));
+            }
+        }
+    }
+}
+
+#[test]
+fn distant_instances_are_grouped_apart() {
+    let mut library = GdsLibrary::new("spread");
+
+    let mut leaf = GdsStruct::new("leaf");
+    leaf.elems.push(GdsElement::GdsBoundary(GdsBoundary {
+        layer: 1,
+        datatype: 0,
+        xy: GdsPoint::vec(&[(0, 0), (10, 0), (10, 10), (0, 10), (0, 0)]),
+        ..Default::default()
+    }));
+    library.structs.push(leaf);
+
+    // Two neighbors near the origin, and one at the far corner of a 10 mm
+    // die.
+    let mut top = GdsStruct::new("top");
+    for (x, y) in [(0, 0), (20, 0), (10_000_000, 10_000_000)] {
+        top.elems.push(GdsElement::GdsStructRef(GdsStructRef {
+            name: "leaf".to_string(),
+            xy: GdsPoint::new(x, y),
+            ..Default::default()
+        }));
+    }
+    library.structs.push(top);
+
+    let mut world = common::load_and_instantiate(&common::to_bytes(&library));
+    let mut query = world.query::<(&Mesh, &LayerMesh)>();
+    let view = BoundingBox {
+        min_x: -100.0,
+        min_y: -100.0,
+        max_x: 100.0,
+        max_y: 100.0,
+    };
+    let mut checked = 0;
+    for (mesh, _) in query.iter(&world) {
+        let geometry = world.get::<Geometry>(mesh.geometry).unwrap();
+        if geometry.instance_count() == 0 {
+            continue;
+        }
+
+        // The tile as a whole spans the die, so only its groups can be
+        // culled.
+        assert!(mesh.bounds.unwrap().intersects(&view));
+        let groups = geometry.instance_groups();
+        assert_eq!(groups.len(), 2);
+        let visible: Vec<_> = groups
+            .iter()
+            .filter(|group| group.bounds.intersects(&view))
+            .collect();
+        assert_eq!(visible.len(), 1);
+        assert_eq!(visible[0].instance_count(), 2);
+        assert!(visible[0].bounds.max_x <= 30.0 && visible[0].bounds.max_y <= 30.0);
+        checked += 1;
+    }
+    assert!(checked > 0);
+}
+
+#[test]
+fn arrays_across_the_die_make_few_groups() {
+    let mut library = GdsLibrary::new("array");
+
+    let mut leaf = GdsStruct::new("leaf");
+    leaf.elems.push(GdsElement::GdsBoundary(GdsBoundary {
+        layer: 1,
+        datatype: 0,
+        xy: GdsPoint::vec(&[(0, 0), (10, 0), (10, 10), (0, 10), (0, 0)]),
+        ..Default::default()
+    }));
+    library.structs.push(leaf);
+
+    // 100 x 100 instances spread over a 10 mm die.
+    let mut top = GdsStruct::new("top");
+    top.elems.push(GdsElement::GdsArrayRef(GdsArrayRef {
+        name: "leaf".to_string(),
+        xy: [
+            GdsPoint::new(0, 0),
+            GdsPoint::new(10_000_000, 0),
+            GdsPoint::new(0, 10_000_000),
+        ],
+        cols: 100,
+        rows: 100,
+        ..Default::default()
+    }));
+    library.structs.push(top);
+
+    let mut world = common::load_and_instantiate(&common::to_bytes(&library));
+    let mut query = world.query::<(&Mesh, &LayerMesh)>();
+    let mut checked = 0;
+    for (mesh, _) in query.iter(&world) {
+        let geometry = world.get::<Geometry>(mesh.geometry).unwrap();
+        if geometry.instance_count() == 0 {
+            continue;
+        }
+        assert_eq!(geometry.instance_count(), 10_000);
+        assert_eq!(geometry.instance_groups().len(), 100);
+        checked += 1;
+    }
+    assert!(checked > 0);
+}
+
+fn contains(outer: &BoundingBox, inner: &BoundingBox) -> bool {
+    outer.min_x <= inner.min_x + 1e-3
+        && outer.max_x >= inner.max_x - 1e-3
+        && outer.min_y <= inner.min_y + 1e-3
+        && outer.max_y >= inner.max_y - 1e-3
+}
@@ -0,0 +1,49 @@
+//! Verifies that library units are carried from the GDS header into the World.
+
+use bevy_ecs::world::World;
+use gds21::GdsLibrary;
+use gds21::GdsStruct;
+use gds21::GdsUnits;
+use layout_viewer::core::loader::Loader;
+use layout_viewer::core::units::LibraryUnits;
+
+fn load_units(units: GdsUnits) -> LibraryUnits {
+    let mut library = GdsLibrary::new("units");
+    library.units = units;
+    library.structs.push(GdsStruct::new("top"));
+    let mut gds_content = Vec::new();
+    library.write(&mut gds_content).unwrap();
+
+    let mut world = None;
+    for progress in Loader::new(&gds_content) {
+        world = progress.unwrap().take_world();
+    }
+    let world: World = world.expect("World was not yielded");
+    *world.get_resource::<LibraryUnits>().unwrap()
+}
+
+fn assert_close(actual: f64, expected: f64) {
+    assert!(
+        (actual - expected).abs() <= expected.abs() * 1e-9,
+        "expected {}, got {}",
+        expected,
+        actual
+    );
+}
+
+#[test]
+fn default_units() {
+    let units = load_units(GdsUnits::default());
+    assert_close(units.db_unit, 1e-9);
+    assert_close(units.db_unit_in_user_units, 1e-3);
+    assert_close(units.user_unit(), 1e-6);
+    assert_close(units.to_microns(2500.0), 2.5);
+    assert_close(units.from_microns(2.5), 2500.0);
+}
+
+#[test]
+fn angstrom_units() {
+    let units = load_units(GdsUnits::new(1e-4, 1e-10));
+    assert_close(units.microns_per_db_unit(), 1e-4);
+    assert_close(units.user_unit(), 1e-6);
+}
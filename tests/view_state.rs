@@ -0,0 +1,114 @@
+//! Verifies that view states survive a round trip through URL query
+//! parameters, and that malformed parameters are skipped.
+
+use layout_viewer::core::components::LayerKey;
+use layout_viewer::core::instancer::InstanceLimits;
+use layout_viewer::core::view_state::ViewState;
+
+fn query(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+    pairs
+        .iter()
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect()
+}
+
+#[test]
+fn view_state_round_trips_through_query() {
+    let state = ViewState {
+        center: Some((12.5, -3.25)),
+        width: Some(40.0),
+        cell: Some("RINGO".to_string()),
+        hidden_layers: vec![LayerKey::new(1, 0), LayerKey::new(17, 5)],
+        layer_colors: vec![(LayerKey::new(2, 0), "ff8000".to_string())],
+        selected: Some((LayerKey::new(3, 0), (1.5, 2.0))),
+    };
+    let query = state.to_query();
+    assert_eq!(
+        query,
+        self::query(&[
+            ("x", "12.5"),
+            ("y", "-3.25"),
+            ("w", "40"),
+            ("cell", "RINGO"),
+            ("hide", "1/0,17/5"),
+            ("color", "2/0:ff8000"),
+            ("sel", "3/0:1.5:2"),
+        ])
+    );
+    assert_eq!(ViewState::from_query(&query), state);
+    assert!(ViewState::default().to_query().is_empty());
+}
+
+#[test]
+fn updating_the_query_keeps_other_parameters() {
+    let url = query(&[
+        ("max_depth", "8"),
+        ("x", "1"),
+        ("hide", "4/0"),
+        ("max_instances", "500"),
+        ("theme", "dark"),
+    ]);
+    let state = ViewState {
+        center: Some((2.0, 3.0)),
+        width: Some(10.0),
+        cell: Some("TOP".to_string()),
+        ..ViewState::default()
+    };
+    let updated = state.update_query(&url);
+    assert_eq!(
+        updated,
+        query(&[
+            ("max_depth", "8"),
+            ("max_instances", "500"),
+            ("theme", "dark"),
+            ("x", "2"),
+            ("y", "3"),
+            ("w", "10"),
+            ("cell", "TOP"),
+        ])
+    );
+    assert_eq!(ViewState::from_query(&updated), state);
+    assert_eq!(
+        InstanceLimits::from_query(&updated),
+        InstanceLimits {
+            max_depth: 8,
+            max_instances: 500,
+        }
+    );
+    assert_eq!(state.update_query(&updated), updated);
+}
+
+#[test]
+fn malformed_parameters_are_ignored() {
+    let state = ViewState::from_query(&query(&[
+        ("x", "abc"),
+        ("y", "2"),
+        ("w", "-5"),
+        ("cell", ""),
+        ("hide", "1/0,bogus,2"),
+        ("color", "1/0:zzzzzz,2/0:00FF00,3/0"),
+        ("sel", "1/0:4"),
+        ("unknown", "1"),
+    ]));
+    assert_eq!(state.center, None);
+    assert_eq!(state.width, None);
+    assert_eq!(state.cell, None);
+    assert_eq!(state.hidden_layers, vec![LayerKey::new(1, 0)]);
+    assert_eq!(
+        state.layer_colors,
+        vec![(LayerKey::new(2, 0), "00ff00".to_string())]
+    );
+    assert_eq!(state.selected, None);
+}
+
+#[test]
+fn layer_keys_parse_from_strings() {
+    assert_eq!("5/2".parse::<LayerKey>(), Ok(LayerKey::new(5, 2)));
+    assert_eq!(" 7 / 0 ".parse::<LayerKey>(), Ok(LayerKey::new(7, 0)));
+    assert!("5".parse::<LayerKey>().is_err());
+    assert!("a/b".parse::<LayerKey>().is_err());
+    assert_eq!(
+        LayerKey::new(5, 2).to_string().parse(),
+        Ok(LayerKey::new(5, 2))
+    );
+}